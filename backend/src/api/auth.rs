@@ -0,0 +1,56 @@
+use rocket::{
+    Request,
+    http::Status,
+    request::{FromRequest, Outcome},
+};
+
+/// Session token of a game sent as `Authorization: Bearer <token>`, which proves ownership of
+/// the game since only whoever created it knows the token. Requests without one are rejected
+/// with `401 Unauthorized`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionToken(pub String);
+
+impl SessionToken {
+    fn from_header(value: &str) -> Option<Self> {
+        let (scheme, token) = value.split_once(' ')?;
+        let token = token.trim();
+        (scheme.eq_ignore_ascii_case("bearer") && !token.is_empty())
+            .then(|| SessionToken(token.to_string()))
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for SessionToken {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        match request
+            .headers()
+            .get_one("Authorization")
+            .and_then(SessionToken::from_header)
+        {
+            Some(token) => Outcome::Success(token),
+            None => Outcome::Error((Status::Unauthorized, ())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SessionToken;
+
+    #[test]
+    fn test_from_header() {
+        assert_eq!(
+            SessionToken::from_header("Bearer abc123"),
+            Some(SessionToken("abc123".to_string()))
+        );
+        assert_eq!(
+            SessionToken::from_header("bearer  abc123 "),
+            Some(SessionToken("abc123".to_string()))
+        );
+        assert_eq!(SessionToken::from_header("Basic abc123"), None);
+        assert_eq!(SessionToken::from_header("Bearer "), None);
+        assert_eq!(SessionToken::from_header("abc123"), None);
+    }
+}
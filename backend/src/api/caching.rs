@@ -0,0 +1,207 @@
+use chrono::{DateTime, Utc};
+use rocket::{
+    Request, Response,
+    http::{Header, Status},
+    request::{FromRequest, Outcome},
+    response::{self, Responder},
+};
+
+use crate::service::game_cache::GameStateVersion;
+
+/// Format of the `Last-Modified` and `If-Modified-Since` headers (RFC 9110 IMF-fixdate)
+const HTTP_DATE: &str = "%a, %d %b %Y %H:%M:%S GMT";
+
+/// Validators sent along with a conditional GET
+pub struct Conditional {
+    if_none_match: Option<String>,
+    if_modified_since: Option<DateTime<Utc>>,
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Conditional {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let headers = request.headers();
+        Outcome::Success(Conditional {
+            if_none_match: headers.get_one("If-None-Match").map(str::to_string),
+            // Unparseable dates are ignored, as required for If-Modified-Since
+            if_modified_since: headers
+                .get_one("If-Modified-Since")
+                .and_then(|date| DateTime::parse_from_rfc2822(date).ok())
+                .map(|date| date.with_timezone(&Utc)),
+        })
+    }
+}
+
+impl Conditional {
+    /// Whether the client's copy is still current. `If-None-Match` takes precedence over
+    /// `If-Modified-Since`, which only has second precision.
+    fn is_current(&self, version: &GameStateVersion) -> bool {
+        if let Some(if_none_match) = &self.if_none_match {
+            let etag = version.etag();
+            return if_none_match
+                .split(',')
+                .map(|tag| tag.trim().trim_start_matches("W/"))
+                .any(|tag| tag == "*" || tag == etag);
+        }
+        self.if_modified_since
+            .is_some_and(|since| version.last_modified().timestamp() <= since.timestamp())
+    }
+
+    /// Respond with [body] unless the client's copy of [version] is current
+    pub fn respond<R>(&self, version: &GameStateVersion, body: impl FnOnce() -> R) -> Cached<R> {
+        Cached {
+            etag: version.etag(),
+            last_modified: version.last_modified(),
+            body: (!self.is_current(version)).then(body),
+        }
+    }
+}
+
+/// A response carrying `ETag` and `Last-Modified`, or `304 Not Modified` without a body
+pub struct Cached<R> {
+    etag: String,
+    last_modified: DateTime<Utc>,
+    body: Option<R>,
+}
+
+impl<'r, R: Responder<'r, 'static>> Responder<'r, 'static> for Cached<R> {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        let mut response = match self.body {
+            Some(body) => body.respond_to(request)?,
+            None => Response::build().status(Status::NotModified).finalize(),
+        };
+        response.set_header(Header::new("ETag", self.etag));
+        response.set_header(Header::new(
+            "Last-Modified",
+            self.last_modified.format(HTTP_DATE).to_string(),
+        ));
+        // Clients may store the response but have to revalidate it before every use
+        response.set_header(Header::new("Cache-Control", "no-cache"));
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rocket::{
+        http::{Header, Status},
+        local::blocking::Client,
+    };
+
+    use crate::{
+        config::AppConfig,
+        db::test_pool,
+        repository::{GameRepository, LeaderboardRepository},
+        service::aoc_utils::AocUtils,
+    };
+
+    const DATA: &str = r#"{"event":"2024","day1_ts":1733029200,"owner_id":1,"num_days":25,"members":{"1":{"id":1,"local_score":0,"name":null,"last_star_ts":0,"stars":0,"completion_day_level":{}}}}"#;
+
+    #[test]
+    fn test_conditional_get() {
+        let pool = test_pool();
+        {
+            let conn = pool.get().unwrap();
+            GameRepository::new()
+                .create_game(&conn, "abcd1234", 42, "token", &Default::default())
+                .unwrap();
+            for year in AocUtils::event_years() {
+                LeaderboardRepository::new()
+                    .save_leaderboard(&conn, year, 42, DATA)
+                    .unwrap();
+            }
+        }
+        let client = Client::tracked(crate::api::build(pool, AppConfig::default())).unwrap();
+
+        let response = client.get("/v1/game/abcd1234/completion").dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let etag = response.headers().get_one("ETag").unwrap().to_string();
+        let last_modified = response
+            .headers()
+            .get_one("Last-Modified")
+            .unwrap()
+            .to_string();
+
+        let response = client
+            .get("/v1/game/abcd1234/completion")
+            .header(Header::new("If-None-Match", etag.clone()))
+            .dispatch();
+        assert_eq!(response.status(), Status::NotModified);
+        assert_eq!(response.headers().get_one("ETag"), Some(etag.as_str()));
+        assert!(response.into_string().is_none());
+
+        let response = client
+            .get("/v1/game/abcd1234/completion")
+            .header(Header::new("If-Modified-Since", last_modified))
+            .dispatch();
+        assert_eq!(response.status(), Status::NotModified);
+
+        // The validator is shared by every view of the same game state
+        let response = client
+            .get("/v1/game/abcd1234/puzzles/all")
+            .header(Header::new("If-None-Match", format!("\"other\", {}", etag)))
+            .dispatch();
+        assert_eq!(response.status(), Status::NotModified);
+
+        let response = client
+            .get("/v1/game/abcd1234/completion")
+            .header(Header::new("If-None-Match", "\"other\""))
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+    }
+
+    #[test]
+    fn test_member_removed() {
+        let pool = test_pool();
+        {
+            // Inserted directly to date everything an hour back, so that the removal is later
+            let conn = pool.get().unwrap();
+            conn.execute_batch(
+                "INSERT INTO games (id, leaderboard_id, session_token, created_at, updated_at,
+                                    starts_at)
+                 VALUES ('abcd1234', 42, 'token', unixepoch() - 3600, unixepoch() - 3600,
+                         unixepoch() - 3600);
+                 INSERT INTO game_memberships (game_id, member_id, member_name, created_at)
+                 VALUES ('abcd1234', 1, 'Alice', unixepoch() - 3600),
+                        ('abcd1234', 2, 'Bob', unixepoch() - 1800);",
+            )
+            .unwrap();
+            for year in AocUtils::event_years() {
+                conn.execute(
+                    "INSERT INTO leaderboard_cache (year, leaderboard_id, data, updated_at)
+                     VALUES (?1, 42, ?2, unixepoch() - 3600);",
+                    rusqlite::params![year as i64, DATA],
+                )
+                .unwrap();
+            }
+        }
+        let client = Client::tracked(crate::api::build(pool, AppConfig::default())).unwrap();
+
+        let response = client.get("/v1/game/abcd1234/completion").dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let last_modified = response
+            .headers()
+            .get_one("Last-Modified")
+            .unwrap()
+            .to_string();
+
+        // Bob joined last, without touching the game their removal would move Last-Modified back
+        let response = client
+            .delete("/v1/game/abcd1234/members/2")
+            .header(Header::new("Authorization", "Bearer token"))
+            .dispatch();
+        assert_eq!(response.status(), Status::NoContent);
+
+        let response = client
+            .get("/v1/game/abcd1234/completion")
+            .header(Header::new("If-Modified-Since", last_modified.clone()))
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        assert_ne!(
+            response.headers().get_one("Last-Modified"),
+            Some(last_modified.as_str())
+        );
+    }
+}
@@ -0,0 +1,337 @@
+use rocket::{
+    Request, Response, catch,
+    http::Status,
+    response::{self, Responder},
+    serde::json::Json,
+};
+use serde_json::json;
+
+use crate::{
+    db::DbError,
+    model::series::SeriesError,
+    service::{
+        game::{GameError, GameMembershipError},
+        invite::InviteError,
+        leaderboard::{BingoError, LeaderboardError},
+        webhook::WebhookError,
+    },
+};
+
+/// Error body of every failed API request: `{"code": .., "message": .., "details": ..}`.
+///
+/// `code` is stable and meant for programs, `message` is meant for humans and may change.
+/// Internal failures are logged and only reported with a generic message.
+#[derive(Debug, serde::Serialize, utoipa::ToSchema)]
+pub struct ApiError {
+    #[serde(skip)]
+    pub status: Status,
+    pub code: &'static str,
+    pub message: String,
+    pub details: Option<serde_json::Value>,
+}
+
+impl ApiError {
+    pub fn new(status: Status, code: &'static str, message: impl Into<String>) -> Self {
+        ApiError {
+            status,
+            code,
+            message: message.into(),
+            details: None,
+        }
+    }
+
+    pub fn with_details(mut self, details: serde_json::Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+
+    pub fn bad_request(code: &'static str, message: impl Into<String>) -> Self {
+        Self::new(Status::BadRequest, code, message)
+    }
+
+    /// Log [cause] and report a failure without any internals
+    pub fn internal(code: &'static str, message: &str, cause: impl std::fmt::Display) -> Self {
+        tracing::error!(code, error = %cause, "{}", message);
+        Self::new(Status::InternalServerError, code, message)
+    }
+
+    fn database(cause: impl std::fmt::Display) -> Self {
+        Self::internal("database_error", "Internal database error", cause)
+    }
+
+    fn invalid_data(cause: impl std::fmt::Display) -> Self {
+        Self::internal("invalid_data", "Stored data could not be read", cause)
+    }
+
+    fn not_game_owner(game_id: &str) -> Self {
+        Self::new(
+            Status::Forbidden,
+            "not_game_owner",
+            "The session token does not belong to this game",
+        )
+        .with_details(json!({ "game_id": game_id }))
+    }
+
+    fn game_not_found(game_id: &str) -> Self {
+        Self::new(Status::NotFound, "game_not_found", "Game not found")
+            .with_details(json!({ "game_id": game_id }))
+    }
+}
+
+impl<'r> Responder<'r, 'static> for ApiError {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        let status = self.status;
+        Response::build_from(Json(self).respond_to(request)?)
+            .status(status)
+            .ok()
+    }
+}
+
+impl From<r2d2::Error> for ApiError {
+    fn from(err: r2d2::Error) -> Self {
+        ApiError::database(err)
+    }
+}
+
+impl From<DbError> for ApiError {
+    fn from(err: DbError) -> Self {
+        ApiError::database(err)
+    }
+}
+
+impl From<GameError> for ApiError {
+    fn from(err: GameError) -> Self {
+        match err {
+            GameError::DatabaseError(_) => ApiError::database(err),
+            GameError::InvalidData(_) => ApiError::invalid_data(err),
+            GameError::IdGenerationFailed(_) => {
+                ApiError::internal("id_generation_failed", "Failed to generate a game ID", err)
+            }
+            GameError::NotFound(id) => ApiError::game_not_found(&id),
+            GameError::InvalidSchedule(message) => {
+                ApiError::bad_request("invalid_schedule", message)
+            }
+            GameError::InvalidField { field, message } => {
+                ApiError::bad_request("invalid_field", format!("{} {}", field, message))
+                    .with_details(json!({ "field": field }))
+            }
+            GameError::NotOwner(id) => ApiError::not_game_owner(&id),
+            GameError::NotFinished(id) => ApiError::new(
+                Status::Conflict,
+                "game_not_finished",
+                "The game has not finished yet",
+            )
+            .with_details(json!({ "game_id": id })),
+            GameError::LeaderboardNotFound => ApiError::new(
+                Status::NotFound,
+                "leaderboard_not_found",
+                "Leaderboard of the game could not be loaded",
+            ),
+            GameError::Bingo(e) => e.into(),
+        }
+    }
+}
+
+impl From<GameMembershipError> for ApiError {
+    fn from(err: GameMembershipError) -> Self {
+        match err {
+            GameMembershipError::DatabaseError(_) => ApiError::database(err),
+            GameMembershipError::InvalidData(_) => ApiError::invalid_data(err),
+            GameMembershipError::NotFound(id) => ApiError::new(
+                Status::NotFound,
+                "membership_not_found",
+                "Membership not found",
+            )
+            .with_details(json!({ "membership_id": id })),
+            GameMembershipError::GameNotFound(id) => ApiError::game_not_found(&id),
+            GameMembershipError::NotOwner(id) => ApiError::not_game_owner(&id),
+            GameMembershipError::AlreadyMember(id) => ApiError::new(
+                Status::Conflict,
+                "already_member",
+                "Member already joined the game",
+            )
+            .with_details(json!({ "member_id": id })),
+            GameMembershipError::NotOnLeaderboard(id) => ApiError::new(
+                Status::UnprocessableEntity,
+                "not_on_leaderboard",
+                "Member is not on the game's leaderboard",
+            )
+            .with_details(json!({ "member_id": id })),
+        }
+    }
+}
+
+impl From<LeaderboardError> for ApiError {
+    fn from(err: LeaderboardError) -> Self {
+        match err {
+            LeaderboardError::NotCached => ApiError::bad_request(
+                "leaderboard_not_cached",
+                "Leaderboard is not cached and no session token was provided",
+            ),
+            LeaderboardError::DatabaseError(_) => ApiError::database(err),
+            LeaderboardError::InvalidData(_) => ApiError::invalid_data(err),
+            LeaderboardError::InvalidSession => ApiError::new(
+                Status::Unauthorized,
+                "invalid_session",
+                "Advent of Code rejected the session token",
+            ),
+            LeaderboardError::FetchError(_) | LeaderboardError::ParseError(_) => {
+                tracing::warn!(error = %err, "Failed to load leaderboard from AoC");
+                ApiError::new(
+                    Status::BadGateway,
+                    "upstream_error",
+                    "Failed to load the leaderboard from Advent of Code",
+                )
+            }
+        }
+    }
+}
+
+impl From<BingoError> for ApiError {
+    fn from(err: BingoError) -> Self {
+        match err {
+            BingoError::NoOptions => ApiError::new(
+                Status::UnprocessableEntity,
+                "no_bingo_options",
+                "No puzzles are available for a bingo card",
+            ),
+            BingoError::LeaderboardError(e) => e.into(),
+        }
+    }
+}
+
+impl From<InviteError> for ApiError {
+    fn from(err: InviteError) -> Self {
+        match err {
+            InviteError::DatabaseError(_) => ApiError::database(err),
+            InviteError::Game(e) => e.into(),
+            InviteError::Membership(e) => e.into(),
+            InviteError::NotFound(_) => {
+                ApiError::new(Status::NotFound, "invite_not_found", "Invite not found")
+            }
+            InviteError::InvalidOptions(message) => {
+                ApiError::bad_request("invalid_invite", message)
+            }
+            InviteError::Expired => ApiError::new(Status::Gone, "invite_expired", "Invite expired"),
+            InviteError::UsedUp => {
+                ApiError::new(Status::Gone, "invite_used_up", "Invite has no uses left")
+            }
+            InviteError::AlreadyRequested(id) => ApiError::new(
+                Status::Conflict,
+                "approval_pending",
+                "Member already awaits approval to join the game",
+            )
+            .with_details(json!({ "member_id": id })),
+            InviteError::RedemptionNotFound(id) => ApiError::new(
+                Status::NotFound,
+                "redemption_not_found",
+                "Redemption not found",
+            )
+            .with_details(json!({ "redemption_id": id })),
+            InviteError::AlreadyDecided(id) => ApiError::new(
+                Status::Conflict,
+                "redemption_decided",
+                "Redemption was already approved or rejected",
+            )
+            .with_details(json!({ "redemption_id": id })),
+        }
+    }
+}
+
+impl From<WebhookError> for ApiError {
+    fn from(err: WebhookError) -> Self {
+        match err {
+            WebhookError::DatabaseError(_) => ApiError::database(err),
+            WebhookError::Game(e) => e.into(),
+            WebhookError::NotFound(id) => {
+                ApiError::new(Status::NotFound, "webhook_not_found", "Webhook not found")
+                    .with_details(json!({ "webhook_id": id }))
+            }
+            WebhookError::InvalidUrl(url) => ApiError::bad_request(
+                "invalid_url",
+                "Webhook URL must be an http(s) URL with a resolvable host",
+            )
+            .with_details(json!({ "url": url })),
+            WebhookError::PrivateAddress(url) => ApiError::bad_request(
+                "private_url",
+                "Webhook URL must not point to a loopback, private or link-local address",
+            )
+            .with_details(json!({ "url": url })),
+            WebhookError::UnknownEvent(event) => {
+                ApiError::bad_request("unknown_event", format!("Unknown event: {}", event))
+                    .with_details(json!({ "event": event }))
+            }
+        }
+    }
+}
+
+impl From<SeriesError> for ApiError {
+    fn from(err: SeriesError) -> Self {
+        match err {
+            SeriesError::TooManyBuckets(max) => {
+                ApiError::bad_request("too_many_buckets", err.to_string())
+                    .with_details(json!({ "max_buckets": max }))
+            }
+        }
+    }
+}
+
+/// Requests Rocket rejects before reaching a handler get the same error body as handler errors
+#[catch(default)]
+pub fn default_catcher(status: Status, _request: &Request) -> ApiError {
+    let code = match status.code {
+        400 => "bad_request",
+        401 => "unauthorized",
+        404 => "not_found",
+        422 => "invalid_body",
+        500..=599 => "internal_error",
+        _ => "request_failed",
+    };
+    ApiError::new(status, code, status.reason_lossy())
+}
+
+#[cfg(test)]
+mod tests {
+    use rocket::{
+        http::{ContentType, Status},
+        local::blocking::Client,
+    };
+
+    use crate::{config::AppConfig, db::test_pool};
+
+    fn client() -> Client {
+        Client::tracked(crate::api::build(test_pool(), AppConfig::default())).unwrap()
+    }
+
+    fn body(response: rocket::local::blocking::LocalResponse) -> serde_json::Value {
+        serde_json::from_str(&response.into_string().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_handler_error() {
+        let client = client();
+        let response = client.get("/v1/game/unknown1/timeline").dispatch();
+        assert_eq!(response.status(), Status::NotFound);
+        assert_eq!(response.content_type(), Some(ContentType::JSON));
+        assert_eq!(
+            body(response),
+            serde_json::json!({
+                "code": "game_not_found",
+                "message": "Game not found",
+                "details": {"game_id": "unknown1"},
+            })
+        );
+    }
+
+    #[test]
+    fn test_rejected_request() {
+        let client = client();
+        let response = client
+            .post("/game")
+            .header(ContentType::JSON)
+            .body(r#"{"leaderboard_id": "not a number"}"#)
+            .dispatch();
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+        assert_eq!(body(response)["code"], "invalid_body");
+    }
+}
@@ -1,276 +1,803 @@
-use std::collections::{HashMap, HashSet};
-
 use chrono::{DateTime, Utc};
-use rocket::{State, delete, get, http::Status, post, serde::json::Json};
+use rocket::{
+    FromFormField, Shutdown, State, delete, get,
+    http::Status,
+    patch, post,
+    response::stream::{Event, EventStream},
+    serde::json::Json,
+    tokio::{select, sync::broadcast::error::RecvError},
+};
 use serde::Deserialize;
 
 use crate::{
     db::DbPool,
     model::{
-        aoc::{AocPart, AocPuzzle},
-        game::{GameDto, GameLeaderboardMemberDto, GameMembershipDto},
-        leaderboard::{AocMemberId, Day, Year},
-    },
-    service::{
-        GameService, LeaderboardService,
-        game::{GameError, GameMembershipError},
+        aoc::{AocPuzzle, PuzzleDate},
+        game::{
+            GameCompletions, GameDto, GameEvent, GameLeaderboardMemberDto, GameMembershipDto,
+            GameMetadataPatch, GameOptions, GameResultDto,
+        },
+        leaderboard::AocMemberId,
+        series::{ScoreSeriesDto, SeriesBucket},
+        timeline::GameTimelineEntryDto,
     },
+    service::{GameEventHub, GameService, GameStateCache},
+};
+
+use super::{
+    auth::SessionToken,
+    caching::{Cached, Conditional},
+    error::ApiError,
 };
 
-#[derive(Deserialize)]
+#[derive(Deserialize, utoipa::ToSchema)]
 pub struct CreateGameRequest {
     pub leaderboard_id: u32,
     pub session_token: String,
+    #[serde(flatten)]
+    pub options: GameOptions,
 }
 
-#[derive(serde::Serialize)]
+#[derive(serde::Serialize, utoipa::ToSchema)]
 pub struct CreateGameResponse {
     pub game: GameDto,
 }
 
 /// POST /game - Create a new game and return the generated game ID
+#[utoipa::path(
+    post,
+    path = "/v1/game",
+    tag = "game",
+    request_body = CreateGameRequest,
+    responses(
+        (status = 200, body = CreateGameResponse),
+        (
+            status = 400,
+            description = "`ends_at` is not after `starts_at` or a text field is invalid",
+            body = ApiError
+        ),
+        (status = 500, body = ApiError)
+    )
+)]
 #[post("/", data = "<req>")]
 pub async fn create(
     pool: &State<DbPool>,
     req: Json<CreateGameRequest>,
-) -> Result<Json<CreateGameResponse>, (Status, String)> {
+) -> Result<Json<CreateGameResponse>, ApiError> {
     let req = req.into_inner();
-    let conn = pool
-        .get()
-        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    let conn = pool.get()?;
 
     let service = GameService::new();
-    match service.create_game(&conn, req.leaderboard_id, &req.session_token, 10) {
-        Ok(game) => Ok(Json(CreateGameResponse { game })),
-        Err(e) => Err((Status::InternalServerError, e.to_string())),
-    }
+    let game = service.create_game(
+        &conn,
+        req.leaderboard_id,
+        &req.session_token,
+        &req.options,
+        10,
+    )?;
+    Ok(Json(CreateGameResponse { game }))
+}
+
+/// DELETE /game/<id> - Delete a game with its members, webhooks and result
+#[utoipa::path(
+    delete,
+    path = "/v1/game/{id}",
+    tag = "game",
+    params(("id" = String, Path, description = "Game ID")),
+    security(("session_token" = [])),
+    responses(
+        (status = 204, description = "Game deleted"),
+        (status = 401, description = "No session token", body = ApiError),
+        (status = 403, description = "Session token is not the game's", body = ApiError),
+        (status = 404, description = "Game not found", body = ApiError)
+    )
+)]
+#[delete("/<id>")]
+pub async fn delete(
+    pool: &State<DbPool>,
+    token: SessionToken,
+    id: &str,
+) -> Result<Status, ApiError> {
+    let mut conn = pool.get()?;
+
+    GameService::new().delete_game(&mut conn, id, &token.0)?;
+    Ok(Status::NoContent)
+}
+
+#[derive(serde::Serialize, utoipa::ToSchema)]
+pub struct UpdateGameResponse {
+    pub game: GameDto,
 }
 
-#[derive(serde::Serialize)]
+/// PATCH /game/<id> - Change the title, description, creator name or visibility of a game
+#[utoipa::path(
+    patch,
+    path = "/v1/game/{id}",
+    tag = "game",
+    params(("id" = String, Path, description = "Game ID")),
+    request_body = GameMetadataPatch,
+    security(("session_token" = [])),
+    responses(
+        (status = 200, body = UpdateGameResponse),
+        (status = 400, description = "A field is too long or contains control characters", body = ApiError),
+        (status = 401, description = "No session token", body = ApiError),
+        (status = 403, description = "Session token is not the game's", body = ApiError),
+        (status = 404, description = "Game not found", body = ApiError)
+    )
+)]
+#[patch("/<id>", data = "<req>")]
+pub async fn update(
+    pool: &State<DbPool>,
+    token: SessionToken,
+    id: &str,
+    req: Json<GameMetadataPatch>,
+) -> Result<Json<UpdateGameResponse>, ApiError> {
+    let mut conn = pool.get()?;
+
+    let game = GameService::new().update_metadata(&mut conn, id, &token.0, &req)?;
+    Ok(Json(UpdateGameResponse { game }))
+}
+
+#[derive(serde::Serialize, utoipa::ToSchema)]
+pub struct ArchiveGameResponse {
+    pub game: GameDto,
+}
+
+/// POST /game/<id>/archive - Freeze a game and stop refreshing it
+#[utoipa::path(
+    post,
+    path = "/v1/game/{id}/archive",
+    tag = "game",
+    params(("id" = String, Path, description = "Game ID")),
+    security(("session_token" = [])),
+    responses(
+        (status = 200, body = ArchiveGameResponse),
+        (status = 401, description = "No session token", body = ApiError),
+        (status = 403, description = "Session token is not the game's", body = ApiError),
+        (status = 404, description = "Game not found", body = ApiError)
+    )
+)]
+#[post("/<id>/archive")]
+pub async fn archive(
+    pool: &State<DbPool>,
+    token: SessionToken,
+    id: &str,
+) -> Result<Json<ArchiveGameResponse>, ApiError> {
+    let game = GameService::new().archive_game(pool, id, &token.0).await?;
+    Ok(Json(ArchiveGameResponse { game }))
+}
+
+#[derive(serde::Serialize, utoipa::ToSchema)]
 pub struct GetGameMembersResponse {
     pub possible_members: Vec<GameLeaderboardMemberDto>,
     pub members: Vec<GameMembershipDto>,
 }
 
-fn map_game_error(e: GameError) -> (Status, String) {
-    match e {
-        GameError::DatabaseError(_) | GameError::IdGenerationFailed(_) => {
-            (Status::InternalServerError, e.to_string())
-        }
-        GameError::NotFound(_) | GameError::LeaderboardNotFound => {
-            (Status::NotFound, e.to_string())
-        }
-    }
-}
-
+#[utoipa::path(
+    get,
+    path = "/v1/game/{id}/members",
+    tag = "game",
+    params(("id" = String, Path, description = "Game ID")),
+    responses(
+        (status = 200, body = GetGameMembersResponse),
+        (status = 404, description = "Game not found", body = ApiError)
+    )
+)]
 #[get("/<id>/members")]
 pub async fn get_members(
     pool: &State<DbPool>,
     id: &str,
-) -> Result<Json<GetGameMembersResponse>, (Status, String)> {
+) -> Result<Json<GetGameMembersResponse>, ApiError> {
     let service = GameService::new();
-    let possible_members = service
-        .get_possible_members(pool, id)
-        .await
-        .map_err(map_game_error)?;
-
-    let mut conn = pool
-        .get()
-        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
-    let members = service
-        .get_memberships(&mut conn, id)
-        .map_err(map_game_error)?;
+    let possible_members = service.get_possible_members(pool, id).await?;
+
+    let mut conn = pool.get()?;
+    let members = service.get_memberships(&mut conn, id)?;
 
     Ok(Json(GetGameMembersResponse {
-        possible_members: possible_members,
+        possible_members,
         members,
     }))
 }
 
-#[derive(serde::Serialize)]
+#[derive(serde::Serialize, utoipa::ToSchema)]
 pub struct GetAllPuzzlesResponse {
     pub puzzles: Vec<AocPuzzle>,
     pub members: Vec<GameMembershipDto>,
     pub game_id: String,
 }
 
+#[utoipa::path(
+    get,
+    path = "/v1/game/{id}/puzzles/all",
+    tag = "game",
+    params(("id" = String, Path, description = "Game ID")),
+    responses(
+        (status = 200, body = GetAllPuzzlesResponse),
+        (status = 304, description = "Unchanged since `If-None-Match` or `If-Modified-Since`"),
+        (status = 404, description = "Game not found", body = ApiError)
+    )
+)]
 #[get("/<id>/puzzles/all")]
 pub async fn get_all_puzzles(
     pool: &State<DbPool>,
+    cache: &State<GameStateCache>,
+    conditional: Conditional,
     id: &str,
-) -> Result<Json<GetAllPuzzlesResponse>, (Status, String)> {
-    let service = GameService::new();
-
-    // Get game and members (sync work)
-    let (game, members) = {
-        let mut conn = pool
-            .get()
-            .map_err(|e| (Status::InternalServerError, e.to_string()))?;
-        let game = service.get_game(&conn, id).map_err(map_game_error)?;
-        let members = service
-            .get_memberships(&mut conn, id)
-            .map_err(map_game_error)?;
-        (game, members)
-    };
+) -> Result<Cached<Json<GetAllPuzzlesResponse>>, ApiError> {
+    let (version, state) = cache.get(pool, id).await?;
 
-    // Get bingo options (async work)
-    let lbs = LeaderboardService::new();
-    let options = lbs
-        .get_bingo_options(
-            pool,
-            None,
-            game.leaderboard_id,
-            Some(&game.session_token),
-            Some(
-                members
-                    .iter()
-                    .map(|m| m.member_id)
-                    .collect::<Vec<_>>()
-                    .as_slice(),
-            ),
-            Some(game.created_at),
-        )
-        .await
-        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
-
-    Ok(Json(GetAllPuzzlesResponse {
-        puzzles: options,
-        members,
-        game_id: game.id,
+    Ok(conditional.respond(&version, || {
+        Json(GetAllPuzzlesResponse {
+            puzzles: state.puzzles.clone(),
+            members: state.members.clone(),
+            game_id: state.game.id.clone(),
+        })
     }))
 }
 
 /// For all members in a game, return all the puzzles in get_all_puzzles for that game that they now completed
+///
+/// Deprecated: only mounted on the legacy `/game` routes, `/v1` serves [get_completion_v1].
 #[get("/<id>/completion")]
 pub async fn get_completion(
+    pool: &State<DbPool>,
+    cache: &State<GameStateCache>,
+    conditional: Conditional,
+    id: &str,
+) -> Result<Cached<Json<GameCompletions>>, ApiError> {
+    let (version, state) = cache.get(pool, id).await?;
+
+    Ok(conditional.respond(&version, || Json(state.completions.clone())))
+}
+
+#[derive(serde::Serialize, utoipa::ToSchema)]
+pub struct CompletionDto {
+    pub puzzle: AocPuzzle,
+    pub completed_at: DateTime<Utc>,
+}
+
+#[derive(serde::Serialize, utoipa::ToSchema)]
+pub struct MemberCompletionsDto {
+    pub member_id: AocMemberId,
+    /// Ordered by completion time
+    pub completions: Vec<CompletionDto>,
+}
+
+#[derive(serde::Serialize, utoipa::ToSchema)]
+pub struct GetCompletionResponse {
+    /// Every member of the game ordered by member ID, including those without completions
+    pub members: Vec<MemberCompletionsDto>,
+}
+
+/// GET /v1/game/<id>/completion - Puzzles on the card each member completed
+#[utoipa::path(
+    get,
+    path = "/v1/game/{id}/completion",
+    tag = "game",
+    params(("id" = String, Path, description = "Game ID")),
+    responses(
+        (status = 200, body = GetCompletionResponse),
+        (status = 304, description = "Unchanged since `If-None-Match` or `If-Modified-Since`"),
+        (status = 404, description = "Game not found", body = ApiError)
+    )
+)]
+#[get("/<id>/completion")]
+pub async fn get_completion_v1(
+    pool: &State<DbPool>,
+    cache: &State<GameStateCache>,
+    conditional: Conditional,
+    id: &str,
+) -> Result<Cached<Json<GetCompletionResponse>>, ApiError> {
+    let (version, state) = cache.get(pool, id).await?;
+
+    Ok(conditional.respond(&version, || {
+        let mut members: Vec<MemberCompletionsDto> = state
+            .members
+            .iter()
+            .map(|member| {
+                let mut completions: Vec<CompletionDto> = state
+                    .completions
+                    .get(&member.member_id)
+                    .into_iter()
+                    .flatten()
+                    .map(|(year, day, part, completed_at)| CompletionDto {
+                        puzzle: PuzzleDate {
+                            year: *year,
+                            day: *day,
+                        }
+                        .to_aoc_puzzle_part(part.clone()),
+                        completed_at: *completed_at,
+                    })
+                    .collect();
+                completions
+                    .sort_by(|a, b| (a.completed_at, &a.puzzle).cmp(&(b.completed_at, &b.puzzle)));
+                MemberCompletionsDto {
+                    member_id: member.member_id,
+                    completions,
+                }
+            })
+            .collect();
+        members.sort_by_key(|member| member.member_id);
+
+        Json(GetCompletionResponse { members })
+    }))
+}
+
+/// GET /game/<id>/result - Final standings of a finished game
+#[utoipa::path(
+    get,
+    path = "/v1/game/{id}/result",
+    tag = "game",
+    params(("id" = String, Path, description = "Game ID")),
+    responses(
+        (status = 200, body = GameResultDto),
+        (status = 404, description = "Game not found", body = ApiError),
+        (status = 409, description = "Game has not finished yet", body = ApiError)
+    )
+)]
+#[get("/<id>/result")]
+pub async fn get_result(pool: &State<DbPool>, id: &str) -> Result<Json<GameResultDto>, ApiError> {
+    let service = GameService::new();
+    Ok(Json(service.get_result(pool, id).await?))
+}
+
+const TIMELINE_DEFAULT_LIMIT: usize = 50;
+const TIMELINE_MAX_LIMIT: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, FromFormField, utoipa::ToSchema)]
+#[schema(rename_all = "lowercase")]
+pub enum TimelineOrder {
+    /// Oldest star first
+    Asc,
+    /// Newest star first, for activity feeds
+    Desc,
+}
+
+#[derive(serde::Serialize, utoipa::ToSchema)]
+pub struct GetTimelineResponse {
+    pub entries: Vec<GameTimelineEntryDto>,
+    /// Number of entries in the whole timeline
+    pub total: usize,
+    pub offset: usize,
+    pub limit: usize,
+}
+
+/// GET /game/<id>/timeline?limit=&offset=&order= - Page of the stars earned on the game's card
+///
+/// `limit` defaults to 50 and is capped at 500, `order` is `asc` (default) or `desc`.
+#[utoipa::path(
+    get,
+    path = "/v1/game/{id}/timeline",
+    tag = "game",
+    params(
+        ("id" = String, Path, description = "Game ID"),
+        ("limit" = Option<usize>, Query, description = "Page size, 50 by default and at most 500"),
+        ("offset" = Option<usize>, Query, description = "Number of entries to skip"),
+        ("order" = Option<TimelineOrder>, Query, description = "Oldest (default) or newest first")
+    ),
+    responses(
+        (status = 200, body = GetTimelineResponse),
+        (status = 404, description = "Game not found", body = ApiError)
+    )
+)]
+#[get("/<id>/timeline?<limit>&<offset>&<order>")]
+pub async fn get_timeline(
     pool: &State<DbPool>,
     id: &str,
-) -> Result<
-    Json<HashMap<AocMemberId, HashSet<(Year, Day, AocPart, DateTime<Utc>)>>>,
-    (Status, String),
-> {
+    limit: Option<usize>,
+    offset: Option<usize>,
+    order: Option<TimelineOrder>,
+) -> Result<Json<GetTimelineResponse>, ApiError> {
     let service = GameService::new();
+    let state = service.get_game_state(pool, id).await?;
+
+    let limit = limit
+        .unwrap_or(TIMELINE_DEFAULT_LIMIT)
+        .min(TIMELINE_MAX_LIMIT);
+    let offset = offset.unwrap_or(0);
+    let mut entries = state.timeline();
+    let total = entries.len();
+    if order == Some(TimelineOrder::Desc) {
+        entries.reverse();
+    }
+    let entries = entries.into_iter().skip(offset).take(limit).collect();
+
+    Ok(Json(GetTimelineResponse {
+        entries,
+        total,
+        offset,
+        limit,
+    }))
+}
 
-    // Get game and members (sync work)
-    let (game, members) = {
-        let mut conn = pool
-            .get()
-            .map_err(|e| (Status::InternalServerError, e.to_string()))?;
-        let game = service.get_game(&conn, id).map_err(map_game_error)?;
-        let members = service
-            .get_memberships(&mut conn, id)
-            .map_err(map_game_error)?;
-        (game, members)
+/// GET /game/<id>/series?bucket=&tz= - Cumulative score and stars per member over time
+///
+/// `bucket` is `hour` or `day` (default), `tz` an IANA time zone name the buckets are aligned
+/// to, `UTC` by default.
+#[utoipa::path(
+    get,
+    path = "/v1/game/{id}/series",
+    tag = "game",
+    params(
+        ("id" = String, Path, description = "Game ID"),
+        ("bucket" = Option<SeriesBucket>, Query, description = "Bucket size, `day` by default"),
+        ("tz" = Option<String>, Query, description = "IANA time zone, `UTC` by default")
+    ),
+    responses(
+        (status = 200, body = ScoreSeriesDto),
+        (status = 400, description = "Invalid parameters or too many buckets", body = ApiError),
+        (status = 404, description = "Game not found", body = ApiError)
+    )
+)]
+#[get("/<id>/series?<bucket>&<tz>")]
+pub async fn get_score_series(
+    pool: &State<DbPool>,
+    id: &str,
+    bucket: Option<&str>,
+    tz: Option<&str>,
+) -> Result<Json<ScoreSeriesDto>, ApiError> {
+    let bucket = match bucket {
+        Some(bucket) => bucket
+            .parse::<SeriesBucket>()
+            .map_err(|e| ApiError::bad_request("invalid_parameter", e))?,
+        None => SeriesBucket::Day,
     };
+    let tz = match tz {
+        Some(tz) => tz.parse::<chrono_tz::Tz>().map_err(|_| {
+            ApiError::bad_request("invalid_parameter", format!("Unknown time zone: {}", tz))
+        })?,
+        None => chrono_tz::Tz::UTC,
+    };
+
+    let service = GameService::new();
+    let state = service.get_game_state(pool, id).await?;
+
+    state
+        .score_series(bucket, tz, &chrono::Utc::now())
+        .map(Json)
+        .map_err(Into::into)
+}
 
-    // Get bingo options (async work)
-    let lbs = LeaderboardService::new();
-    let options = lbs
-        .get_bingo_options(
-            pool,
-            None,
-            game.leaderboard_id,
-            Some(&game.session_token),
-            Some(
-                members
-                    .iter()
-                    .map(|m| m.member_id)
-                    .collect::<Vec<_>>()
-                    .as_slice(),
-            ),
-            Some(game.created_at),
-        )
-        .await
-        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
-    let current_leaderboards = lbs
-        .get_or_create_all_leaderboards(pool, game.leaderboard_id, Some(&game.session_token))
-        .await;
-
-    let mut completions: HashMap<AocMemberId, HashSet<(Year, Day, AocPart, DateTime<Utc>)>> =
-        HashMap::new();
-
-    for leaderboard in current_leaderboards.into_iter().filter_map(|r| r.ok()) {
-        for member in leaderboard.data.members.values() {
-            if !members.iter().any(|m| m.member_id == member.id) {
+/// GET /game/<id>/events - Live game events as Server-Sent Events
+///
+/// Every event carries its type as SSE event name and the [GameEvent] as JSON data.
+#[utoipa::path(
+    get,
+    path = "/v1/game/{id}/events",
+    tag = "game",
+    params(("id" = String, Path, description = "Game ID")),
+    responses(
+        (
+            status = 200,
+            description = "Stream of events, named by their `type`",
+            body = GameEvent,
+            content_type = "text/event-stream"
+        ),
+        (status = 404, description = "Game not found", body = ApiError)
+    )
+)]
+#[get("/<id>/events")]
+pub async fn events(
+    pool: &State<DbPool>,
+    hub: &State<GameEventHub>,
+    id: &str,
+    mut shutdown: Shutdown,
+) -> Result<EventStream![], ApiError> {
+    let game_id = {
+        let conn = pool.get()?;
+        GameService::new().get_game(&conn, id)?.id
+    };
+    let mut receiver = hub.subscribe();
+
+    Ok(EventStream! {
+        loop {
+            let message = select! {
+                message = receiver.recv() => match message {
+                    Ok(message) => message,
+                    Err(RecvError::Closed) => break,
+                    // Slow clients miss events rather than holding everyone back
+                    Err(RecvError::Lagged(_)) => continue,
+                },
+                _ = &mut shutdown => break,
+            };
+            if message.game_id != game_id {
                 continue;
             }
-            for (&day, day_completion) in member.completion_day_level.iter() {
-                for (&part, star_info) in day_completion.iter() {
-                    if !options.iter().any(|option| {
-                        AocPart::from(part) == option.part
-                            && option.date.year == leaderboard.year
-                            && option.date.day == day
-                    }) {
-                        continue;
-                    }
-                    completions
-                        .entry(member.id)
-                        .or_insert_with(HashSet::new)
-                        .insert((
-                            leaderboard.year as Year,
-                            day,
-                            AocPart::from(part),
-                            DateTime::<Utc>::from_timestamp(star_info.get_star_ts as i64, 0)
-                                .unwrap(),
-                        ));
-                }
-            }
+            yield Event::json(&message.event).event(message.event.name());
         }
-    }
-
-    Ok(Json(completions))
+    })
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, utoipa::ToSchema)]
 pub struct CreateMembershipRequest {
     pub member_id: u32,
-    pub member_name: String,
+    /// Ignored, members join under their name on the game's leaderboard
+    #[allow(unused)]
+    #[serde(default)]
+    pub member_name: Option<String>,
 }
 
-#[derive(serde::Serialize)]
+#[derive(serde::Serialize, utoipa::ToSchema)]
 pub struct CreateMembershipResponse {
     pub membership: GameMembershipDto,
 }
 
-/// POST /game/<id>/members - Add a member to a game
+/// POST /game/<id>/members - Add a member to a game, others join with an invite
+#[utoipa::path(
+    post,
+    path = "/v1/game/{id}/members",
+    tag = "game",
+    params(("id" = String, Path, description = "Game ID")),
+    request_body = CreateMembershipRequest,
+    security(("session_token" = [])),
+    responses(
+        (status = 200, body = CreateMembershipResponse),
+        (status = 401, description = "No session token", body = ApiError),
+        (status = 403, description = "Session token is not the game's", body = ApiError),
+        (status = 404, description = "Game not found", body = ApiError),
+        (status = 409, description = "Member already joined the game", body = ApiError),
+        (status = 422, description = "Member is not on the game's cached leaderboard", body = ApiError)
+    )
+)]
 #[post("/<id>/members", data = "<req>")]
 pub async fn create_membership(
     pool: &State<DbPool>,
+    hub: &State<GameEventHub>,
+    token: SessionToken,
     id: &str,
     req: Json<CreateMembershipRequest>,
-) -> Result<Json<CreateMembershipResponse>, (Status, String)> {
+) -> Result<Json<CreateMembershipResponse>, ApiError> {
     let req = req.into_inner();
-    let mut conn = pool
-        .get()
-        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    let mut conn = pool.get()?;
     let service = GameService::new();
 
-    match service.create_membership(&mut conn, id, req.member_id, &req.member_name) {
-        Ok(membership) => Ok(Json(CreateMembershipResponse { membership })),
-        Err(GameMembershipError::GameNotFound(_)) => {
-            Err((Status::NotFound, "Game not found".to_string()))
-        }
-        Err(e) => Err((Status::InternalServerError, e.to_string())),
-    }
+    let membership = service.create_membership(&mut conn, id, &token.0, req.member_id)?;
+    hub.publish(
+        id,
+        GameEvent::MemberJoined {
+            membership: membership.clone(),
+        },
+    );
+    Ok(Json(CreateMembershipResponse { membership }))
 }
 
 /// DELETE /game/<game_id>/members/<member_id> - Remove a member from a game
+#[utoipa::path(
+    delete,
+    path = "/v1/game/{game_id}/members/{member_id}",
+    tag = "game",
+    params(
+        ("game_id" = String, Path, description = "Game ID"),
+        ("member_id" = AocMemberId, Path, description = "AoC member ID")
+    ),
+    security(("session_token" = [])),
+    responses(
+        (status = 204, description = "Member removed"),
+        (status = 401, description = "No session token", body = ApiError),
+        (status = 403, description = "Session token is not the game's", body = ApiError),
+        (status = 404, description = "Game not found", body = ApiError)
+    )
+)]
 #[delete("/<game_id>/members/<member_id>")]
 pub async fn delete_membership(
     pool: &State<DbPool>,
+    token: SessionToken,
     game_id: &str,
     member_id: u32,
-) -> Result<Status, (Status, String)> {
-    let mut conn = pool
-        .get()
-        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+) -> Result<Status, ApiError> {
+    let mut conn = pool.get()?;
     let service = GameService::new();
 
-    match service.delete_membership_by_game_and_member(&mut conn, game_id, member_id) {
-        Ok(_) => Ok(Status::NoContent),
-        Err(GameMembershipError::NotFound(_)) | Err(GameMembershipError::GameNotFound(_)) => {
-            Err((Status::NotFound, "Game or membership not found".to_string()))
+    service.delete_membership_by_game_and_member(&mut conn, game_id, &token.0, member_id)?;
+    Ok(Status::NoContent)
+}
+
+#[cfg(test)]
+mod tests {
+    use rocket::{
+        http::{ContentType, Header, Status},
+        local::blocking::Client,
+    };
+
+    use crate::{
+        config::AppConfig,
+        db::{DbPool, test_pool},
+        repository::{GameRepository, LeaderboardRepository},
+        service::aoc_utils::AocUtils,
+    };
+
+    const DATA: &str = r#"{"event":"2024","day1_ts":1733029200,"owner_id":1,"num_days":25,"members":{"1":{"id":1,"local_score":0,"name":null,"last_star_ts":0,"stars":0,"completion_day_level":{}}}}"#;
+
+    fn client() -> (DbPool, Client) {
+        let pool = test_pool();
+        {
+            let conn = pool.get().unwrap();
+            let repo = GameRepository::new();
+            repo.create_game(&conn, "abcd1234", 42, "token", &Default::default())
+                .unwrap();
+            repo.create_membership(&conn, "abcd1234", 1, "Alice")
+                .unwrap();
+            for year in AocUtils::event_years() {
+                LeaderboardRepository::new()
+                    .save_leaderboard(&conn, year, 42, DATA)
+                    .unwrap();
+            }
+        }
+        let client =
+            Client::tracked(crate::api::build(pool.clone(), AppConfig::default())).unwrap();
+        (pool, client)
+    }
+
+    fn bearer(token: &str) -> Header<'static> {
+        Header::new("Authorization", format!("Bearer {}", token))
+    }
+
+    #[test]
+    fn test_create_membership() {
+        let (_pool, client) = client();
+        let body = r#"{"member_id": 1, "member_name": "Alice"}"#;
+
+        let response = client
+            .post("/v1/game/abcd1234/members")
+            .header(ContentType::JSON)
+            .body(body)
+            .dispatch();
+        assert_eq!(response.status(), Status::Unauthorized);
+        let response = client
+            .post("/v1/game/abcd1234/members")
+            .header(ContentType::JSON)
+            .header(bearer("other"))
+            .body(body)
+            .dispatch();
+        assert_eq!(response.status(), Status::Forbidden);
+
+        // Alice joined already in [client]
+        let response = client
+            .post("/v1/game/abcd1234/members")
+            .header(ContentType::JSON)
+            .header(bearer("token"))
+            .body(body)
+            .dispatch();
+        assert_eq!(response.status(), Status::Conflict);
+    }
+
+    #[test]
+    fn test_delete_membership() {
+        let (pool, client) = client();
+
+        let response = client.delete("/v1/game/abcd1234/members/1").dispatch();
+        assert_eq!(response.status(), Status::Unauthorized);
+        let response = client
+            .delete("/v1/game/abcd1234/members/1")
+            .header(bearer("other"))
+            .dispatch();
+        assert_eq!(response.status(), Status::Forbidden);
+        {
+            let conn = pool.get().unwrap();
+            let memberships = GameRepository::new()
+                .get_memberships_by_game(&conn, "abcd1234")
+                .unwrap();
+            assert_eq!(memberships.len(), 1);
+        }
+
+        let response = client
+            .delete("/v1/game/abcd1234/members/1")
+            .header(bearer("token"))
+            .dispatch();
+        assert_eq!(response.status(), Status::NoContent);
+        let conn = pool.get().unwrap();
+        assert!(
+            GameRepository::new()
+                .get_memberships_by_game(&conn, "abcd1234")
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_delete() {
+        let (pool, client) = client();
+
+        let response = client.delete("/v1/game/abcd1234").dispatch();
+        assert_eq!(response.status(), Status::Unauthorized);
+        let response = client
+            .delete("/v1/game/abcd1234")
+            .header(bearer("other"))
+            .dispatch();
+        assert_eq!(response.status(), Status::Forbidden);
+
+        let response = client
+            .delete("/v1/game/abcd1234")
+            .header(bearer("token"))
+            .dispatch();
+        assert_eq!(response.status(), Status::NoContent);
+        {
+            let conn = pool.get().unwrap();
+            assert!(
+                GameRepository::new()
+                    .get_memberships_by_game(&conn, "abcd1234")
+                    .unwrap()
+                    .is_empty()
+            );
         }
-        Err(e) => Err((Status::InternalServerError, e.to_string())),
+
+        let response = client
+            .delete("/v1/game/abcd1234")
+            .header(bearer("token"))
+            .dispatch();
+        assert_eq!(response.status(), Status::NotFound);
+    }
+
+    #[test]
+    fn test_update() {
+        let (_pool, client) = client();
+
+        let response = client
+            .patch("/v1/game/abcd1234")
+            .header(bearer("token"))
+            .body(r#"{"title": "  Team bingo ", "visibility": "listed"}"#)
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let body: serde_json::Value =
+            serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        assert_eq!(body["game"]["title"], "Team bingo");
+        assert_eq!(body["game"]["visibility"], "listed");
+
+        // Empty strings clear a field, fields left out are kept
+        let response = client
+            .patch("/v1/game/abcd1234")
+            .header(bearer("token"))
+            .body(r#"{"title": "", "description": "First to 50 stars"}"#)
+            .dispatch();
+        let body: serde_json::Value =
+            serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        assert_eq!(body["game"]["title"], serde_json::Value::Null);
+        assert_eq!(body["game"]["description"], "First to 50 stars");
+        assert_eq!(body["game"]["visibility"], "listed");
+
+        let response = client
+            .patch("/v1/game/abcd1234")
+            .header(bearer("token"))
+            .body(format!(r#"{{"creator_name": "{}"}}"#, "a".repeat(51)))
+            .dispatch();
+        assert_eq!(response.status(), Status::BadRequest);
+        let body: serde_json::Value =
+            serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        assert_eq!(body["code"], "invalid_field");
+        assert_eq!(body["details"]["field"], "creator_name");
+
+        let response = client
+            .patch("/v1/game/abcd1234")
+            .header(bearer("other"))
+            .body(r#"{"title": "Mine now"}"#)
+            .dispatch();
+        assert_eq!(response.status(), Status::Forbidden);
+    }
+
+    #[test]
+    fn test_archive() {
+        let (_pool, client) = client();
+
+        let response = client
+            .get("/v1/game/abcd1234/result")
+            .header(bearer("token"))
+            .dispatch();
+        assert_eq!(response.status(), Status::Conflict);
+
+        let response = client
+            .post("/v1/game/abcd1234/archive")
+            .header(bearer("token"))
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let body: serde_json::Value =
+            serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        assert_eq!(body["game"]["status"], "archived");
+
+        let response = client.get("/v1/game/abcd1234/result").dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let body: serde_json::Value =
+            serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        assert_eq!(body["standings"][0]["member_name"], "Alice");
     }
 }
@@ -1,11 +1,69 @@
-use rocket::{get, serde::json::Json};
+use rocket::{State, get, http::Status, serde::json::Json};
 
-#[derive(serde::Serialize)]
+use crate::{
+    config::AppConfig, db::DbPool, model::health::ReadinessDto, service::health::HealthMonitor,
+};
+
+#[derive(serde::Serialize, utoipa::ToSchema)]
 pub struct HealthResponse {
     status: &'static str,
 }
 
+/// GET /health - Liveness, kept for existing probes
+#[utoipa::path(get, path = "/health", tag = "health", responses((status = 200, body = HealthResponse)))]
 #[get("/health")]
 pub fn health() -> Json<HealthResponse> {
     Json(HealthResponse { status: "OK" })
 }
+
+/// GET /health/live - Liveness, the process is up and answering requests
+#[utoipa::path(get, path = "/health/live", tag = "health", responses((status = 200, body = HealthResponse)))]
+#[get("/health/live")]
+pub fn live() -> Json<HealthResponse> {
+    health()
+}
+
+/// GET /health/ready - Readiness, whether the instance can serve requests right now
+///
+/// Responds with 503 if any check fails, degraded checks are reported but still count as ready.
+#[utoipa::path(
+    get,
+    path = "/health/ready",
+    tag = "health",
+    responses(
+        (status = 200, description = "Ready, possibly degraded", body = ReadinessDto),
+        (status = 503, description = "Not ready", body = ReadinessDto)
+    )
+)]
+#[get("/health/ready")]
+pub fn ready(pool: &State<DbPool>, config: &State<AppConfig>) -> (Status, Json<ReadinessDto>) {
+    let readiness =
+        HealthMonitor::global().readiness(pool, config.features.game_refresher, chrono::Utc::now());
+    let status = if readiness.is_ready() {
+        Status::Ok
+    } else {
+        Status::ServiceUnavailable
+    };
+    (status, Json(readiness))
+}
+
+#[cfg(test)]
+mod tests {
+    use rocket::{http::Status, local::blocking::Client};
+
+    use crate::{config::AppConfig, db::test_pool};
+
+    #[test]
+    fn test_ready() {
+        let client = Client::tracked(crate::api::build(test_pool(), AppConfig::default())).unwrap();
+        let response = client.get("/health/ready").dispatch();
+        assert_eq!(response.status(), Status::Ok);
+
+        let body: serde_json::Value =
+            serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        assert_eq!(body["checks"]["database"]["status"], "pass");
+        assert_eq!(body["checks"]["migrations"]["status"], "pass");
+        assert!(body["checks"]["refresher"].is_object());
+        assert!(body["checks"]["upstream"].is_object());
+    }
+}
@@ -0,0 +1,380 @@
+use chrono::{DateTime, Utc};
+use rocket::{State, delete, get, http::Status, post, serde::json::Json};
+use serde::Deserialize;
+
+use crate::{
+    db::DbPool,
+    model::{
+        game::{GameEvent, GameId, GameMembershipDto},
+        invite::{GameInviteDto, InviteOptions, InviteRedemptionDto},
+        leaderboard::AocMemberId,
+    },
+    service::{GameEventHub, InviteService},
+};
+
+use super::{auth::SessionToken, error::ApiError};
+
+#[derive(serde::Serialize, utoipa::ToSchema)]
+pub struct CreateInviteResponse {
+    pub invite: GameInviteDto,
+}
+
+/// POST /game/<id>/invites - Create an invite code for a game
+#[utoipa::path(
+    post,
+    path = "/v1/game/{id}/invites",
+    tag = "invite",
+    params(("id" = String, Path, description = "Game ID")),
+    request_body = InviteOptions,
+    security(("session_token" = [])),
+    responses(
+        (status = 200, body = CreateInviteResponse),
+        (status = 400, description = "Invalid expiry or use limit", body = ApiError),
+        (status = 401, description = "No session token", body = ApiError),
+        (status = 403, description = "Session token is not the game's", body = ApiError),
+        (status = 404, description = "Game not found", body = ApiError)
+    )
+)]
+#[post("/<id>/invites", data = "<req>")]
+pub async fn create(
+    pool: &State<DbPool>,
+    token: SessionToken,
+    id: &str,
+    req: Json<InviteOptions>,
+) -> Result<Json<CreateInviteResponse>, ApiError> {
+    let mut conn = pool.get()?;
+
+    let invite = InviteService::new().create_invite(&mut conn, id, &token.0, &req)?;
+    Ok(Json(CreateInviteResponse { invite }))
+}
+
+#[derive(serde::Serialize, utoipa::ToSchema)]
+pub struct GetInvitesResponse {
+    pub invites: Vec<GameInviteDto>,
+}
+
+/// GET /game/<id>/invites - List the invites of a game
+#[utoipa::path(
+    get,
+    path = "/v1/game/{id}/invites",
+    tag = "invite",
+    params(("id" = String, Path, description = "Game ID")),
+    security(("session_token" = [])),
+    responses(
+        (status = 200, body = GetInvitesResponse),
+        (status = 401, description = "No session token", body = ApiError),
+        (status = 403, description = "Session token is not the game's", body = ApiError),
+        (status = 404, description = "Game not found", body = ApiError)
+    )
+)]
+#[get("/<id>/invites")]
+pub async fn get_all(
+    pool: &State<DbPool>,
+    token: SessionToken,
+    id: &str,
+) -> Result<Json<GetInvitesResponse>, ApiError> {
+    let conn = pool.get()?;
+
+    let invites = InviteService::new().get_invites(&conn, id, &token.0)?;
+    Ok(Json(GetInvitesResponse { invites }))
+}
+
+/// DELETE /game/<id>/invites/<invite_id> - Revoke an invite
+#[utoipa::path(
+    delete,
+    path = "/v1/game/{id}/invites/{invite_id}",
+    tag = "invite",
+    params(
+        ("id" = String, Path, description = "Game ID"),
+        ("invite_id" = u32, Path, description = "Invite ID")
+    ),
+    security(("session_token" = [])),
+    responses(
+        (status = 204, description = "Invite deleted with its pending redemptions"),
+        (status = 401, description = "No session token", body = ApiError),
+        (status = 403, description = "Session token is not the game's", body = ApiError),
+        (status = 404, description = "Game or invite not found", body = ApiError)
+    )
+)]
+#[delete("/<id>/invites/<invite_id>")]
+pub async fn delete(
+    pool: &State<DbPool>,
+    token: SessionToken,
+    id: &str,
+    invite_id: u32,
+) -> Result<Status, ApiError> {
+    let conn = pool.get()?;
+
+    InviteService::new().delete_invite(&conn, id, &token.0, invite_id)?;
+    Ok(Status::NoContent)
+}
+
+#[derive(serde::Serialize, utoipa::ToSchema)]
+pub struct GetRedemptionsResponse {
+    /// Newest first
+    pub redemptions: Vec<InviteRedemptionDto>,
+}
+
+/// GET /game/<id>/redemptions - Redeemed invites of a game, including those awaiting approval
+#[utoipa::path(
+    get,
+    path = "/v1/game/{id}/redemptions",
+    tag = "invite",
+    params(("id" = String, Path, description = "Game ID")),
+    security(("session_token" = [])),
+    responses(
+        (status = 200, body = GetRedemptionsResponse),
+        (status = 401, description = "No session token", body = ApiError),
+        (status = 403, description = "Session token is not the game's", body = ApiError),
+        (status = 404, description = "Game not found", body = ApiError)
+    )
+)]
+#[get("/<id>/redemptions")]
+pub async fn get_redemptions(
+    pool: &State<DbPool>,
+    token: SessionToken,
+    id: &str,
+) -> Result<Json<GetRedemptionsResponse>, ApiError> {
+    let conn = pool.get()?;
+
+    let redemptions = InviteService::new().get_redemptions(&conn, id, &token.0)?;
+    Ok(Json(GetRedemptionsResponse { redemptions }))
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct DecideRedemptionRequest {
+    /// `true` lets the member join, `false` rejects the redemption
+    pub approve: bool,
+}
+
+#[derive(serde::Serialize, utoipa::ToSchema)]
+pub struct RedemptionResponse {
+    pub redemption: InviteRedemptionDto,
+    /// Set if the member joined the game
+    pub membership: Option<GameMembershipDto>,
+}
+
+/// POST /game/<id>/redemptions/<redemption_id> - Approve or reject a pending redemption
+#[utoipa::path(
+    post,
+    path = "/v1/game/{id}/redemptions/{redemption_id}",
+    tag = "invite",
+    params(
+        ("id" = String, Path, description = "Game ID"),
+        ("redemption_id" = u32, Path, description = "Redemption ID")
+    ),
+    request_body = DecideRedemptionRequest,
+    security(("session_token" = [])),
+    responses(
+        (status = 200, body = RedemptionResponse),
+        (status = 401, description = "No session token", body = ApiError),
+        (status = 403, description = "Session token is not the game's", body = ApiError),
+        (status = 404, description = "Game or redemption not found", body = ApiError),
+        (status = 409, description = "Redemption already decided, or member already joined", body = ApiError),
+        (status = 422, description = "Member is no longer on the game's leaderboard", body = ApiError)
+    )
+)]
+#[post("/<id>/redemptions/<redemption_id>", data = "<req>")]
+pub async fn decide_redemption(
+    pool: &State<DbPool>,
+    hub: &State<GameEventHub>,
+    token: SessionToken,
+    id: &str,
+    redemption_id: u32,
+    req: Json<DecideRedemptionRequest>,
+) -> Result<Json<RedemptionResponse>, ApiError> {
+    let mut conn = pool.get()?;
+
+    let (redemption, membership) = InviteService::new().decide_redemption(
+        &mut conn,
+        id,
+        &token.0,
+        redemption_id,
+        req.approve,
+    )?;
+    if let Some(membership) = &membership {
+        hub.publish(
+            id,
+            GameEvent::MemberJoined {
+                membership: membership.clone(),
+            },
+        );
+    }
+    Ok(Json(RedemptionResponse {
+        redemption,
+        membership,
+    }))
+}
+
+#[derive(serde::Serialize, utoipa::ToSchema)]
+pub struct GetInviteResponse {
+    pub game_id: GameId,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub creator_name: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Redeeming only asks the owner to approve joining
+    pub requires_approval: bool,
+}
+
+/// GET /v1/invite/<code> - The game an invite code joins
+#[utoipa::path(
+    get,
+    path = "/v1/invite/{code}",
+    tag = "invite",
+    params(("code" = String, Path, description = "Invite code")),
+    responses(
+        (status = 200, body = GetInviteResponse),
+        (status = 404, description = "Invite not found", body = ApiError),
+        (status = 410, description = "Invite expired or has no uses left", body = ApiError)
+    )
+)]
+#[get("/<code>")]
+pub async fn get(pool: &State<DbPool>, code: &str) -> Result<Json<GetInviteResponse>, ApiError> {
+    let conn = pool.get()?;
+
+    let (invite, game) = InviteService::new().get_invite(&conn, code)?;
+    Ok(Json(GetInviteResponse {
+        game_id: game.id,
+        title: game.title,
+        description: game.description,
+        creator_name: game.creator_name,
+        expires_at: invite.expires_at,
+        requires_approval: invite.requires_approval,
+    }))
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct RedeemInviteRequest {
+    /// AoC member to join as, their name is taken from the game's leaderboard
+    pub member_id: AocMemberId,
+}
+
+/// POST /v1/invite/<code> - Join a game as an AoC member of its leaderboard
+#[utoipa::path(
+    post,
+    path = "/v1/invite/{code}",
+    tag = "invite",
+    params(("code" = String, Path, description = "Invite code")),
+    request_body = RedeemInviteRequest,
+    responses(
+        (status = 200, description = "Joined, or awaiting approval if the redemption is pending", body = RedemptionResponse),
+        (status = 404, description = "Invite not found", body = ApiError),
+        (status = 409, description = "Member already joined or awaits approval", body = ApiError),
+        (status = 410, description = "Invite expired or has no uses left", body = ApiError),
+        (status = 422, description = "Member is not on the game's cached leaderboard", body = ApiError)
+    )
+)]
+#[post("/<code>", data = "<req>")]
+pub async fn redeem(
+    pool: &State<DbPool>,
+    hub: &State<GameEventHub>,
+    code: &str,
+    req: Json<RedeemInviteRequest>,
+) -> Result<Json<RedemptionResponse>, ApiError> {
+    let req = req.into_inner();
+    let mut conn = pool.get()?;
+
+    let (redemption, membership) = InviteService::new().redeem(&mut conn, code, req.member_id)?;
+    if let Some(membership) = &membership {
+        hub.publish(
+            &membership.game_id,
+            GameEvent::MemberJoined {
+                membership: membership.clone(),
+            },
+        );
+    }
+    Ok(Json(RedemptionResponse {
+        redemption,
+        membership,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use rocket::{
+        http::{ContentType, Header, Status},
+        local::blocking::Client,
+    };
+
+    use crate::{
+        config::AppConfig,
+        db::test_pool,
+        repository::{GameRepository, LeaderboardRepository},
+    };
+
+    const DATA: &str = r#"{"event":"2024","day1_ts":1733029200,"owner_id":1,"num_days":25,"members":{"1":{"id":1,"local_score":0,"name":"Alice","last_star_ts":0,"stars":0,"completion_day_level":{}}}}"#;
+
+    #[test]
+    fn test_invite_with_approval() {
+        let pool = test_pool();
+        {
+            let conn = pool.get().unwrap();
+            GameRepository::new()
+                .create_game(&conn, "abcd1234", 42, "token", &Default::default())
+                .unwrap();
+            LeaderboardRepository::new()
+                .save_leaderboard(&conn, 2024, 42, DATA)
+                .unwrap();
+        }
+        let client = Client::tracked(crate::api::build(pool, AppConfig::default())).unwrap();
+        let owner = || Header::new("Authorization", "Bearer token");
+
+        let response = client
+            .post("/v1/game/abcd1234/invites")
+            .header(ContentType::JSON)
+            .body(r#"{"requires_approval": true, "max_uses": 5}"#)
+            .dispatch();
+        assert_eq!(response.status(), Status::Unauthorized);
+        let response = client
+            .post("/v1/game/abcd1234/invites")
+            .header(owner())
+            .header(ContentType::JSON)
+            .body(r#"{"requires_approval": true, "max_uses": 5}"#)
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let body: serde_json::Value =
+            serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        let code = body["invite"]["code"].as_str().unwrap().to_string();
+
+        let response = client.get(format!("/v1/invite/{}", code)).dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let body: serde_json::Value =
+            serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        assert_eq!(body["game_id"], "abcd1234");
+        assert_eq!(body["requires_approval"], true);
+
+        let response = client
+            .post(format!("/v1/invite/{}", code))
+            .header(ContentType::JSON)
+            .body(r#"{"member_id": 1}"#)
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let body: serde_json::Value =
+            serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        assert_eq!(body["redemption"]["status"], "pending");
+        assert!(body["membership"].is_null());
+        let redemption_id = body["redemption"]["id"].as_u64().unwrap();
+
+        let response = client
+            .post(format!("/v1/game/abcd1234/redemptions/{}", redemption_id))
+            .header(owner())
+            .header(ContentType::JSON)
+            .body(r#"{"approve": true}"#)
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let body: serde_json::Value =
+            serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        assert_eq!(body["redemption"]["status"], "approved");
+        assert_eq!(body["membership"]["member_name"], "Alice");
+
+        let response = client
+            .post(format!("/v1/invite/{}", code))
+            .header(ContentType::JSON)
+            .body(r#"{"member_id": 1}"#)
+            .dispatch();
+        assert_eq!(response.status(), Status::Conflict);
+        let response = client.get("/v1/invite/unknown").dispatch();
+        assert_eq!(response.status(), Status::NotFound);
+    }
+}
@@ -1,26 +1,47 @@
-use rocket::{State, http::Status, post, serde::json::Json};
+use chrono::Utc;
+use rocket::{State, get, post, serde::json::Json};
 
 use crate::{
     db::DbPool,
-    model::leaderboard::{
-        AocMemberId, LeaderboardDto, ShuffleLeaderboardDataDto, ShuffleLeaderboardDayDto,
-        ShuffleLeaderboardDto,
+    model::{
+        game::GameSummaryDto,
+        leaderboard::{
+            AocLeaderboardId, AocMemberId, LeaderboardDto, ShuffleLeaderboardDataDto,
+            ShuffleLeaderboardDayDto, ShuffleLeaderboardDto,
+        },
     },
-    service::LeaderboardService,
+    service::{GameService, GameStateCache, LeaderboardService, board_access::BoardAccess},
 };
 
-#[derive(serde::Deserialize)]
+use super::{auth::SessionToken, error::ApiError};
+
+/// Number of leading members in each game summary
+const SUMMARY_STANDINGS: usize = 3;
+
+#[derive(serde::Deserialize, utoipa::ToSchema)]
 pub struct LeaderboardRequest {
     year: u32,
     board_id: u32,
     session_token: String,
 }
 
+/// POST /v1/leaderboard - A cached leaderboard year, fetched from AoC when missing or stale
+#[utoipa::path(
+    post,
+    path = "/v1/leaderboard",
+    tag = "leaderboard",
+    request_body = LeaderboardRequest,
+    responses(
+        (status = 200, body = LeaderboardDto),
+        (status = 401, description = "Session token rejected by AoC", body = ApiError),
+        (status = 502, description = "AoC could not be reached", body = ApiError)
+    )
+)]
 #[post("/", data = "<req>")]
 pub async fn index(
     pool: &State<DbPool>,
     req: Json<LeaderboardRequest>,
-) -> Result<Json<LeaderboardDto>, (Status, String)> {
+) -> Result<Json<LeaderboardDto>, ApiError> {
     let req = req.into_inner();
 
     let result = {
@@ -29,13 +50,10 @@ pub async fn index(
             .await
     };
 
-    match result {
-        Ok(response) => Ok(Json(response)),
-        Err(e) => Err((Status::InternalServerError, e.to_string())),
-    }
+    Ok(Json(result?))
 }
 
-#[derive(serde::Deserialize)]
+#[derive(serde::Deserialize, utoipa::ToSchema)]
 pub struct BingoAllRequest {
     board_id: u32,
     session_token: String,
@@ -48,11 +66,23 @@ pub struct BingoAllRequest {
     difficulty: Option<f32>,
 }
 
+/// POST /v1/leaderboard/bingo/all - Every puzzle that can go on a card for the given members
+#[utoipa::path(
+    post,
+    path = "/v1/leaderboard/bingo/all",
+    tag = "leaderboard",
+    request_body = BingoAllRequest,
+    responses(
+        (status = 200, body = ShuffleLeaderboardDto),
+        (status = 401, description = "Session token rejected by AoC", body = ApiError),
+        (status = 422, description = "No puzzles available", body = ApiError)
+    )
+)]
 #[post("/bingo/all", data = "<req>")]
 pub async fn bingo_all(
     pool: &State<DbPool>,
     req: Json<BingoAllRequest>,
-) -> Result<Json<ShuffleLeaderboardDto>, (Status, String)> {
+) -> Result<Json<ShuffleLeaderboardDto>, ApiError> {
     let req = req.into_inner();
 
     let puzzles_result = {
@@ -68,10 +98,7 @@ pub async fn bingo_all(
         .await
     };
 
-    let puzzles = match puzzles_result {
-        Ok(leaderboard) => leaderboard,
-        Err(e) => return Err((Status::BadRequest, e.to_string())),
-    };
+    let puzzles = puzzles_result?;
 
     let result = {
         let mut data = ShuffleLeaderboardDataDto {
@@ -96,3 +123,152 @@ pub async fn bingo_all(
 
     Ok(Json(result))
 }
+
+#[derive(serde::Serialize, utoipa::ToSchema)]
+pub struct LeaderboardGamesResponse {
+    /// Scheduled and running games, newest first
+    pub active: Vec<GameSummaryDto>,
+    /// Finished and archived games, newest first
+    pub past: Vec<GameSummaryDto>,
+}
+
+/// GET /v1/leaderboard/<board_id>/games - Listed games on a leaderboard, for its members only
+///
+/// Membership is proven by a session token that sees the leaderboard on AoC, or that a game on
+/// the leaderboard was created with.
+#[utoipa::path(
+    get,
+    path = "/v1/leaderboard/{board_id}/games",
+    tag = "leaderboard",
+    params(("board_id" = u32, Path, description = "AoC private leaderboard ID")),
+    security(("session_token" = [])),
+    responses(
+        (status = 200, body = LeaderboardGamesResponse),
+        (status = 401, description = "Missing session token, or not a member of the leaderboard", body = ApiError),
+        (status = 502, description = "AoC could not be reached", body = ApiError)
+    )
+)]
+#[get("/<board_id>/games")]
+pub async fn games(
+    pool: &State<DbPool>,
+    cache: &State<GameStateCache>,
+    session_token: SessionToken,
+    board_id: AocLeaderboardId,
+) -> Result<Json<LeaderboardGamesResponse>, ApiError> {
+    BoardAccess::global()
+        .verify(pool, board_id, &session_token.0)
+        .await?;
+
+    let games = {
+        let conn = pool.get()?;
+        GameService::new().get_listed_games(&conn, board_id)?
+    };
+
+    let now = Utc::now();
+    let mut response = LeaderboardGamesResponse {
+        active: vec![],
+        past: vec![],
+    };
+    for game in games {
+        let (member_count, standings) = match cache.get(pool, &game.id).await {
+            Ok((_, state)) => {
+                let mut standings = state.standings();
+                standings.truncate(SUMMARY_STANDINGS);
+                (state.members.len() as u32, standings)
+            }
+            Err(e) => {
+                tracing::warn!(game_id = game.id, error = %e, "Failed to compute game standings");
+                let mut conn = pool.get()?;
+                let members = GameService::new().get_memberships(&mut conn, &game.id)?;
+                (members.len() as u32, vec![])
+            }
+        };
+        let status = game.status_at(&now);
+        let summary = GameSummaryDto {
+            id: game.id,
+            title: game.title,
+            description: game.description,
+            creator_name: game.creator_name,
+            status,
+            starts_at: game.starts_at,
+            ends_at: game.ends_at,
+            created_at: game.created_at,
+            member_count,
+            standings,
+        };
+        if status.is_frozen() {
+            response.past.push(summary);
+        } else {
+            response.active.push(summary);
+        }
+    }
+
+    Ok(Json(response))
+}
+
+#[cfg(test)]
+mod tests {
+    use rocket::{
+        http::{Header, Status},
+        local::blocking::Client,
+    };
+
+    use crate::{
+        config::AppConfig,
+        db::test_pool,
+        model::game::{GameOptions, GameVisibility},
+        repository::{GameRepository, LeaderboardRepository},
+        service::aoc_utils::AocUtils,
+    };
+
+    const DATA: &str = r#"{"event":"2024","day1_ts":1733029200,"owner_id":1,"num_days":25,"members":{"1":{"id":1,"local_score":0,"name":null,"last_star_ts":0,"stars":0,"completion_day_level":{}}}}"#;
+
+    #[test]
+    fn test_games() {
+        let pool = test_pool();
+        {
+            let conn = pool.get().unwrap();
+            let repo = GameRepository::new();
+            let listed = GameOptions {
+                title: Some("Team bingo".to_string()),
+                visibility: GameVisibility::Listed,
+                ..Default::default()
+            };
+            repo.create_game(&conn, "abcd1234", 42, "token", &listed)
+                .unwrap();
+            repo.create_membership(&conn, "abcd1234", 1, "Alice")
+                .unwrap();
+            repo.create_game(&conn, "efgh5678", 42, "other", &Default::default())
+                .unwrap();
+            repo.create_game(&conn, "ijkl9012", 43, "token", &listed)
+                .unwrap();
+            for year in AocUtils::event_years() {
+                LeaderboardRepository::new()
+                    .save_leaderboard(&conn, year, 42, DATA)
+                    .unwrap();
+            }
+        }
+        let client = Client::tracked(crate::api::build(pool, AppConfig::default())).unwrap();
+
+        let response = client.get("/v1/leaderboard/42/games").dispatch();
+        assert_eq!(response.status(), Status::Unauthorized);
+
+        // The owner of the unlisted game is a member too
+        let response = client
+            .get("/v1/leaderboard/42/games")
+            .header(Header::new("Authorization", "Bearer other"))
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let body: serde_json::Value =
+            serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        assert_eq!(body["past"], serde_json::json!([]));
+        let active = body["active"].as_array().unwrap();
+        assert_eq!(active.len(), 1);
+        assert_eq!(active[0]["id"], "abcd1234");
+        assert_eq!(active[0]["title"], "Team bingo");
+        assert_eq!(active[0]["status"], "running");
+        assert_eq!(active[0]["member_count"], 1);
+        assert_eq!(active[0]["standings"][0]["member_name"], "Alice");
+        assert!(active[0].get("session_token").is_none());
+    }
+}
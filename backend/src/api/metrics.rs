@@ -0,0 +1,108 @@
+use std::time::Instant;
+
+use rocket::{
+    Data, Request, Response, State,
+    fairing::{Fairing, Info, Kind},
+    get,
+    http::ContentType,
+};
+
+use crate::{db::DbPool, service::metrics::Metrics};
+
+use super::error::ApiError;
+
+/// Records the count and latency of every request by route
+pub struct RequestMetrics;
+
+/// When the request was received, kept in the request-local cache
+struct RequestStart(Instant);
+
+#[rocket::async_trait]
+impl Fairing for RequestMetrics {
+    fn info(&self) -> Info {
+        Info {
+            name: "Request metrics",
+            kind: Kind::Request | Kind::Response,
+        }
+    }
+
+    async fn on_request(&self, request: &mut Request<'_>, _: &mut Data<'_>) {
+        request.local_cache(|| RequestStart(Instant::now()));
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        let started = request.local_cache(|| RequestStart(Instant::now()));
+        // Route patterns keep the number of label values bounded, unlike request paths
+        let route = request
+            .route()
+            .map(|route| route.uri.path())
+            .unwrap_or("unmatched");
+        Metrics::global().observe_request(
+            request.method().as_str(),
+            route,
+            response.status().code,
+            started.0.elapsed(),
+        );
+    }
+}
+
+/// GET /metrics - Metrics in the Prometheus text format
+#[get("/metrics")]
+pub fn metrics(pool: &State<DbPool>) -> Result<(ContentType, String), ApiError> {
+    let body = Metrics::global().render(pool)?;
+    Ok((
+        ContentType::new("text", "plain").with_params(("version", "0.0.4")),
+        body,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use rocket::{http::Status, local::blocking::Client};
+
+    use crate::{
+        config::AppConfig,
+        db::test_pool,
+        repository::{GameRepository, LeaderboardRepository},
+        service::aoc_utils::AocUtils,
+    };
+
+    const DATA: &str = r#"{"event":"2024","day1_ts":1733029200,"owner_id":1,"num_days":25,"members":{"1":{"id":1,"local_score":0,"name":null,"last_star_ts":0,"stars":0,"completion_day_level":{}}}}"#;
+
+    #[test]
+    fn test_metrics() {
+        let pool = test_pool();
+        {
+            let conn = pool.get().unwrap();
+            let repo = GameRepository::new();
+            repo.create_game(&conn, "abcd1234", 42, "token", &Default::default())
+                .unwrap();
+            repo.create_membership(&conn, "abcd1234", 7, "Member 7")
+                .unwrap();
+            // Cached leaderboards keep the game refresher from fetching them from AoC
+            for year in AocUtils::event_years() {
+                LeaderboardRepository::new()
+                    .save_leaderboard(&conn, year, 42, DATA)
+                    .unwrap();
+            }
+        }
+        let client = Client::tracked(crate::api::build(pool, AppConfig::default())).unwrap();
+        client.get("/health").dispatch();
+        client.get("/v1/game/unknown1/timeline").dispatch();
+
+        let response = client.get("/metrics").dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let body = response.into_string().unwrap();
+        assert!(
+            body.contains(
+                r#"bingo_http_requests_total{method="GET",route="/health",status="200"}"#
+            )
+        );
+        assert!(body.contains(
+            r#"bingo_http_requests_total{method="GET",route="/v1/game/<id>/timeline",status="404"}"#
+        ));
+        assert!(body.contains("bingo_games 1\n"));
+        assert!(body.contains("bingo_game_members 1\n"));
+        assert!(body.contains(r#"bingo_db_pool_connections{state="max"} 1"#));
+    }
+}
@@ -1,50 +1,301 @@
-use rocket::{Config, routes};
-use rocket_cors::CorsOptions;
+use rocket::{Config, Route, catchers, fairing::AdHoc, routes};
+use rocket_cors::{AllowedOrigins, Cors, CorsOptions};
 
-use crate::db::DbPool;
+use crate::{
+    config::{AppConfig, CorsConfig, FeatureConfig, RetentionConfig},
+    db::DbPool,
+    service::{
+        GameEventHub, GameStateCache,
+        game_events::GameRefresher,
+        retention::RetentionJob,
+        webhook::{RetryPolicy, WebhookDispatcher},
+    },
+};
 
+use self::request_id::traced;
+
+mod auth;
+mod caching;
+mod error;
 mod game;
 mod health;
+mod invite;
 mod leaderboard;
+mod metrics;
+mod openapi;
+mod rate_limit;
+mod request_id;
+mod webhook;
+mod ws;
+
+/// Prefix of the current API version. The unprefixed `/game` and `/leaderboard` routes are
+/// deprecated: they keep their response shapes for existing clients and announce their
+/// successor with `Deprecation` and `Link` headers, see [deprecation_headers].
+const API_V1: &str = "/v1";
+
+/// Routes of the unprefixed API that are replaced by [API_V1]
+const LEGACY_PREFIXES: [&str; 2] = ["/game", "/leaderboard"];
+
+/// When the unprefixed routes were deprecated, as `Deprecation` header value (RFC 9745)
+const LEGACY_DEPRECATED_AT: &str = "@1792281600";
+
+/// Game routes of the unprefixed API, frozen at what it offered before [API_V1]. Everything
+/// added since is only available under [API_V1].
+fn legacy_game_routes() -> Vec<Route> {
+    routes![
+        game::create,
+        game::get_members,
+        game::get_all_puzzles,
+        game::create_membership,
+        game::delete_membership,
+        game::get_completion,
+    ]
+}
+
+/// Leaderboard routes of the unprefixed API, see [legacy_game_routes]
+fn legacy_leaderboard_routes() -> Vec<Route> {
+    routes![leaderboard::index, leaderboard::bingo_all]
+}
+
+/// Game routes under [API_V1]
+fn game_routes() -> Vec<Route> {
+    routes![
+        game::create,
+        game::update,
+        game::delete,
+        game::archive,
+        game::get_members,
+        game::get_all_puzzles,
+        game::get_result,
+        game::create_membership,
+        game::delete_membership,
+        game::get_timeline,
+        game::get_score_series,
+        game::events,
+        ws::channel,
+        webhook::create,
+        webhook::get_all,
+        webhook::delete,
+        webhook::get_deliveries,
+        invite::create,
+        invite::get_all,
+        invite::delete,
+        invite::get_redemptions,
+        invite::decide_redemption,
+        game::get_completion_v1,
+    ]
+}
 
 trait ConfigureRocket {
-    fn mount_routes(self: Self) -> Self;
+    fn mount_routes(self: Self, features: &FeatureConfig) -> Self;
     fn config(self: Self) -> Self;
 }
 
 impl ConfigureRocket for rocket::Rocket<rocket::Build> {
-    fn mount_routes(self: Self) -> Self {
-        self.mount("/", routes![health::health])
+    fn mount_routes(self: Self, features: &FeatureConfig) -> Self {
+        let mut rocket = self
+            .register("/", catchers![error::default_catcher])
             .mount(
-                "/leaderboard",
-                routes![leaderboard::index, leaderboard::bingo_all],
+                "/",
+                traced(routes![
+                    health::health,
+                    health::live,
+                    health::ready,
+                    rate_limit::rate_limited
+                ]),
             )
+            .mount("/leaderboard", traced(legacy_leaderboard_routes()))
+            .mount("/game", traced(legacy_game_routes()))
             .mount(
-                "/game",
-                routes![
-                    game::create,
-                    game::get_members,
-                    game::get_all_puzzles,
-                    game::create_membership,
-                    game::delete_membership,
-                    game::get_completion,
-                ],
+                format!("{}/leaderboard", API_V1),
+                traced(routes![
+                    leaderboard::index,
+                    leaderboard::bingo_all,
+                    leaderboard::games
+                ]),
             )
+            .mount(format!("{}/game", API_V1), traced(game_routes()))
+            .mount(
+                format!("{}/invite", API_V1),
+                traced(routes![invite::get, invite::redeem]),
+            );
+        if features.metrics {
+            rocket = rocket.mount("/", traced(routes![metrics::metrics]));
+        }
+        if features.api_docs {
+            rocket = rocket.mount("/", traced(openapi::routes()));
+        }
+        rocket
     }
 
     fn config(self: Self) -> Self {
         let base = self.figment().clone();
-        self.configure(
-            base.merge((Config::LOG_LEVEL, "off"))
-                .merge((Config::CLI_COLORS, "false")),
-        )
+        // Rocket logs through the `log` facade into the subscriber installed by
+        // [crate::logging::init], which decides on levels and format
+        self.configure(base.merge((Config::CLI_COLORS, "false")))
+    }
+}
+
+/// Marks responses of deprecated unprefixed routes and points to their `/v1` successor
+fn deprecation_headers() -> AdHoc {
+    AdHoc::on_response("Legacy API deprecation", |request, response| {
+        Box::pin(async move {
+            let path = request.uri().path();
+            let is_legacy = LEGACY_PREFIXES
+                .iter()
+                .any(|prefix| path == *prefix || path.starts_with(format!("{}/", prefix).as_str()));
+            if request.route().is_none() || !is_legacy {
+                return;
+            }
+            response.set_raw_header("Deprecation", LEGACY_DEPRECATED_AT);
+            response.set_raw_header(
+                "Link",
+                format!("<{}{}>; rel=\"successor-version\"", API_V1, path),
+            );
+        })
+    })
+}
+
+/// Runs the [GameRefresher] in the background for as long as the server is up
+fn game_refresher() -> AdHoc {
+    AdHoc::on_liftoff("Game refresher", |rocket| {
+        Box::pin(async move {
+            let pool = rocket.state::<DbPool>().unwrap().clone();
+            let hub = rocket.state::<GameEventHub>().unwrap().clone();
+            let shutdown = rocket.shutdown();
+            tokio::spawn(async move {
+                GameRefresher::new(pool, hub)
+                    .run(async move {
+                        shutdown.await;
+                    })
+                    .await;
+            });
+        })
+    })
+}
+
+/// Runs the [WebhookDispatcher] in the background for as long as the server is up
+fn webhook_dispatcher() -> AdHoc {
+    AdHoc::on_liftoff("Webhook dispatcher", |rocket| {
+        Box::pin(async move {
+            let pool = rocket.state::<DbPool>().unwrap().clone();
+            let hub = rocket.state::<GameEventHub>().unwrap().clone();
+            let shutdown = rocket.shutdown();
+            tokio::spawn(async move {
+                WebhookDispatcher::new(pool, RetryPolicy::default())
+                    .run(hub, async move {
+                        shutdown.await;
+                    })
+                    .await;
+            });
+        })
+    })
+}
+
+/// Runs the [RetentionJob] in the background for as long as the server is up
+fn retention_job(config: RetentionConfig) -> AdHoc {
+    AdHoc::on_liftoff("Retention job", |rocket| {
+        Box::pin(async move {
+            let pool = rocket.state::<DbPool>().unwrap().clone();
+            let shutdown = rocket.shutdown();
+            tokio::spawn(async move {
+                RetentionJob::new(pool, config)
+                    .run(async move {
+                        shutdown.await;
+                    })
+                    .await;
+            });
+        })
+    })
+}
+
+/// CORS fairing allowing the configured origins
+fn cors(config: &CorsConfig) -> Cors {
+    let allowed_origins = if config.allows_any_origin() {
+        AllowedOrigins::all()
+    } else {
+        AllowedOrigins::some_exact(&config.allowed_origins)
+    };
+    CorsOptions {
+        allowed_origins,
+        ..Default::default()
     }
+    .to_cors()
+    .expect("CORS origins are validated with the configuration")
 }
 
-pub fn build(pool: DbPool) -> rocket::Rocket<rocket::Build> {
-    rocket::build()
+pub fn build(pool: DbPool, config: AppConfig) -> rocket::Rocket<rocket::Build> {
+    let features = config.features.clone();
+    let mut rocket = rocket::build()
         .manage(pool)
-        .mount_routes()
+        .manage(GameEventHub::new())
+        .manage(GameStateCache::new())
+        .mount_routes(&features)
         .config()
-        .attach(CorsOptions::default().to_cors().unwrap())
+        .attach(cors(&config.cors))
+        .attach(request_id::RequestTracing)
+        .attach(deprecation_headers());
+    // Also managed when disabled, WebSocket sessions check it per frame
+    let limiter = rate_limit::RateLimiter::new(config.rate_limit.clone());
+    if config.rate_limit.enabled {
+        rocket = rocket.attach(rate_limit::RateLimiting(limiter.clone()));
+    }
+    rocket = rocket.manage(limiter);
+    if config.retention.enabled {
+        rocket = rocket.attach(retention_job(config.retention.clone()));
+    }
+    rocket = rocket.manage(config);
+    if features.metrics {
+        rocket = rocket.attach(metrics::RequestMetrics);
+    }
+    if features.game_refresher {
+        rocket = rocket.attach(game_refresher());
+    }
+    if features.webhooks {
+        rocket = rocket.attach(webhook_dispatcher());
+    }
+    rocket
+}
+
+#[cfg(test)]
+mod tests {
+    use rocket::{http::Status, local::blocking::Client};
+
+    use crate::{config::AppConfig, db::test_pool};
+
+    #[test]
+    fn test_legacy_routes_deprecated() {
+        let client = Client::tracked(super::build(test_pool(), AppConfig::default())).unwrap();
+
+        let response = client.get("/game/unknown1/completion").dispatch();
+        assert_eq!(response.status(), Status::NotFound);
+        assert_eq!(
+            response.headers().get_one("Deprecation"),
+            Some("@1792281600")
+        );
+        assert_eq!(
+            response.headers().get_one("Link"),
+            Some("</v1/game/unknown1/completion>; rel=\"successor-version\"")
+        );
+
+        let response = client.get("/v1/game/unknown1/completion").dispatch();
+        assert_eq!(response.status(), Status::NotFound);
+        assert_eq!(response.headers().get_one("Deprecation"), None);
+
+        let response = client.get("/health").dispatch();
+        assert_eq!(response.headers().get_one("Deprecation"), None);
+
+        // Routes added after the unprefixed API was deprecated only exist under /v1
+        let response = client.get("/game/unknown1/timeline").dispatch();
+        assert_eq!(response.status(), Status::NotFound);
+        assert_eq!(response.headers().get_one("Deprecation"), None);
+        let body: serde_json::Value =
+            serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        assert_eq!(body["code"], "not_found");
+        let response = client.get("/v1/game/unknown1/timeline").dispatch();
+        assert_eq!(response.status(), Status::NotFound);
+        let body: serde_json::Value =
+            serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        assert_eq!(body["code"], "game_not_found");
+    }
 }
@@ -0,0 +1,152 @@
+//! OpenAPI document of the HTTP API, served at `/openapi.json` and browsable at `/docs`.
+//!
+//! Routes show up here through their `#[utoipa::path]` attribute and must be listed in [ApiDoc],
+//! which the tests check for every route under `/v1`.
+
+use utoipa::{
+    Modify, OpenApi,
+    openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme},
+};
+use utoipa_swagger_ui::SwaggerUi;
+
+use super::{game, health, invite, leaderboard, webhook, ws};
+use crate::model::series::SeriesBucket;
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "AoC Bingo",
+        description = "Bingo games on top of Advent of Code private leaderboards.\n\n\
+            All routes are versioned under `/v1`. The unprefixed `/game` and `/leaderboard` routes \
+            are deprecated and only kept for existing clients, routes added since only exist under \
+            `/v1`. The unprefixed routes answer with a `Deprecation` header and a `Link` header \
+            pointing to their `/v1` successor. They return the same bodies as `/v1`, except \
+            `GET /game/{id}/completion` which returns completions as positional \
+            `[year, day, part, completed_at]` arrays per member ID.\n\n\
+            Requests are rate limited per client IP and per game. Routes that may fetch from \
+            Advent of Code have the lowest limits. Rejected requests get `429` with a \
+            `Retry-After` header in seconds and the error code `rate_limited`."
+    ),
+    paths(
+        health::health,
+        health::live,
+        health::ready,
+        leaderboard::index,
+        leaderboard::bingo_all,
+        leaderboard::games,
+        game::create,
+        game::update,
+        game::delete,
+        game::archive,
+        game::get_members,
+        game::get_all_puzzles,
+        game::get_completion_v1,
+        game::get_result,
+        game::get_timeline,
+        game::get_score_series,
+        game::events,
+        ws::channel,
+        game::create_membership,
+        game::delete_membership,
+        invite::create,
+        invite::get_all,
+        invite::delete,
+        invite::get_redemptions,
+        invite::decide_redemption,
+        invite::get,
+        invite::redeem,
+        webhook::create,
+        webhook::get_all,
+        webhook::delete,
+        webhook::get_deliveries,
+    ),
+    modifiers(&SessionTokenAuth),
+    // Only used as query parameters, which are not collected automatically
+    components(schemas(game::TimelineOrder, SeriesBucket)),
+    tags(
+        (name = "game", description = "Bingo games and their members"),
+        (name = "invite", description = "Joining games with invite codes"),
+        (name = "webhook", description = "Posting game events to other services"),
+        (name = "leaderboard", description = "Cached AoC leaderboards"),
+        (name = "health", description = "Service status")
+    )
+)]
+pub struct ApiDoc;
+
+/// Security scheme of the routes only a game's owner may use, see [super::auth::SessionToken]
+struct SessionTokenAuth;
+
+impl Modify for SessionTokenAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "session_token",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .description(Some("The AoC session token the game was created with"))
+                    .build(),
+            ),
+        );
+    }
+}
+
+/// Routes serving the OpenAPI document and a bundled Swagger UI
+pub fn routes() -> SwaggerUi {
+    SwaggerUi::new("/docs/<_..>").url("/openapi.json", ApiDoc::openapi())
+}
+
+#[cfg(test)]
+mod tests {
+    use rocket::{http::Status, local::blocking::Client};
+    use utoipa::OpenApi;
+
+    use super::ApiDoc;
+    use crate::{config::AppConfig, db::test_pool};
+
+    #[test]
+    fn test_served() {
+        let client = Client::tracked(crate::api::build(test_pool(), AppConfig::default())).unwrap();
+        let response = client.get("/openapi.json").dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let response = client.get("/docs/").dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        assert!(response.into_string().unwrap().contains("swagger-ui"));
+    }
+
+    #[test]
+    fn test_document() {
+        let doc = serde_json::to_value(ApiDoc::openapi()).unwrap();
+        assert!(doc["paths"]["/v1/game/{id}/timeline"]["get"].is_object());
+        assert!(doc["paths"]["/v1/leaderboard/bingo/all"]["post"].is_object());
+        assert_eq!(
+            doc["components"]["securitySchemes"]["session_token"]["scheme"],
+            "bearer"
+        );
+
+        // Every referenced schema is part of the document
+        let text = doc.to_string();
+        let schemas = doc["components"]["schemas"].as_object().unwrap();
+        for reference in text.split("\"#/components/schemas/").skip(1) {
+            let name = reference.split('"').next().unwrap();
+            assert!(schemas.contains_key(name), "Missing schema {}", name);
+        }
+
+        // Every versioned route is documented
+        let rocket = crate::api::build(test_pool(), AppConfig::default());
+        for route in rocket.routes() {
+            let path = route.uri.path();
+            if !path.starts_with("/v1/") {
+                continue;
+            }
+            let path = path.replace('<', "{").replace('>', "}");
+            let method = route.method.as_str().to_lowercase();
+            assert!(
+                doc["paths"][&path][&method].is_object(),
+                "Missing route {} {}",
+                method,
+                path
+            );
+        }
+    }
+}
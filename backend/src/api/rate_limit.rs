@@ -0,0 +1,534 @@
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use rocket::{
+    Data, Request,
+    fairing::{Fairing, Info, Kind},
+    get,
+    http::{Header, Method, Status, uri::Origin},
+    request::{FromRequest, Outcome},
+    response::{self, Responder},
+};
+use serde_json::json;
+
+use crate::config::{RateLimit, RateLimitConfig, RouteClassLimits};
+
+use super::{API_V1, error::ApiError};
+
+/// Internal route that rejected requests are rerouted to
+const LIMITED_PATH: &str = "/__rate_limited";
+
+/// Above this many tracked buckets, buckets that refilled completely are dropped
+const MAX_TRACKED_BUCKETS: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RouteClass {
+    Standard,
+    GameState,
+    Upstream,
+}
+
+impl RouteClass {
+    fn name(&self) -> &'static str {
+        match self {
+            RouteClass::Standard => "standard",
+            RouteClass::GameState => "game_state",
+            RouteClass::Upstream => "upstream",
+        }
+    }
+
+    fn limits<'c>(&self, config: &'c RateLimitConfig) -> &'c RouteClassLimits {
+        match self {
+            RouteClass::Standard => &config.standard,
+            RouteClass::GameState => &config.game_state,
+            RouteClass::Upstream => &config.upstream,
+        }
+    }
+}
+
+/// Class of a request and the game it concerns, `None` for requests that are never limited.
+///
+/// Requests are classified by path since fairings run before routing.
+fn classify(method: Method, path: &str) -> Option<(RouteClass, Option<&str>)> {
+    let path = path.strip_prefix(API_V1).unwrap_or(path);
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    match (method, segments.as_slice()) {
+        (Method::Options, _) => None,
+        (_, ["leaderboard", ..]) => Some((RouteClass::Upstream, None)),
+        (Method::Post, ["game"]) => Some((RouteClass::Upstream, None)),
+        (_, ["game", id, "members"]) => Some((RouteClass::Upstream, Some(*id))),
+        (Method::Post, ["game", id, "archive"]) => Some((RouteClass::GameState, Some(*id))),
+        (
+            Method::Get,
+            ["game", id, "puzzles", "all"]
+            | [
+                "game",
+                id,
+                "completion" | "result" | "timeline" | "series" | "events",
+            ],
+        ) => Some((RouteClass::GameState, Some(*id))),
+        (_, ["game", id, ..]) => Some((RouteClass::Standard, Some(*id))),
+        (_, ["game"] | ["ws"] | ["invite", ..]) => Some((RouteClass::Standard, None)),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Scope {
+    Client(Option<IpAddr>),
+    Game(String),
+}
+
+/// Token bucket holding up to `requests` tokens, refilled evenly over `period_secs`
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl Bucket {
+    fn full(limit: &RateLimit, now: Instant) -> Self {
+        Bucket {
+            tokens: limit.requests as f64,
+            updated_at: now,
+        }
+    }
+
+    fn refill(&mut self, limit: &RateLimit, now: Instant) {
+        let rate = limit.requests as f64 / limit.period_secs as f64;
+        let elapsed = now.saturating_duration_since(self.updated_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * rate).min(limit.requests as f64);
+        self.updated_at = now;
+    }
+
+    /// How long until a token is available, zero if one is available now
+    fn wait(&self, limit: &RateLimit) -> Duration {
+        if self.tokens >= 1.0 {
+            return Duration::ZERO;
+        }
+        let rate = limit.requests as f64 / limit.period_secs as f64;
+        Duration::from_secs_f64((1.0 - self.tokens) / rate)
+    }
+
+    fn is_full(&self, limit: &RateLimit) -> bool {
+        self.tokens >= limit.requests as f64
+    }
+}
+
+/// Token buckets of every client and game, by route class. Clones share their buckets.
+#[derive(Clone)]
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: Arc<Mutex<HashMap<(RouteClass, Scope), Bucket>>>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        RateLimiter {
+            config,
+            buckets: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Address the request counts towards, only taken from a header if a trusted proxy sets it
+    fn client_ip(&self, request: &Request<'_>) -> Option<IpAddr> {
+        let forwarded = self
+            .config
+            .client_ip_header
+            .as_deref()
+            .and_then(|header| request.headers().get_one(header))
+            .and_then(|ip| ip.trim().parse().ok());
+        forwarded.or_else(|| request.remote().map(|remote| remote.ip()))
+    }
+
+    /// Take a token from every bucket the request counts towards, or none of them if any is
+    /// empty. Returns how long to wait in that case.
+    fn check(
+        &self,
+        class: RouteClass,
+        client: Option<IpAddr>,
+        game_id: Option<&str>,
+        now: Instant,
+    ) -> Result<(), Duration> {
+        if !self.config.enabled {
+            return Ok(());
+        }
+        let limits = class.limits(&self.config);
+        let mut scopes = Vec::with_capacity(2);
+        if let Some(limit) = limits.per_client {
+            scopes.push(((class, Scope::Client(client)), limit));
+        }
+        if let (Some(limit), Some(game_id)) = (limits.per_game, game_id) {
+            scopes.push(((class, Scope::Game(game_id.to_string())), limit));
+        }
+
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() > MAX_TRACKED_BUCKETS {
+            self.prune(&mut buckets, now);
+        }
+
+        let mut wait = Duration::ZERO;
+        for (key, limit) in &scopes {
+            let bucket = buckets
+                .entry(key.clone())
+                .or_insert_with(|| Bucket::full(limit, now));
+            bucket.refill(limit, now);
+            wait = wait.max(bucket.wait(limit));
+        }
+        if !wait.is_zero() {
+            return Err(wait);
+        }
+        for (key, _) in &scopes {
+            buckets.get_mut(key).unwrap().tokens -= 1.0;
+        }
+        Ok(())
+    }
+
+    /// Drop buckets that refilled completely, they are recreated full when needed
+    fn prune(&self, buckets: &mut HashMap<(RouteClass, Scope), Bucket>, now: Instant) {
+        buckets.retain(|(class, scope), bucket| {
+            let limits = class.limits(&self.config);
+            let limit = match scope {
+                Scope::Client(_) => limits.per_client,
+                Scope::Game(_) => limits.per_game,
+            };
+            match limit {
+                Some(limit) => {
+                    bucket.refill(&limit, now);
+                    !bucket.is_full(&limit)
+                }
+                None => false,
+            }
+        });
+    }
+}
+
+/// Why a request was rerouted to [LIMITED_PATH], kept in the request-local cache
+#[derive(Debug, Clone)]
+pub struct Limited {
+    class: RouteClass,
+    retry_after: Duration,
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Limited {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        match request.local_cache(|| None::<Limited>) {
+            Some(limited) => Outcome::Success(limited.clone()),
+            None => Outcome::Forward(Status::NotFound),
+        }
+    }
+}
+
+/// The limits of the requesting client, for work done outside of requests such as WebSocket
+/// frames, which are only classified once when the connection is upgraded
+#[derive(Clone)]
+pub struct ClientLimiter {
+    limiter: RateLimiter,
+    client: Option<IpAddr>,
+}
+
+impl ClientLimiter {
+    /// Count one request of [class] concerning [game_id], see [RateLimiter::check]
+    pub fn check(&self, class: RouteClass, game_id: Option<&str>) -> Result<(), ApiError> {
+        self.limiter
+            .check(class, self.client, game_id, Instant::now())
+            .map_err(|retry_after| limited_error(class, retry_after).0)
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ClientLimiter {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let limiter = request
+            .rocket()
+            .state::<RateLimiter>()
+            .expect("the rate limiter is always managed")
+            .clone();
+        let client = limiter.client_ip(request);
+        Outcome::Success(ClientLimiter { limiter, client })
+    }
+}
+
+/// Rejects requests over their limits with `429 Too Many Requests`
+pub struct RateLimiting(pub RateLimiter);
+
+#[rocket::async_trait]
+impl Fairing for RateLimiting {
+    fn info(&self) -> Info {
+        Info {
+            name: "Rate limiting",
+            kind: Kind::Request,
+        }
+    }
+
+    async fn on_request(&self, request: &mut Request<'_>, _: &mut Data<'_>) {
+        let Some((class, game_id)) = classify(request.method(), request.uri().path().as_str())
+        else {
+            return;
+        };
+        let client = self.0.client_ip(request);
+        let Err(retry_after) = self.0.check(class, client, game_id, Instant::now()) else {
+            return;
+        };
+
+        tracing::warn!(
+            class = class.name(),
+            client = ?client,
+            game_id,
+            "Rate limit exceeded"
+        );
+        request.local_cache(|| Some(Limited { class, retry_after }));
+        request.set_method(Method::Get);
+        request.set_uri(Origin::parse(LIMITED_PATH).unwrap());
+    }
+}
+
+/// Error response with a `Retry-After` header
+pub struct TooManyRequests {
+    error: ApiError,
+    retry_after_secs: u64,
+}
+
+impl<'r> Responder<'r, 'static> for TooManyRequests {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        let mut response = self.error.respond_to(request)?;
+        response.set_header(Header::new(
+            "Retry-After",
+            self.retry_after_secs.to_string(),
+        ));
+        Ok(response)
+    }
+}
+
+/// Error for a request of [class] over its limits, and the seconds to wait before retrying
+fn limited_error(class: RouteClass, retry_after: Duration) -> (ApiError, u64) {
+    // Retry-After only has second precision, rounding down would invite an early retry
+    let retry_after_secs = retry_after.as_secs_f64().ceil().max(1.0) as u64;
+    let error = ApiError::new(
+        Status::TooManyRequests,
+        "rate_limited",
+        "Too many requests, try again later",
+    )
+    .with_details(json!({
+        "class": class.name(),
+        "retry_after_secs": retry_after_secs,
+    }));
+    (error, retry_after_secs)
+}
+
+/// Target of requests rejected by [RateLimiting], not found when requested directly
+#[get("/__rate_limited")]
+pub fn rate_limited(limited: Limited) -> TooManyRequests {
+    let (error, retry_after_secs) = limited_error(limited.class, limited.retry_after);
+    TooManyRequests {
+        error,
+        retry_after_secs,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        net::{IpAddr, Ipv4Addr},
+        time::{Duration, Instant},
+    };
+
+    use rocket::{
+        http::{Header, Method, Status},
+        local::blocking::Client,
+    };
+
+    use super::{ClientLimiter, RateLimiter, RouteClass, classify};
+    use crate::{
+        config::{AppConfig, RateLimit, RateLimitConfig, RouteClassLimits},
+        db::test_pool,
+    };
+
+    fn config() -> RateLimitConfig {
+        RateLimitConfig {
+            game_state: RouteClassLimits {
+                per_client: Some(RateLimit {
+                    requests: 2,
+                    period_secs: 10,
+                }),
+                per_game: Some(RateLimit {
+                    requests: 3,
+                    period_secs: 30,
+                }),
+            },
+            ..RateLimitConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(
+            classify(Method::Get, "/v1/game/abcd1234/timeline"),
+            Some((RouteClass::GameState, Some("abcd1234")))
+        );
+        assert_eq!(
+            classify(Method::Post, "/game/abcd1234/members"),
+            Some((RouteClass::Upstream, Some("abcd1234")))
+        );
+        assert_eq!(
+            classify(Method::Post, "/v1/leaderboard/bingo/all"),
+            Some((RouteClass::Upstream, None))
+        );
+        assert_eq!(
+            classify(Method::Get, "/v1/game/abcd1234/webhooks"),
+            Some((RouteClass::Standard, Some("abcd1234")))
+        );
+        assert_eq!(
+            classify(Method::Post, "/v1/invite/abcdefgh12345678"),
+            Some((RouteClass::Standard, None))
+        );
+        assert_eq!(classify(Method::Get, "/health/ready"), None);
+        assert_eq!(classify(Method::Options, "/v1/game"), None);
+    }
+
+    #[test]
+    fn test_buckets() {
+        let limiter = RateLimiter::new(config());
+        let alice = Some(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)));
+        let bob = Some(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)));
+        let start = Instant::now();
+        let check = |client, at: u64| {
+            limiter.check(
+                RouteClass::GameState,
+                client,
+                Some("abcd1234"),
+                start + Duration::from_secs(at),
+            )
+        };
+
+        assert_eq!(check(alice, 0), Ok(()));
+        assert_eq!(check(alice, 0), Ok(()));
+        // One request per 5 seconds for a client
+        assert_eq!(check(alice, 0), Err(Duration::from_secs(5)));
+        assert_eq!(check(bob, 0), Ok(()));
+        // The game allowed 3 requests and refills one every 10 seconds
+        assert_eq!(check(bob, 5), Err(Duration::from_secs(5)));
+        assert_eq!(check(alice, 10), Ok(()));
+    }
+
+    #[test]
+    fn test_too_many_requests() {
+        let config = AppConfig {
+            rate_limit: config(),
+            ..AppConfig::default()
+        };
+        let client = Client::tracked(crate::api::build(test_pool(), config)).unwrap();
+
+        for _ in 0..2 {
+            let response = client.get("/v1/game/unknown1/timeline").dispatch();
+            assert_eq!(response.status(), Status::NotFound);
+        }
+        let response = client.get("/v1/game/unknown1/timeline").dispatch();
+        assert_eq!(response.status(), Status::TooManyRequests);
+        assert_eq!(response.headers().get_one("Retry-After"), Some("5"));
+        let body: serde_json::Value =
+            serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        assert_eq!(body["code"], "rate_limited");
+        assert_eq!(body["details"]["class"], "game_state");
+
+        // Other classes have their own buckets
+        let response = client.get("/v1/game/unknown1/webhooks").dispatch();
+        assert_eq!(response.status(), Status::Unauthorized);
+
+        let response = client.get("/__rate_limited").dispatch();
+        assert_eq!(response.status(), Status::NotFound);
+    }
+
+    #[test]
+    fn test_client_ip_header() {
+        let build = |rate_limit| {
+            let config = AppConfig {
+                rate_limit,
+                ..AppConfig::default()
+            };
+            Client::tracked(crate::api::build(test_pool(), config)).unwrap()
+        };
+        let get = |client: &Client, game_id: &str, ip: &str| {
+            client
+                .get(format!("/v1/game/{}/timeline", game_id))
+                .header(Header::new("X-Real-IP", ip.to_string()))
+                .dispatch()
+                .status()
+        };
+
+        // Without a trusted proxy the header is ignored, rotating it gives no fresh bucket
+        let client = build(config());
+        assert_eq!(get(&client, "unknown1", "10.0.0.1"), Status::NotFound);
+        assert_eq!(get(&client, "unknown2", "10.0.0.2"), Status::NotFound);
+        assert_eq!(
+            get(&client, "unknown3", "10.0.0.3"),
+            Status::TooManyRequests
+        );
+        let response = client
+            .get("/v1/game/unknown4/timeline")
+            .remote("10.0.0.9:4000".parse().unwrap())
+            .dispatch();
+        assert_eq!(response.status(), Status::NotFound);
+
+        let client = build(RateLimitConfig {
+            client_ip_header: Some("X-Real-IP".to_string()),
+            ..config()
+        });
+        assert_eq!(get(&client, "unknown1", "10.0.0.1"), Status::NotFound);
+        assert_eq!(get(&client, "unknown2", "10.0.0.1"), Status::NotFound);
+        assert_eq!(get(&client, "unknown3", "10.0.0.2"), Status::NotFound);
+        assert_eq!(
+            get(&client, "unknown4", "10.0.0.1"),
+            Status::TooManyRequests
+        );
+    }
+
+    #[test]
+    fn test_client_limiter() {
+        let limiter = RateLimiter::new(config());
+        let client = ClientLimiter {
+            limiter: limiter.clone(),
+            client: Some(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1))),
+        };
+        assert!(
+            client
+                .check(RouteClass::GameState, Some("abcd1234"))
+                .is_ok()
+        );
+        assert!(
+            client
+                .check(RouteClass::GameState, Some("abcd1234"))
+                .is_ok()
+        );
+        let error = client
+            .check(RouteClass::GameState, Some("abcd1234"))
+            .unwrap_err();
+        assert_eq!(error.code, "rate_limited");
+        // Clones share their buckets with the requests limited by [RateLimiting]
+        assert!(
+            limiter
+                .check(RouteClass::GameState, client.client, None, Instant::now())
+                .is_err()
+        );
+
+        let disabled = ClientLimiter {
+            limiter: RateLimiter::new(RateLimitConfig {
+                enabled: false,
+                ..config()
+            }),
+            client: None,
+        };
+        for _ in 0..3 {
+            assert!(disabled.check(RouteClass::GameState, None).is_ok());
+        }
+    }
+}
@@ -0,0 +1,164 @@
+use std::convert::Infallible;
+
+use rand::Rng;
+use rocket::{
+    Data, Request, Response, Route,
+    fairing::{Fairing, Info, Kind},
+    http::Header,
+    request::{FromRequest, Outcome},
+    route::{self, Handler},
+};
+use tracing::{Instrument, Span};
+
+/// Header carrying the request ID, taken from the request if present and always set on the
+/// response
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+/// Longest request ID accepted from clients, longer ones are replaced
+const MAX_REQUEST_ID_LEN: usize = 64;
+
+/// ID of the current request, also recorded on every log line emitted while handling it
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequestId(pub String);
+
+impl RequestId {
+    fn generate() -> Self {
+        let bytes: [u8; 8] = rand::thread_rng().r#gen();
+        RequestId(hex::encode(bytes))
+    }
+
+    /// An ID from a client or proxy, if it is safe to log and echo back
+    fn from_header(value: &str) -> Option<Self> {
+        let valid = !value.is_empty()
+            && value.len() <= MAX_REQUEST_ID_LEN
+            && value
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+        valid.then(|| RequestId(value.to_string()))
+    }
+}
+
+/// Request ID and span of a request, kept in the request-local cache
+struct RequestContext {
+    id: RequestId,
+    span: Span,
+}
+
+fn context<'r>(request: &'r Request<'_>) -> &'r RequestContext {
+    request.local_cache(|| {
+        let id = request
+            .headers()
+            .get_one(REQUEST_ID_HEADER)
+            .and_then(RequestId::from_header)
+            .unwrap_or_else(RequestId::generate);
+        let span = tracing::info_span!(
+            "request",
+            request_id = %id.0,
+            method = %request.method(),
+            path = %request.uri().path(),
+        );
+        RequestContext { id, span }
+    })
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for RequestId {
+    type Error = Infallible;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(context(request).id.clone())
+    }
+}
+
+/// Assigns every request an ID and logs its outcome
+pub struct RequestTracing;
+
+#[rocket::async_trait]
+impl Fairing for RequestTracing {
+    fn info(&self) -> Info {
+        Info {
+            name: "Request tracing",
+            kind: Kind::Request | Kind::Response,
+        }
+    }
+
+    async fn on_request(&self, request: &mut Request<'_>, _: &mut Data<'_>) {
+        context(request);
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        let context = context(request);
+        response.set_header(Header::new(REQUEST_ID_HEADER, context.id.0.clone()));
+
+        let status = response.status().code;
+        let route = request.route().map(|route| route.uri.path());
+        let _entered = context.span.enter();
+        if status >= 500 {
+            tracing::error!(status, route, "Request failed");
+        } else {
+            tracing::info!(status, route, "Request completed");
+        }
+    }
+}
+
+/// Runs a route's handler inside the span of the request, so everything logged by services,
+/// repositories and clients on its behalf carries the request ID
+#[derive(Clone)]
+struct Traced(Box<dyn Handler>);
+
+#[rocket::async_trait]
+impl Handler for Traced {
+    async fn handle<'r>(&self, request: &'r Request<'_>, data: Data<'r>) -> route::Outcome<'r> {
+        let span = context(request).span.clone();
+        self.0.handle(request, data).instrument(span).await
+    }
+}
+
+/// [routes] with their handlers running in the span of the request
+pub fn traced(routes: impl Into<Vec<Route>>) -> Vec<Route> {
+    routes
+        .into()
+        .into_iter()
+        .map(|mut route| {
+            route.handler = Box::new(Traced(route.handler));
+            route
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use rocket::{http::Header, local::blocking::Client};
+
+    use super::{REQUEST_ID_HEADER, RequestId};
+    use crate::{config::AppConfig, db::test_pool};
+
+    #[test]
+    fn test_request_id_from_header() {
+        assert_eq!(
+            RequestId::from_header("abc-123"),
+            Some(RequestId("abc-123".to_string()))
+        );
+        assert_eq!(RequestId::from_header(""), None);
+        assert_eq!(RequestId::from_header("a b"), None);
+        assert_eq!(RequestId::from_header(&"a".repeat(65)), None);
+    }
+
+    #[test]
+    fn test_response_header() {
+        let client = Client::tracked(crate::api::build(test_pool(), AppConfig::default())).unwrap();
+
+        let response = client
+            .get("/health")
+            .header(Header::new(REQUEST_ID_HEADER, "abc-123"))
+            .dispatch();
+        assert_eq!(
+            response.headers().get_one(REQUEST_ID_HEADER),
+            Some("abc-123")
+        );
+
+        let response = client.get("/v1/game/unknown1/timeline").dispatch();
+        let id = response.headers().get_one(REQUEST_ID_HEADER).unwrap();
+        assert_eq!(id.len(), 16);
+    }
+}
@@ -0,0 +1,237 @@
+use rocket::{State, delete, get, http::Status, post, serde::json::Json};
+use serde::Deserialize;
+
+use crate::{
+    db::DbPool,
+    model::webhook::{GameWebhookDto, WebhookDeliveryDto, WebhookFormat},
+    service::WebhookService,
+};
+
+use super::{auth::SessionToken, error::ApiError};
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct CreateWebhookRequest {
+    pub url: String,
+    pub format: WebhookFormat,
+    /// Event types to post, all but `score_changed` and `member_joined` if omitted
+    pub events: Option<Vec<String>>,
+}
+
+#[derive(serde::Serialize, utoipa::ToSchema)]
+pub struct CreateWebhookResponse {
+    pub webhook: GameWebhookDto,
+    /// Key for verifying the `X-Bingo-Signature` header, not shown again
+    pub secret: String,
+}
+
+/// POST /game/<id>/webhooks - Post events of a game to a URL
+#[utoipa::path(
+    post,
+    path = "/v1/game/{id}/webhooks",
+    tag = "webhook",
+    params(("id" = String, Path, description = "Game ID")),
+    request_body = CreateWebhookRequest,
+    security(("session_token" = [])),
+    responses(
+        (status = 200, body = CreateWebhookResponse),
+        (status = 400, description = "Invalid or private URL, or unknown event", body = ApiError),
+        (status = 401, description = "No session token", body = ApiError),
+        (status = 403, description = "Session token is not the game's", body = ApiError),
+        (status = 404, description = "Game not found", body = ApiError)
+    )
+)]
+#[post("/<id>/webhooks", data = "<req>")]
+pub async fn create(
+    pool: &State<DbPool>,
+    token: SessionToken,
+    id: &str,
+    req: Json<CreateWebhookRequest>,
+) -> Result<Json<CreateWebhookResponse>, ApiError> {
+    let req = req.into_inner();
+    let conn = pool.get()?;
+
+    let webhook = WebhookService::new()
+        .create_webhook(&conn, id, &token.0, &req.url, req.format, req.events)?;
+    Ok(Json(CreateWebhookResponse {
+        secret: webhook.secret.clone(),
+        webhook,
+    }))
+}
+
+#[derive(serde::Serialize, utoipa::ToSchema)]
+pub struct GetWebhooksResponse {
+    pub webhooks: Vec<GameWebhookDto>,
+}
+
+/// GET /game/<id>/webhooks - List the webhooks of a game
+#[utoipa::path(
+    get,
+    path = "/v1/game/{id}/webhooks",
+    tag = "webhook",
+    params(("id" = String, Path, description = "Game ID")),
+    security(("session_token" = [])),
+    responses(
+        (status = 200, body = GetWebhooksResponse),
+        (status = 401, description = "No session token", body = ApiError),
+        (status = 403, description = "Session token is not the game's", body = ApiError),
+        (status = 404, description = "Game not found", body = ApiError)
+    )
+)]
+#[get("/<id>/webhooks")]
+pub async fn get_all(
+    pool: &State<DbPool>,
+    token: SessionToken,
+    id: &str,
+) -> Result<Json<GetWebhooksResponse>, ApiError> {
+    let conn = pool.get()?;
+
+    let webhooks = WebhookService::new().get_webhooks(&conn, id, &token.0)?;
+    Ok(Json(GetWebhooksResponse { webhooks }))
+}
+
+/// DELETE /game/<id>/webhooks/<webhook_id> - Stop posting events to a webhook
+#[utoipa::path(
+    delete,
+    path = "/v1/game/{id}/webhooks/{webhook_id}",
+    tag = "webhook",
+    params(
+        ("id" = String, Path, description = "Game ID"),
+        ("webhook_id" = u32, Path, description = "Webhook ID")
+    ),
+    security(("session_token" = [])),
+    responses(
+        (status = 204, description = "Webhook deleted with its delivery log"),
+        (status = 401, description = "No session token", body = ApiError),
+        (status = 403, description = "Session token is not the game's", body = ApiError),
+        (status = 404, description = "Game or webhook not found", body = ApiError)
+    )
+)]
+#[delete("/<id>/webhooks/<webhook_id>")]
+pub async fn delete(
+    pool: &State<DbPool>,
+    token: SessionToken,
+    id: &str,
+    webhook_id: u32,
+) -> Result<Status, ApiError> {
+    let conn = pool.get()?;
+
+    WebhookService::new().delete_webhook(&conn, id, &token.0, webhook_id)?;
+    Ok(Status::NoContent)
+}
+
+#[derive(serde::Serialize, utoipa::ToSchema)]
+pub struct GetDeliveriesResponse {
+    pub deliveries: Vec<WebhookDeliveryDto>,
+}
+
+/// GET /game/<id>/webhooks/<webhook_id>/deliveries - Recent delivery attempts, newest first
+#[utoipa::path(
+    get,
+    path = "/v1/game/{id}/webhooks/{webhook_id}/deliveries",
+    tag = "webhook",
+    params(
+        ("id" = String, Path, description = "Game ID"),
+        ("webhook_id" = u32, Path, description = "Webhook ID")
+    ),
+    security(("session_token" = [])),
+    responses(
+        (status = 200, body = GetDeliveriesResponse),
+        (status = 401, description = "No session token", body = ApiError),
+        (status = 403, description = "Session token is not the game's", body = ApiError),
+        (status = 404, description = "Game or webhook not found", body = ApiError)
+    )
+)]
+#[get("/<id>/webhooks/<webhook_id>/deliveries")]
+pub async fn get_deliveries(
+    pool: &State<DbPool>,
+    token: SessionToken,
+    id: &str,
+    webhook_id: u32,
+) -> Result<Json<GetDeliveriesResponse>, ApiError> {
+    let conn = pool.get()?;
+
+    let deliveries = WebhookService::new().get_deliveries(&conn, id, &token.0, webhook_id)?;
+    Ok(Json(GetDeliveriesResponse { deliveries }))
+}
+
+#[cfg(test)]
+mod tests {
+    use rocket::{
+        http::{ContentType, Header, Status},
+        local::blocking::Client,
+    };
+
+    use crate::{config::AppConfig, db::test_pool, repository::GameRepository};
+
+    #[test]
+    fn test_owner_only() {
+        let pool = test_pool();
+        {
+            let conn = pool.get().unwrap();
+            GameRepository::new()
+                .create_game(&conn, "abcd1234", 42, "token", &Default::default())
+                .unwrap();
+        }
+        let client = Client::tracked(crate::api::build(pool, AppConfig::default())).unwrap();
+        let bearer = |token: &str| Header::new("Authorization", format!("Bearer {}", token));
+        let body = r#"{"url": "https://1.1.1.1/hook", "format": "json"}"#;
+
+        let response = client
+            .post("/v1/game/abcd1234/webhooks")
+            .header(ContentType::JSON)
+            .body(body)
+            .dispatch();
+        assert_eq!(response.status(), Status::Unauthorized);
+        let response = client
+            .post("/v1/game/abcd1234/webhooks")
+            .header(ContentType::JSON)
+            .header(bearer("other"))
+            .body(body)
+            .dispatch();
+        assert_eq!(response.status(), Status::Forbidden);
+
+        let response = client
+            .post("/v1/game/abcd1234/webhooks")
+            .header(ContentType::JSON)
+            .header(bearer("token"))
+            .body(body)
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let body: serde_json::Value =
+            serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        let webhook_id = body["webhook"]["id"].as_u64().unwrap();
+
+        let response = client
+            .get("/v1/game/abcd1234/webhooks")
+            .header(bearer("other"))
+            .dispatch();
+        assert_eq!(response.status(), Status::Forbidden);
+        let response = client
+            .get(format!(
+                "/v1/game/abcd1234/webhooks/{}/deliveries",
+                webhook_id
+            ))
+            .header(bearer("other"))
+            .dispatch();
+        assert_eq!(response.status(), Status::Forbidden);
+        let response = client
+            .delete(format!("/v1/game/abcd1234/webhooks/{}", webhook_id))
+            .header(bearer("other"))
+            .dispatch();
+        assert_eq!(response.status(), Status::Forbidden);
+
+        let response = client
+            .delete(format!("/v1/game/abcd1234/webhooks/{}", webhook_id))
+            .header(bearer("token"))
+            .dispatch();
+        assert_eq!(response.status(), Status::NoContent);
+
+        let response = client
+            .post("/v1/game/abcd1234/webhooks")
+            .header(ContentType::JSON)
+            .header(bearer("token"))
+            .body(r#"{"url": "http://169.254.169.254/latest", "format": "json"}"#)
+            .dispatch();
+        assert_eq!(response.status(), Status::BadRequest);
+    }
+}
@@ -0,0 +1,370 @@
+//! WebSocket channel for following several games at once, used by lockout and spectator views.
+//!
+//! Connect to `GET /v1/game/ws`. All frames are JSON text frames tagged with a `type` field.
+//!
+//! Client to server:
+//!
+//! - `{"type":"subscribe","game_id":"abcd1234","name":"Alice","member_id":123}` starts following a
+//!   game. `name` and `member_id` are optional and only used for presence, `member_id` marks the
+//!   viewer as playing as that member. Subscribing again updates the presence information.
+//!   Subscribing counts as a request to `GET /v1/game/<id>/puzzles/all` towards the rate limits,
+//!   and a connection follows at most [MAX_SUBSCRIPTIONS] games.
+//! - `{"type":"unsubscribe","game_id":"abcd1234"}` stops following a game.
+//! - `{"type":"ping"}` is answered with `{"type":"pong"}`.
+//!
+//! Server to client:
+//!
+//! - `{"type":"snapshot","game_id":..,"version":0,"card":{..}}` is the full card of a game, sent
+//!   after subscribing and whenever the server could not keep up with diffs.
+//! - `{"type":"diff","game_id":..,"version":n,"changes":[..]}` are incremental changes to the card,
+//!   see [GameCardDiff]. `version` increases by one per diff, on a gap clients should resubscribe.
+//!   Clients drop completions and scores of members removed by a `member_removed` change.
+//! - `{"type":"presence","game_id":..,"viewers":[..]}` lists everyone currently following a game.
+//! - `{"type":"event","game_id":..,"event":{..}}` forwards the events also sent over
+//!   `GET /v1/game/<id>/events`, see [GameEvent].
+//! - `{"type":"error","code":"..","message":".."}` reports a rejected client frame, the connection
+//!   stays open. Codes are the same as in error responses of the HTTP API.
+//!
+//! Cards are built from the same [GameState] as `GET /v1/game/<id>/puzzles/all` and
+//! `GET /v1/game/<id>/completion`.
+
+use std::collections::HashMap;
+
+use rocket::{
+    Shutdown, State,
+    futures::{SinkExt, StreamExt},
+    get,
+    tokio::{select, sync::broadcast::error::RecvError},
+};
+use rocket_ws::{Channel, Message, WebSocket, stream::DuplexStream};
+
+use crate::{
+    db::DbPool,
+    model::{
+        card::{GameCardDiff, GameCardDto},
+        game::{GameEvent, GameId, GameState},
+        leaderboard::AocMemberId,
+    },
+    service::{
+        GameEventHub, GameService,
+        game_events::{GameEventMessage, GamePresenceMessage, ViewerDto},
+    },
+};
+
+use super::{
+    error::ApiError,
+    rate_limit::{ClientLimiter, RouteClass},
+};
+
+/// Games a single connection may follow at once
+pub const MAX_SUBSCRIPTIONS: usize = 20;
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    Subscribe {
+        game_id: GameId,
+        name: Option<String>,
+        member_id: Option<AocMemberId>,
+    },
+    Unsubscribe {
+        game_id: GameId,
+    },
+    Ping,
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage<'a> {
+    Snapshot {
+        game_id: &'a str,
+        version: u64,
+        card: &'a GameCardDto,
+    },
+    Diff {
+        game_id: &'a str,
+        version: u64,
+        changes: Vec<GameCardDiff>,
+    },
+    Presence {
+        game_id: &'a str,
+        viewers: &'a [ViewerDto],
+    },
+    Event {
+        game_id: &'a str,
+        event: &'a GameEvent,
+    },
+    Error {
+        code: &'static str,
+        message: String,
+    },
+    Pong,
+}
+
+impl From<ApiError> for ServerMessage<'_> {
+    fn from(error: ApiError) -> Self {
+        ServerMessage::Error {
+            code: error.code,
+            message: error.message,
+        }
+    }
+}
+
+/// Card last sent to the client for a subscribed game
+struct Subscription {
+    version: u64,
+    card: GameCardDto,
+}
+
+struct Session {
+    id: u64,
+    pool: DbPool,
+    hub: GameEventHub,
+    limiter: ClientLimiter,
+    subscriptions: HashMap<GameId, Subscription>,
+}
+
+type WsResult = rocket_ws::result::Result<()>;
+
+impl Session {
+    async fn send(stream: &mut DuplexStream, message: &ServerMessage<'_>) -> WsResult {
+        // Serializing our own message types cannot fail
+        let text = serde_json::to_string(message).unwrap();
+        stream.send(Message::Text(text)).await
+    }
+
+    async fn run(mut self, mut stream: DuplexStream, mut shutdown: Shutdown) -> WsResult {
+        let mut events = self.hub.subscribe();
+        let mut states = self.hub.subscribe_states();
+        let mut presence = self.hub.subscribe_presence();
+
+        let result = loop {
+            let result = select! {
+                message = stream.next() => match message {
+                    Some(Ok(message)) => self.on_client_message(&mut stream, message).await,
+                    Some(Err(e)) => Err(e),
+                    None => break Ok(()),
+                },
+                event = events.recv() => match event {
+                    Ok(event) => self.on_event(&mut stream, &event).await,
+                    Err(RecvError::Lagged(_)) => Ok(()),
+                    Err(RecvError::Closed) => break Ok(()),
+                },
+                state = states.recv() => match state {
+                    Ok(state) => self.on_state(&mut stream, &state).await,
+                    Err(RecvError::Lagged(_)) => self.resync(&mut stream).await,
+                    Err(RecvError::Closed) => break Ok(()),
+                },
+                message = presence.recv() => match message {
+                    Ok(message) => self.on_presence(&mut stream, &message).await,
+                    Err(RecvError::Lagged(_)) => Ok(()),
+                    Err(RecvError::Closed) => break Ok(()),
+                },
+                _ = &mut shutdown => break Ok(()),
+            };
+            if result.is_err() {
+                break result;
+            }
+        };
+
+        for game_id in self.subscriptions.keys() {
+            self.hub.leave(game_id, self.id);
+        }
+        result
+    }
+
+    async fn on_client_message(&mut self, stream: &mut DuplexStream, message: Message) -> WsResult {
+        let text = match message {
+            Message::Text(text) => text,
+            // Pings are answered by the WebSocket layer, close frames end the stream
+            _ => return Ok(()),
+        };
+        let message = match serde_json::from_str::<ClientMessage>(&text) {
+            Ok(message) => message,
+            Err(e) => {
+                let message = format!("Invalid message: {}", e);
+                let error = ApiError::bad_request("invalid_message", message);
+                return Self::send(stream, &ServerMessage::from(error)).await;
+            }
+        };
+
+        match message {
+            ClientMessage::Subscribe {
+                game_id,
+                name,
+                member_id,
+            } => {
+                if !self.subscriptions.contains_key(&game_id)
+                    && self.subscriptions.len() >= MAX_SUBSCRIPTIONS
+                {
+                    let error = ApiError::bad_request(
+                        "too_many_subscriptions",
+                        format!(
+                            "At most {} games can be followed at once",
+                            MAX_SUBSCRIPTIONS
+                        ),
+                    );
+                    return Self::send(stream, &ServerMessage::from(error)).await;
+                }
+                if let Err(error) = self.limiter.check(RouteClass::GameState, Some(&game_id)) {
+                    return Self::send(stream, &ServerMessage::from(error)).await;
+                }
+
+                let service = GameService::new();
+                let state = match service.get_game_state(&self.pool, &game_id).await {
+                    Ok(state) => state,
+                    Err(e) => {
+                        let error = ApiError::from(e);
+                        return Self::send(stream, &ServerMessage::from(error)).await;
+                    }
+                };
+                let card = GameCardDto::from(&state);
+                Self::send(
+                    stream,
+                    &ServerMessage::Snapshot {
+                        game_id: &game_id,
+                        version: 0,
+                        card: &card,
+                    },
+                )
+                .await?;
+                self.subscriptions
+                    .insert(game_id.clone(), Subscription { version: 0, card });
+                self.hub.join(
+                    &game_id,
+                    ViewerDto {
+                        session_id: self.id,
+                        name: name.unwrap_or_else(|| format!("Spectator #{}", self.id)),
+                        member_id,
+                    },
+                );
+                Ok(())
+            }
+            ClientMessage::Unsubscribe { game_id } => {
+                if self.subscriptions.remove(&game_id).is_some() {
+                    self.hub.leave(&game_id, self.id);
+                }
+                Ok(())
+            }
+            ClientMessage::Ping => Self::send(stream, &ServerMessage::Pong).await,
+        }
+    }
+
+    async fn on_event(
+        &mut self,
+        stream: &mut DuplexStream,
+        message: &GameEventMessage,
+    ) -> WsResult {
+        if !self.subscriptions.contains_key(&message.game_id) {
+            return Ok(());
+        }
+        Self::send(
+            stream,
+            &ServerMessage::Event {
+                game_id: &message.game_id,
+                event: &message.event,
+            },
+        )
+        .await
+    }
+
+    async fn on_state(&mut self, stream: &mut DuplexStream, state: &GameState) -> WsResult {
+        let Some(subscription) = self.subscriptions.get_mut(&state.game.id) else {
+            return Ok(());
+        };
+        let card = GameCardDto::from(state);
+        let changes = subscription.card.diff(&card);
+        if changes.is_empty() {
+            return Ok(());
+        }
+        subscription.version += 1;
+        subscription.card = card;
+        Self::send(
+            stream,
+            &ServerMessage::Diff {
+                game_id: &state.game.id,
+                version: subscription.version,
+                changes,
+            },
+        )
+        .await
+    }
+
+    async fn on_presence(
+        &mut self,
+        stream: &mut DuplexStream,
+        message: &GamePresenceMessage,
+    ) -> WsResult {
+        if !self.subscriptions.contains_key(&message.game_id) {
+            return Ok(());
+        }
+        Self::send(
+            stream,
+            &ServerMessage::Presence {
+                game_id: &message.game_id,
+                viewers: &message.viewers,
+            },
+        )
+        .await
+    }
+
+    /// Missed some game states, send fresh snapshots of every subscribed game
+    async fn resync(&mut self, stream: &mut DuplexStream) -> WsResult {
+        let service = GameService::new();
+        let game_ids: Vec<GameId> = self.subscriptions.keys().cloned().collect();
+        for game_id in game_ids {
+            let state = match service.get_game_state(&self.pool, &game_id).await {
+                Ok(state) => state,
+                Err(e) => {
+                    let error = ApiError::from(e);
+                    Self::send(stream, &ServerMessage::from(error)).await?;
+                    continue;
+                }
+            };
+            let card = GameCardDto::from(&state);
+            Self::send(
+                stream,
+                &ServerMessage::Snapshot {
+                    game_id: &game_id,
+                    version: 0,
+                    card: &card,
+                },
+            )
+            .await?;
+            self.subscriptions
+                .insert(game_id, Subscription { version: 0, card });
+        }
+        Ok(())
+    }
+}
+
+/// GET /game/ws - WebSocket channel for live cards and presence, see the module docs for the protocol
+#[utoipa::path(
+    get,
+    path = "/v1/game/ws",
+    tag = "game",
+    responses(
+        (
+            status = 101,
+            description = "WebSocket of JSON frames: `subscribe`, `unsubscribe` and `ping` from the \
+                client, `snapshot`, `diff`, `presence`, `event`, `pong` and `error` from the server"
+        )
+    )
+)]
+#[get("/ws")]
+pub fn channel(
+    ws: WebSocket,
+    pool: &State<DbPool>,
+    hub: &State<GameEventHub>,
+    limiter: ClientLimiter,
+    shutdown: Shutdown,
+) -> Channel<'static> {
+    let session = Session {
+        id: hub.next_session_id(),
+        pool: pool.inner().clone(),
+        hub: hub.inner().clone(),
+        limiter,
+        subscriptions: HashMap::new(),
+    };
+    ws.channel(move |stream| Box::pin(session.run(stream, shutdown)))
+}
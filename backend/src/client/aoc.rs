@@ -1,5 +1,17 @@
+use reqwest::{StatusCode, redirect::Policy};
+use thiserror::Error;
+
 use crate::client::model::leaderboard::LeaderboardResponse;
 
+#[derive(Error, Debug)]
+pub enum AocClientError {
+    #[error("{0}")]
+    Request(#[from] reqwest::Error),
+    /// AoC redirects to the login page or refuses access when the session token is no longer valid
+    #[error("AoC rejected the session token (HTTP {0})")]
+    InvalidSession(StatusCode),
+}
+
 pub struct AocClient {
     client: reqwest::Client,
 }
@@ -7,17 +19,21 @@ pub struct AocClient {
 impl AocClient {
     pub fn new() -> Self {
         AocClient {
-            client: reqwest::Client::new(),
+            client: reqwest::Client::builder()
+                .redirect(Policy::none())
+                .build()
+                .expect("Failed to build HTTP client"),
         }
     }
 
     /// Fetches leaderboard
+    #[tracing::instrument(skip(self, session_token))]
     pub async fn fetch_leaderboard(
         &self,
         year: u32,
         board_id: u32,
         session_token: &str,
-    ) -> Result<LeaderboardResponse, reqwest::Error> {
+    ) -> Result<LeaderboardResponse, AocClientError> {
         let url = format!(
             "https://adventofcode.com/{}/leaderboard/private/view/{}.json",
             year, board_id
@@ -29,7 +45,19 @@ impl AocClient {
             .send()
             .await?;
 
-        let body = response.json::<LeaderboardResponse>().await?;
+        let status = response.status();
+        tracing::debug!(status = status.as_u16(), "AoC responded");
+        if status.is_redirection()
+            || status == StatusCode::UNAUTHORIZED
+            || status == StatusCode::FORBIDDEN
+        {
+            return Err(AocClientError::InvalidSession(status));
+        }
+
+        let body = response
+            .error_for_status()?
+            .json::<LeaderboardResponse>()
+            .await?;
         Ok(body)
     }
 }
@@ -43,8 +71,39 @@ mod tests {
         let json_data = r#"{"num_days":25,"day1_ts":1606798800,"owner_id":2465123,"members":{"2465123":{"local_score":0,"global_score":0,"name":"Markus Becker","id":2465123,"last_star_ts":1700409015,"completion_day_level":{"1":{"1":{"get_star_ts":1700408539,"star_index":null},"2":{"star_index":null,"get_star_ts":1700409015}}},"stars":2},"724629":{"global_score":0,"name":"LFalch","local_score":90,"stars":20,"last_star_ts":1608944425,"completion_day_level":{"4":{"1":{"star_index":4,"get_star_ts":1607540214},"2":{"star_index":5,"get_star_ts":1607542389}},"11":{"1":{"get_star_ts":1608944425,"star_index":17}},"2":{"2":{"get_star_ts":1607537451,"star_index":1},"1":{"get_star_ts":1607536619,"star_index":0}},"7":{"1":{"star_index":10,"get_star_ts":1607875847},"2":{"star_index":11,"get_star_ts":1607876493}},"3":{"1":{"get_star_ts":1607538546,"star_index":2},"2":{"star_index":3,"get_star_ts":1607539339}},"5":{"1":{"star_index":6,"get_star_ts":1607544423},"2":{"star_index":7,"get_star_ts":1607544819}},"6":{"2":{"star_index":9,"get_star_ts":1607873667},"1":{"get_star_ts":1607873246,"star_index":8}},"1":{"1":{"star_index":null,"get_star_ts":1606850589},"2":{"star_index":null,"get_star_ts":1606850739}},"9":{"2":{"get_star_ts":1607879957,"star_index":15},"1":{"star_index":14,"get_star_ts":1607879542}},"8":{"1":{"get_star_ts":1607877157,"star_index":12},"2":{"get_star_ts":1607877943,"star_index":13}},"10":{"1":{"get_star_ts":1607880859,"star_index":16}}},"id":724629},"1546568":{"local_score":0,"name":"Varvara","global_score":0,"stars":0,"id":1546568,"last_star_ts":0,"completion_day_level":{}},"2320819":{"stars":0,"id":2320819,"completion_day_level":{},"last_star_ts":0,"local_score":0,"name":"gaetjen","global_score":0},"2724821":{"local_score":0,"name":"throwpedro","global_score":0,"stars":0,"id":2724821,"completion_day_level":{},"last_star_ts":0}},"event":"2020"}"#;
         let leaderboard: LeaderboardResponse =
             serde_json::from_str(json_data).expect("Failed to deserialize JSON");
-        assert_eq!(leaderboard.num_days, 25);
+        assert_eq!(leaderboard.num_days, Some(25));
+        assert_eq!(leaderboard.day1_ts, Some(1606798800));
         assert_eq!(leaderboard.owner_id, 2465123);
         assert_eq!(leaderboard.members.len(), 5);
     }
+
+    #[test]
+    fn test_deserialize_anonymous_members() {
+        let json_data = r#"{"event":"2025","day1_ts":1764565200,"owner_id":2465123,"num_days":12,"members":{"2465123":{"id":2465123,"local_score":3,"name":"Markus Becker","last_star_ts":1764585193,"stars":1,"completion_day_level":{"1":{"1":{"get_star_ts":1764585193,"star_index":0}}}},"4242424":{"id":4242424,"local_score":2,"name":null,"last_star_ts":1764597431,"stars":1,"completion_day_level":{"1":{"1":{"get_star_ts":1764597431,"star_index":1}}}},"5151515":{"id":5151515,"local_score":0,"name":null,"last_star_ts":0,"stars":0,"completion_day_level":{}}}}"#;
+        let leaderboard: LeaderboardResponse =
+            serde_json::from_str(json_data).expect("Failed to deserialize JSON");
+        assert_eq!(leaderboard.members.len(), 3);
+
+        let named = &leaderboard.members[&2465123];
+        assert_eq!(named.name.as_deref(), Some("Markus Becker"));
+        assert_eq!(named.display_name(), "Markus Becker");
+
+        let anonymous = &leaderboard.members[&4242424];
+        assert_eq!(anonymous.name, None);
+        assert_eq!(anonymous.display_name(), "(anonymous user #4242424)");
+
+        // Cached leaderboards are stored re-serialized, so null names must survive a round trip
+        let cached = serde_json::to_string(&leaderboard).unwrap();
+        let restored: LeaderboardResponse = serde_json::from_str(&cached).unwrap();
+        assert_eq!(restored.members[&5151515].name, None);
+    }
+
+    #[test]
+    fn test_deserialize_without_calendar_fields() {
+        let json_data = r#"{"event":"2016","owner_id":1,"members":{}}"#;
+        let leaderboard: LeaderboardResponse =
+            serde_json::from_str(json_data).expect("Failed to deserialize JSON");
+        assert_eq!(leaderboard.num_days, None);
+        assert_eq!(leaderboard.day1_ts, None);
+    }
 }
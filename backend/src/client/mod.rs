@@ -1,4 +1,4 @@
 mod aoc;
 pub mod model;
 
-pub use aoc::AocClient;
+pub use aoc::{AocClient, AocClientError};
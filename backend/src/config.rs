@@ -0,0 +1,494 @@
+use std::path::PathBuf;
+
+use reqwest::Url;
+use rocket::figment::{
+    Figment,
+    providers::{Env, Format, Serialized, Toml},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::logging::LogFormat;
+
+/// Environment variable with the path of the config file, `bingo.toml` by default
+pub const CONFIG_FILE_ENV: &str = "BINGO_CONFIG";
+const DEFAULT_CONFIG_FILE: &str = "bingo.toml";
+
+/// Prefix of environment variables overriding the config file, nested keys are separated by
+/// `__`, e.g. `BINGO_DATABASE__POOL__MAX_SIZE=8`
+pub const ENV_PREFIX: &str = "BINGO_";
+
+/// Origin allowed by [CorsConfig] to mean any origin
+pub const ANY_ORIGIN: &str = "*";
+
+#[derive(thiserror::Error, Debug)]
+pub enum ConfigError {
+    #[error("Failed to read configuration: {0}")]
+    Load(#[from] Box<rocket::figment::Error>),
+    #[error("Invalid configuration: {}", .0.join("; "))]
+    Invalid(Vec<String>),
+}
+
+/// Server configuration besides what Rocket reads from `Rocket.toml` and `ROCKET_*` variables
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppConfig {
+    pub database: DatabaseConfig,
+    pub cors: CorsConfig,
+    pub features: FeatureConfig,
+    pub rate_limit: RateLimitConfig,
+    pub retention: RetentionConfig,
+    pub log: LogConfig,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DatabaseConfig {
+    /// SQLite database file, its directory is created if missing
+    pub path: PathBuf,
+    pub pool: PoolConfig,
+    pub pragmas: SqlitePragmas,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        DatabaseConfig {
+            path: PathBuf::from("./data/db.sqlite"),
+            pool: PoolConfig::default(),
+            pragmas: SqlitePragmas::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PoolConfig {
+    pub max_size: u32,
+    /// Idle connections kept open, as many as `max_size` if unset
+    pub min_idle: Option<u32>,
+    /// How long a request waits for a free connection before failing
+    pub connection_timeout_secs: u64,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        PoolConfig {
+            // Reasonable for SQLite with WAL mode
+            max_size: 16,
+            min_idle: None,
+            connection_timeout_secs: 30,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JournalMode {
+    Delete,
+    Truncate,
+    Persist,
+    Memory,
+    Wal,
+    Off,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Synchronous {
+    Off,
+    Normal,
+    Full,
+    Extra,
+}
+
+/// Pragmas run on every new connection
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SqlitePragmas {
+    /// WAL lets readers proceed while a write is in progress
+    pub journal_mode: JournalMode,
+    pub synchronous: Synchronous,
+    pub busy_timeout_ms: u32,
+    /// Page cache size per connection
+    pub cache_size_kib: u32,
+    /// Must stay on: deleting games relies on `ON DELETE CASCADE`
+    pub foreign_keys: bool,
+}
+
+impl Default for SqlitePragmas {
+    fn default() -> Self {
+        SqlitePragmas {
+            journal_mode: JournalMode::Wal,
+            synchronous: Synchronous::Normal,
+            busy_timeout_ms: 5000,
+            cache_size_kib: 64000,
+            foreign_keys: true,
+        }
+    }
+}
+
+impl SqlitePragmas {
+    /// Statements applying the pragmas to a connection
+    pub fn to_sql(&self) -> String {
+        let journal_mode = match self.journal_mode {
+            JournalMode::Delete => "DELETE",
+            JournalMode::Truncate => "TRUNCATE",
+            JournalMode::Persist => "PERSIST",
+            JournalMode::Memory => "MEMORY",
+            JournalMode::Wal => "WAL",
+            JournalMode::Off => "OFF",
+        };
+        let synchronous = match self.synchronous {
+            Synchronous::Off => "OFF",
+            Synchronous::Normal => "NORMAL",
+            Synchronous::Full => "FULL",
+            Synchronous::Extra => "EXTRA",
+        };
+        format!(
+            "PRAGMA journal_mode={};
+             PRAGMA busy_timeout={};
+             PRAGMA cache_size=-{};
+             PRAGMA synchronous={};
+             PRAGMA foreign_keys={};
+             PRAGMA temp_store=MEMORY;",
+            journal_mode,
+            self.busy_timeout_ms,
+            self.cache_size_kib,
+            synchronous,
+            if self.foreign_keys { "ON" } else { "OFF" },
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CorsConfig {
+    /// Origins allowed to call the API from a browser, e.g. `https://bingo.example.com`, or
+    /// [ANY_ORIGIN] to allow every origin
+    pub allowed_origins: Vec<String>,
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        CorsConfig {
+            // The frontend in docker-compose and its dev server
+            allowed_origins: vec![
+                "http://localhost:8001".to_string(),
+                "http://localhost:3000".to_string(),
+            ],
+        }
+    }
+}
+
+impl CorsConfig {
+    pub fn allows_any_origin(&self) -> bool {
+        self.allowed_origins
+            .iter()
+            .any(|origin| origin == ANY_ORIGIN)
+    }
+}
+
+/// Optional parts of the server, all enabled by default
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FeatureConfig {
+    /// Recompute games in the background and publish their events
+    pub game_refresher: bool,
+    /// Deliver game events to registered webhooks
+    pub webhooks: bool,
+    /// Serve `/metrics`
+    pub metrics: bool,
+    /// Serve the OpenAPI document and Swagger UI
+    pub api_docs: bool,
+}
+
+impl Default for FeatureConfig {
+    fn default() -> Self {
+        FeatureConfig {
+            game_refresher: true,
+            webhooks: true,
+            metrics: true,
+            api_docs: true,
+        }
+    }
+}
+
+/// At most `requests` in any `period_secs`, with bursts of up to `requests`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RateLimit {
+    pub requests: u32,
+    pub period_secs: u64,
+}
+
+impl RateLimit {
+    pub const fn per_minute(requests: u32) -> Self {
+        RateLimit {
+            requests,
+            period_secs: 60,
+        }
+    }
+}
+
+/// Limits of a class of routes, each one is left out if unset
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RouteClassLimits {
+    /// Requests of a single client IP
+    pub per_client: Option<RateLimit>,
+    /// Requests concerning a single game, from all clients together
+    pub per_game: Option<RateLimit>,
+}
+
+/// Limits on API requests, by class of route. Health checks, metrics and docs are not limited.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RateLimitConfig {
+    pub enabled: bool,
+    /// Routes that only touch the database
+    pub standard: RouteClassLimits,
+    /// Routes computing a game's state from its leaderboards, which may be fetched from AoC
+    pub game_state: RouteClassLimits,
+    /// Routes fetching from AoC directly: leaderboards, game creation and joining
+    pub upstream: RouteClassLimits,
+    /// Header a trusted reverse proxy puts the client's IP in, e.g. `X-Real-IP`. Clients are
+    /// told apart by the address they connect from if unset, since they could send any header.
+    pub client_ip_header: Option<String>,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        RateLimitConfig {
+            enabled: true,
+            standard: RouteClassLimits {
+                per_client: Some(RateLimit::per_minute(300)),
+                per_game: None,
+            },
+            game_state: RouteClassLimits {
+                per_client: Some(RateLimit::per_minute(60)),
+                per_game: Some(RateLimit::per_minute(600)),
+            },
+            upstream: RouteClassLimits {
+                per_client: Some(RateLimit::per_minute(10)),
+                per_game: Some(RateLimit::per_minute(30)),
+            },
+            client_ip_header: None,
+        }
+    }
+}
+
+/// Automatic deletion of games that are over, along with the session tokens stored for them
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetentionConfig {
+    pub enabled: bool,
+    /// Days a finished or archived game is kept before it is deleted
+    pub keep_finished_days: u32,
+    /// How often expired games are looked for
+    pub interval_secs: u64,
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        RetentionConfig {
+            enabled: true,
+            keep_finished_days: 90,
+            interval_secs: 60 * 60,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogConfig {
+    pub format: LogFormat,
+}
+
+impl AppConfig {
+    /// Defaults, overridden by the config file, overridden by environment variables
+    pub fn figment() -> Figment {
+        let file = std::env::var(CONFIG_FILE_ENV).unwrap_or_else(|_| DEFAULT_CONFIG_FILE.into());
+        Figment::from(Serialized::defaults(AppConfig::default()))
+            .merge(Toml::file(file))
+            .merge(Env::prefixed(ENV_PREFIX).ignore(&["config"]).split("__"))
+    }
+
+    /// Load and validate the configuration, should be called once at startup
+    pub fn load() -> Result<Self, ConfigError> {
+        Self::from_figment(&Self::figment())
+    }
+
+    pub fn from_figment(figment: &Figment) -> Result<Self, ConfigError> {
+        let config: AppConfig = figment.extract().map_err(Box::new)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Check what deserializing alone does not, reporting every problem at once
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mut problems = Vec::new();
+
+        let database = &self.database;
+        if database.path.as_os_str().is_empty() {
+            problems.push("database.path must not be empty".to_string());
+        }
+        let pool = &database.pool;
+        if pool.max_size == 0 {
+            problems.push("database.pool.max_size must be at least 1".to_string());
+        }
+        if let Some(min_idle) = pool.min_idle
+            && min_idle > pool.max_size
+        {
+            problems.push(format!(
+                "database.pool.min_idle ({}) must not exceed max_size ({})",
+                min_idle, pool.max_size
+            ));
+        }
+        if pool.connection_timeout_secs == 0 {
+            problems.push("database.pool.connection_timeout_secs must be at least 1".to_string());
+        }
+        if !database.pragmas.foreign_keys {
+            problems.push("database.pragmas.foreign_keys must not be disabled".to_string());
+        }
+
+        for origin in &self.cors.allowed_origins {
+            if origin != ANY_ORIGIN && !is_origin(origin) {
+                problems.push(format!(
+                    "cors.allowed_origins: {} is not an origin like https://example.com",
+                    origin
+                ));
+            }
+        }
+
+        let classes = [
+            ("standard", &self.rate_limit.standard),
+            ("game_state", &self.rate_limit.game_state),
+            ("upstream", &self.rate_limit.upstream),
+        ];
+        for (name, limits) in classes {
+            let scopes = [
+                ("per_client", limits.per_client),
+                ("per_game", limits.per_game),
+            ];
+            for (scope, limit) in scopes {
+                if let Some(limit) = limit
+                    && (limit.requests == 0 || limit.period_secs == 0)
+                {
+                    problems.push(format!(
+                        "rate_limit.{}.{} needs at least 1 request per at least 1 second",
+                        name, scope
+                    ));
+                }
+            }
+        }
+
+        if self.retention.keep_finished_days == 0 {
+            problems.push("retention.keep_finished_days must be at least 1".to_string());
+        }
+        if self.retention.interval_secs == 0 {
+            problems.push("retention.interval_secs must be at least 1".to_string());
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError::Invalid(problems))
+        }
+    }
+}
+
+/// Whether [origin] is a scheme, host and optional port, without path or trailing slash
+fn is_origin(origin: &str) -> bool {
+    match Url::parse(origin) {
+        Ok(url) => {
+            matches!(url.scheme(), "http" | "https")
+                && url.host().is_some()
+                && url.origin().ascii_serialization() == origin
+        }
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rocket::figment::{
+        Figment,
+        providers::{Format, Serialized, Toml},
+    };
+
+    use super::{AppConfig, ConfigError, JournalMode};
+    use crate::logging::LogFormat;
+
+    fn defaults() -> Figment {
+        Figment::from(Serialized::defaults(AppConfig::default()))
+    }
+
+    #[test]
+    fn test_defaults_are_valid() {
+        assert_eq!(
+            AppConfig::from_figment(&defaults()).unwrap(),
+            AppConfig::default()
+        );
+    }
+
+    #[test]
+    fn test_file_overrides() {
+        let figment = defaults().merge(Toml::string(
+            r#"
+            [database]
+            path = "/var/lib/bingo/bingo.sqlite"
+            pool.max_size = 4
+            pragmas.journal_mode = "delete"
+
+            [cors]
+            allowed_origins = ["https://bingo.example.com"]
+
+            [features]
+            webhooks = false
+
+            [log]
+            format = "json"
+            "#,
+        ));
+        let config = AppConfig::from_figment(&figment).unwrap();
+        assert_eq!(config.database.pool.max_size, 4);
+        assert_eq!(config.database.pool.connection_timeout_secs, 30);
+        assert_eq!(config.database.pragmas.journal_mode, JournalMode::Delete);
+        assert!(
+            config
+                .database
+                .pragmas
+                .to_sql()
+                .contains("journal_mode=DELETE")
+        );
+        assert_eq!(
+            config.cors.allowed_origins,
+            vec!["https://bingo.example.com"]
+        );
+        assert!(!config.features.webhooks);
+        assert!(config.features.game_refresher);
+        assert_eq!(config.log.format, LogFormat::Json);
+    }
+
+    #[test]
+    fn test_invalid() {
+        let figment = defaults()
+            .merge(("database.pool.max_size", 0))
+            .merge((
+                "cors.allowed_origins",
+                vec!["*", "https://ok.example.com", "https://bad.example.com/"],
+            ))
+            .merge(("database.pragmas.foreign_keys", false))
+            .merge(("retention.keep_finished_days", 0));
+        match AppConfig::from_figment(&figment) {
+            Err(ConfigError::Invalid(problems)) => {
+                assert_eq!(problems.len(), 4, "{:?}", problems);
+            }
+            other => panic!("Expected invalid config, got {:?}", other),
+        }
+
+        let figment = defaults().merge(("database.pragmas.synchronous", "sometimes"));
+        assert!(matches!(
+            AppConfig::from_figment(&figment),
+            Err(ConfigError::Load(_))
+        ));
+    }
+}
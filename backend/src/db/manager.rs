@@ -5,6 +5,7 @@ use include_dir::Dir;
 use rusqlite::params;
 
 use super::pool::{DbConnection, DbPool, create_pool};
+use crate::config::DatabaseConfig;
 
 pub struct DatabaseManager {
     pool: DbPool,
@@ -26,10 +27,80 @@ pub enum DbError {
     PoolError(#[from] r2d2::Error),
 }
 
+fn sorted_migrations() -> Box<[&'static include_dir::File<'static>]> {
+    let mut migrations = MIGRATION_DIR.files().collect::<Box<_>>();
+    migrations.sort_by_key(|f| f.path().file_name().unwrap().to_str().unwrap());
+    migrations
+}
+
+/// Embedded migrations not yet recorded as applied in [conn]
+pub fn pending_migrations(conn: &rusqlite::Connection) -> Result<Vec<String>, rusqlite::Error> {
+    let has_table: bool = conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'migrations');",
+        [],
+        |row| row.get(0),
+    )?;
+    let applied: Vec<String> = if has_table {
+        let mut stmt = conn.prepare("SELECT id FROM migrations;")?;
+        stmt.query_map([], |row| row.get(0))?
+            .collect::<Result<_, _>>()?
+    } else {
+        Vec::new()
+    };
+
+    Ok(sorted_migrations()
+        .iter()
+        .map(|f| f.path().file_name().unwrap().to_str().unwrap().to_string())
+        .filter(|id| !applied.contains(id))
+        .collect())
+}
+
+/// In-memory database with all migrations applied, for repository tests
+#[cfg(test)]
+pub fn test_connection() -> rusqlite::Connection {
+    let conn = rusqlite::Connection::open_in_memory().unwrap();
+    conn.execute_batch("PRAGMA foreign_keys=ON;").unwrap();
+    for file in sorted_migrations() {
+        conn.execute_batch(file.contents_utf8().unwrap()).unwrap();
+    }
+    conn
+}
+
+/// Pool over a single in-memory database with all migrations applied, for service tests
+#[cfg(test)]
+pub fn test_pool() -> DbPool {
+    let manager = r2d2_sqlite::SqliteConnectionManager::memory().with_init(|conn| {
+        conn.execute_batch(
+            "PRAGMA foreign_keys=ON;
+             CREATE TABLE migrations (id TEXT PRIMARY KEY, applied_at INTEGER DEFAULT (unixepoch()));",
+        )?;
+        for file in sorted_migrations() {
+            conn.execute_batch(file.contents_utf8().unwrap())?;
+            conn.execute(
+                "INSERT INTO migrations (id) VALUES (?1);",
+                params![file.path().file_name().unwrap().to_str().unwrap()],
+            )?;
+        }
+        Ok(())
+    });
+    // Every connection would be a fresh database, so there must only ever be one
+    r2d2::Pool::builder()
+        .max_size(1)
+        .connection_timeout(std::time::Duration::from_secs(5))
+        .build(manager)
+        .unwrap()
+}
+
 impl DatabaseManager {
-    pub fn new(db_path: &str) -> Result<Self, DbError> {
-        fs::create_dir_all("./data").map_err(DbError::FsError)?;
-        let pool = create_pool(db_path)?;
+    pub fn new(config: &DatabaseConfig) -> Result<Self, DbError> {
+        if let Some(dir) = config
+            .path
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+        {
+            fs::create_dir_all(dir).map_err(DbError::FsError)?;
+        }
+        let pool = create_pool(config)?;
         Ok(DatabaseManager { pool })
     }
 
@@ -42,7 +113,7 @@ impl DatabaseManager {
     }
 
     fn setup_migration_table(&self) -> Result<(), DbError> {
-        println!("Setting up migration table...");
+        tracing::debug!("Setting up migration table");
         let conn = self.get_connection()?;
         conn.execute(
             "CREATE TABLE IF NOT EXISTS migrations (
@@ -66,7 +137,7 @@ impl DatabaseManager {
     }
 
     fn apply_migration(&self, migration_id: &str, migration_sql: &str) {
-        println!("Applying migration: {}", migration_id);
+        tracing::info!(migration_id, "Applying migration");
         let conn = self.get_connection().unwrap();
         conn.execute_batch(migration_sql).unwrap();
 
@@ -79,9 +150,10 @@ impl DatabaseManager {
             let applied_id: String = row.get(0).unwrap();
             let applied_at_secs: i64 = row.get(1).unwrap();
             let applied_at = DateTime::from_timestamp(applied_at_secs, 0).unwrap();
-            println!(
-                "Migration applied: id={}, applied_at={}",
-                applied_id, applied_at
+            tracing::info!(
+                migration_id = %applied_id,
+                %applied_at,
+                "Migration applied"
             );
         }
     }
@@ -89,18 +161,14 @@ impl DatabaseManager {
     pub fn init(&self) {
         self.setup_migration_table().unwrap();
 
-        let migrations = {
-            let mut migrations = MIGRATION_DIR.files().collect::<Box<_>>();
-            migrations.sort_by_key(|f| f.path().file_name().unwrap().to_str().unwrap());
-            migrations
-        };
+        let migrations = sorted_migrations();
 
         let applied_migrations = self.get_applied_migrations().unwrap();
 
         for file in migrations {
             let migration_id = file.path().file_name().unwrap().to_str().unwrap();
             if applied_migrations.iter().any(|a| a == migration_id) {
-                println!("Skipping already applied migration: {}", migration_id);
+                tracing::debug!(migration_id, "Skipping already applied migration");
                 continue;
             }
             let migration_sql = file.contents_utf8().unwrap();
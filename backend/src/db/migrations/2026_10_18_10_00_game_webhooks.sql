@@ -0,0 +1,28 @@
+-- Webhook subscriptions: game events are posted to these URLs
+CREATE TABLE IF NOT EXISTS game_webhooks (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    game_id TEXT NOT NULL,
+    url TEXT NOT NULL,
+    format TEXT NOT NULL CHECK(format IN ('json', 'discord', 'slack')),
+    -- Comma separated event types, NULL subscribes to all events
+    events TEXT,
+    secret TEXT NOT NULL,
+    created_at INTEGER DEFAULT (unixepoch()),
+    FOREIGN KEY (game_id) REFERENCES games(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_game_webhooks_game_id ON game_webhooks(game_id);
+
+-- Delivery log: one row per attempt to post an event to a webhook
+CREATE TABLE IF NOT EXISTS webhook_deliveries (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    webhook_id INTEGER NOT NULL,
+    event TEXT NOT NULL,
+    attempt INTEGER NOT NULL,
+    status_code INTEGER,
+    error TEXT,
+    created_at INTEGER DEFAULT (unixepoch()),
+    FOREIGN KEY (webhook_id) REFERENCES game_webhooks(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_webhook_id ON webhook_deliveries(webhook_id);
@@ -0,0 +1,21 @@
+-- Scoring window and lifecycle of games: scheduled until starts_at, running until ends_at and
+-- finished afterwards. Games without ends_at run forever.
+ALTER TABLE games ADD COLUMN starts_at INTEGER;
+ALTER TABLE games ADD COLUMN ends_at INTEGER;
+ALTER TABLE games ADD COLUMN status TEXT NOT NULL DEFAULT 'running';
+
+-- Existing games scored everything solved after their creation
+UPDATE games SET starts_at = created_at;
+
+-- Frozen outcome of finished games, later leaderboard changes do not affect them
+CREATE TABLE IF NOT EXISTS game_results (
+    game_id TEXT PRIMARY KEY,
+    finished_at INTEGER NOT NULL,
+    winner_id INTEGER,
+    -- JSON array of the final standings
+    standings TEXT NOT NULL,
+    -- JSON of the members, card and completions the standings were computed from
+    final_state TEXT NOT NULL,
+    created_at INTEGER DEFAULT (unixepoch()),
+    FOREIGN KEY (game_id) REFERENCES games(id) ON DELETE CASCADE
+);
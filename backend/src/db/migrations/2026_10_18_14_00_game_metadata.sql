@@ -0,0 +1,6 @@
+-- Descriptive fields of games, all optional. Listed games can be found by the members of
+-- their leaderboard, unlisted ones only through their ID.
+ALTER TABLE games ADD COLUMN title TEXT;
+ALTER TABLE games ADD COLUMN description TEXT;
+ALTER TABLE games ADD COLUMN creator_name TEXT;
+ALTER TABLE games ADD COLUMN visibility TEXT NOT NULL DEFAULT 'unlisted';
@@ -0,0 +1,8 @@
+-- A member joins a game at most once. Existing duplicates keep their first membership.
+DELETE FROM game_memberships
+WHERE id NOT IN (
+    SELECT MIN(id) FROM game_memberships GROUP BY game_id, member_id
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_game_memberships_game_member
+    ON game_memberships(game_id, member_id);
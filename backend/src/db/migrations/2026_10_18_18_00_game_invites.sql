@@ -0,0 +1,35 @@
+-- Invite codes: redeeming one joins its game, or asks the owner to approve joining
+CREATE TABLE IF NOT EXISTS game_invites (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    game_id TEXT NOT NULL,
+    code TEXT NOT NULL UNIQUE,
+    -- NULL never expires
+    expires_at INTEGER,
+    -- NULL allows any number of redemptions
+    max_uses INTEGER,
+    requires_approval INTEGER NOT NULL DEFAULT 0,
+    created_at INTEGER DEFAULT (unixepoch()),
+    FOREIGN KEY (game_id) REFERENCES games(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_game_invites_game_id ON game_invites(game_id);
+
+-- One row per redeemed invite, rejected redemptions do not count against max_uses
+CREATE TABLE IF NOT EXISTS invite_redemptions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    invite_id INTEGER NOT NULL,
+    game_id TEXT NOT NULL,
+    member_id INTEGER NOT NULL,
+    member_name TEXT NOT NULL,
+    status TEXT NOT NULL CHECK(status IN ('pending', 'approved', 'rejected')),
+    -- Membership created on approval
+    membership_id INTEGER,
+    created_at INTEGER DEFAULT (unixepoch()),
+    decided_at INTEGER,
+    FOREIGN KEY (invite_id) REFERENCES game_invites(id) ON DELETE CASCADE,
+    FOREIGN KEY (game_id) REFERENCES games(id) ON DELETE CASCADE,
+    FOREIGN KEY (membership_id) REFERENCES game_memberships(id) ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_invite_redemptions_invite_id ON invite_redemptions(invite_id);
+CREATE INDEX IF NOT EXISTS idx_invite_redemptions_game_id ON invite_redemptions(game_id);
@@ -2,6 +2,8 @@ mod manager;
 mod pool;
 mod transaction;
 
-pub use manager::DatabaseManager;
+pub use manager::{DatabaseManager, DbError, pending_migrations};
+#[cfg(test)]
+pub use manager::{test_connection, test_pool};
 pub use pool::{DbConnection, DbPool};
 pub use transaction::with_transaction;
@@ -1,24 +1,20 @@
+use std::time::Duration;
+
 use r2d2_sqlite::SqliteConnectionManager;
 
+use crate::config::DatabaseConfig;
+
 pub type DbPool = r2d2::Pool<SqliteConnectionManager>;
 pub type DbConnection = r2d2::PooledConnection<SqliteConnectionManager>;
 
-pub fn create_pool(db_path: &str) -> Result<DbPool, r2d2::Error> {
-    let manager = SqliteConnectionManager::file(db_path).with_init(|conn| {
-        // Enable WAL mode for better concurrency (readers don't block writers)
-        conn.execute_batch(
-            "PRAGMA journal_mode=WAL;
-             PRAGMA busy_timeout=5000;
-             PRAGMA cache_size=-64000;
-             PRAGMA synchronous=NORMAL;
-             PRAGMA foreign_keys=ON;
-             PRAGMA temp_store=MEMORY;",
-        )?;
-        Ok(())
-    });
+pub fn create_pool(config: &DatabaseConfig) -> Result<DbPool, r2d2::Error> {
+    let pragmas = config.pragmas.to_sql();
+    let manager = SqliteConnectionManager::file(&config.path)
+        .with_init(move |conn| conn.execute_batch(&pragmas));
 
     r2d2::Pool::builder()
-        .max_size(16) // Reasonable for SQLite with WAL mode
-        .connection_timeout(std::time::Duration::from_secs(30))
+        .max_size(config.pool.max_size)
+        .min_idle(config.pool.min_idle)
+        .connection_timeout(Duration::from_secs(config.pool.connection_timeout_secs))
         .build(manager)
 }
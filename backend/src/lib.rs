@@ -1,6 +1,8 @@
 mod api;
 mod client;
+pub mod config;
 mod db;
+pub mod logging;
 pub mod model;
 pub mod repository;
 pub mod service;
@@ -0,0 +1,30 @@
+use tracing_subscriber::{EnvFilter, fmt};
+
+/// Levels used when `RUST_LOG` is not set. Rocket logs every request at info level, which the
+/// request tracing fairing already does with more context.
+const DEFAULT_FILTER: &str = "info,rocket=warn,hyper=warn";
+
+/// Log output, set through `log.format` in [crate::config::AppConfig]
+#[derive(Debug, Clone, Copy, PartialEq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human readable lines for terminals
+    #[default]
+    Pretty,
+    /// One JSON object per line for log collectors
+    Json,
+}
+
+/// Installs the global subscriber, should be called once at startup before Rocket is ignited.
+///
+/// Levels are taken from `RUST_LOG` in the `tracing_subscriber` filter syntax. Records of crates
+/// using the `log` facade, Rocket included, are forwarded to the same output.
+pub fn init(format: LogFormat) {
+    let filter =
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(DEFAULT_FILTER));
+    let builder = fmt().with_env_filter(filter);
+    match format {
+        LogFormat::Pretty => builder.init(),
+        LogFormat::Json => builder.json().init(),
+    }
+}
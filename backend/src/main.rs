@@ -1,28 +1,42 @@
-use backend::{DatabaseManager, build as build_api};
+use backend::{
+    DatabaseManager, build as build_api, config::AppConfig, logging,
+    service::calendar::EventCalendar,
+};
 
 #[tokio::main]
 async fn main() {
-    let db_manager = DatabaseManager::new("./data/db.sqlite").expect("Failed to create database manager");
+    // Logging is configured too, so problems with the configuration can only be printed
+    let config = match AppConfig::load() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    logging::init(config.log.format);
+    EventCalendar::init_global().expect("Failed to load event calendar");
+
+    let db_manager = DatabaseManager::new(&config.database).expect("Failed to create database manager");
     db_manager.init();
 
-    let rocket = build_api(db_manager.get_pool().clone()).ignite().await.unwrap();
+    let rocket = build_api(db_manager.get_pool().clone(), config).ignite().await.unwrap();
     let rocket_shutdown = rocket.shutdown();
 
     let task = tokio::task::spawn(async {
-        println!("Starting backend server...");
+        tracing::info!("Starting backend server");
         rocket.launch().await.unwrap();
-        println!("Backend server has stopped.");
+        tracing::info!("Backend server has stopped");
     });
 
     tokio::pin!(task);
 
     tokio::select! {
         _ = tokio::signal::ctrl_c() => {
-            println!("Shutdown signal received.");
+            tracing::info!("Shutdown signal received");
             rocket_shutdown.notify();
         }
         _ = &mut task => {
-            println!("Backend server task has completed.");
+            tracing::info!("Backend server task has completed");
         }
     };
 }
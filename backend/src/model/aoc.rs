@@ -1,8 +1,27 @@
 use chrono::{DateTime, Datelike, TimeZone, Utc};
+use thiserror::Error;
 
+use crate::model::leaderboard::{Day, Year};
 use crate::service::aoc_utils::AocUtils;
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum AocModelError {
+    #[error("Invalid AoC part: {0}")]
+    InvalidPart(u32),
+    #[error("Invalid AoC puzzle date: year {0}, day {1}")]
+    InvalidDate(Year, Day),
+    #[error("Invalid timestamp: {0}")]
+    InvalidTimestamp(i64),
+}
+
+/// Converts a unix timestamp (seconds) as stored by us or sent by AoC
+pub fn datetime_from_timestamp(ts: i64) -> Result<DateTime<Utc>, AocModelError> {
+    DateTime::from_timestamp(ts, 0).ok_or(AocModelError::InvalidTimestamp(ts))
+}
+
+#[derive(
+    Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize, utoipa::ToSchema,
+)]
 pub struct AocPuzzle {
     pub date: PuzzleDate,
     pub part: AocPart,
@@ -24,42 +43,59 @@ impl PartialOrd for AocPuzzle {
 }
 
 #[derive(
-    Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, serde::Serialize, serde::Deserialize,
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    serde::Serialize,
+    serde::Deserialize,
+    utoipa::ToSchema,
 )]
 pub enum AocPart {
     One,
     Two,
 }
 
-impl Into<u32> for AocPart {
-    fn into(self) -> u32 {
-        match self {
+impl From<AocPart> for u32 {
+    fn from(part: AocPart) -> Self {
+        match part {
             AocPart::One => 1,
             AocPart::Two => 2,
         }
     }
 }
 
-impl From<u32> for AocPart {
-    fn from(value: u32) -> Self {
+impl TryFrom<u32> for AocPart {
+    type Error = AocModelError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
         match value {
-            1 => AocPart::One,
-            2 => AocPart::Two,
-            _ => panic!("Invalid AoC part: {}", value),
+            1 => Ok(AocPart::One),
+            2 => Ok(AocPart::Two),
+            _ => Err(AocModelError::InvalidPart(value)),
         }
     }
 }
 
-#[derive(Debug, Clone, Eq, serde::Serialize, serde::Deserialize, Hash)]
+#[derive(
+    Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize, Hash, utoipa::ToSchema,
+)]
 pub struct PuzzleDate {
     pub year: u32,
     pub day: u32,
 }
 
 impl PuzzleDate {
-    pub fn to_datetime(&self) -> DateTime<Utc> {
-        Utc.with_ymd_and_hms(self.year as i32, 12, self.day, 0, 0, 0)
-            .unwrap()
+    /// The instant the puzzle unlocks: midnight US Eastern Standard Time, see [AocUtils::unlock_timezone]
+    pub fn unlock_time(&self) -> Result<DateTime<Utc>, AocModelError> {
+        AocUtils::unlock_timezone()
+            .with_ymd_and_hms(self.year as i32, 12, self.day, 0, 0, 0)
+            .single()
+            .map(|unlock| unlock.to_utc())
+            .ok_or(AocModelError::InvalidDate(self.year, self.day))
     }
     pub fn to_aoc_puzzle_part(&self, part: AocPart) -> AocPuzzle {
         AocPuzzle {
@@ -67,42 +103,44 @@ impl PuzzleDate {
             part,
         }
     }
-    pub fn new(year: u32, day: u32) -> Self {
-        if day < 1 || day > 25 {
-            panic!("Invalid year or day for AoC puzzle.");
+    pub fn new(year: u32, day: u32) -> Result<Self, AocModelError> {
+        if !(1..=25).contains(&day) {
+            return Err(AocModelError::InvalidDate(year, day));
         }
-        PuzzleDate { year, day }
+        Ok(PuzzleDate { year, day })
     }
     pub fn latest_of_year(year: u32) -> Option<Self> {
         let now = Utc::now();
         Self::latest_of_year_by_date(year, &now)
     }
+    /// Latest puzzle of [year] that is unlocked at [date], `None` if the event has not started yet
     pub fn latest_of_year_by_date(year: u32, date: &DateTime<Utc>) -> Option<Self> {
-        let current_year = date.year() as u32;
-        if current_year == year && date.month() == 12 {
-            Some(PuzzleDate::new(
-                year,
-                date.day()
-                    .min(AocUtils::get_calendar_size_of_year(year).unwrap()),
-            ))
+        let size = AocUtils::get_calendar_size_of_year(year).ok()?;
+        let local = date.with_timezone(&AocUtils::unlock_timezone());
+        let current_year = local.year() as u32;
+        if current_year < year || (current_year == year && local.month() < 12) {
+            None
+        } else if current_year == year {
+            PuzzleDate::new(year, local.day().min(size)).ok()
         } else {
-            match AocUtils::get_calendar_size_of_year(year) {
-                Ok(size) => Some(PuzzleDate { year, day: size }),
-                Err(_) => None,
-            }
+            Some(PuzzleDate { year, day: size })
         }
     }
 }
 
-impl PartialEq for PuzzleDate {
-    fn eq(&self, other: &Self) -> bool {
-        self.year == other.year && self.day == other.day
+impl TryFrom<(Year, Day)> for PuzzleDate {
+    type Error = AocModelError;
+
+    fn try_from((year, day): (Year, Day)) -> Result<Self, Self::Error> {
+        PuzzleDate::new(year, day)
     }
 }
 
-impl Into<DateTime<Utc>> for PuzzleDate {
-    fn into(self) -> DateTime<Utc> {
-        self.to_datetime()
+impl TryFrom<PuzzleDate> for DateTime<Utc> {
+    type Error = AocModelError;
+
+    fn try_from(date: PuzzleDate) -> Result<Self, Self::Error> {
+        date.unlock_time()
     }
 }
 
@@ -120,3 +158,79 @@ impl PartialOrd for PuzzleDate {
         Some(self.cmp(other))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, TimeZone, Utc};
+
+    use super::{AocModelError, AocPart, PuzzleDate, datetime_from_timestamp};
+
+    #[test]
+    fn test_aoc_part_try_from() {
+        assert_eq!(AocPart::try_from(1), Ok(AocPart::One));
+        assert_eq!(AocPart::try_from(2), Ok(AocPart::Two));
+        assert_eq!(AocPart::try_from(3), Err(AocModelError::InvalidPart(3)));
+        assert_eq!(u32::from(AocPart::Two), 2);
+    }
+
+    #[test]
+    fn test_puzzle_date_new() {
+        assert!(PuzzleDate::new(2024, 1).is_ok());
+        assert!(PuzzleDate::try_from((2024, 25)).is_ok());
+        assert_eq!(
+            PuzzleDate::new(2024, 0),
+            Err(AocModelError::InvalidDate(2024, 0))
+        );
+        assert_eq!(
+            PuzzleDate::new(2024, 26),
+            Err(AocModelError::InvalidDate(2024, 26))
+        );
+    }
+
+    #[test]
+    fn test_unlock_time() {
+        let date = PuzzleDate::new(2024, 1).unwrap();
+        assert_eq!(
+            date.unlock_time().unwrap(),
+            Utc.with_ymd_and_hms(2024, 12, 1, 5, 0, 0).unwrap()
+        );
+        let date = PuzzleDate::new(2024, 25).unwrap();
+        assert_eq!(
+            DateTime::<Utc>::try_from(date).unwrap(),
+            Utc.with_ymd_and_hms(2024, 12, 25, 5, 0, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_latest_of_year_by_date() {
+        let before_event = Utc.with_ymd_and_hms(2024, 12, 1, 4, 59, 59).unwrap();
+        assert_eq!(
+            PuzzleDate::latest_of_year_by_date(2024, &before_event),
+            None
+        );
+
+        let first_unlock = Utc.with_ymd_and_hms(2024, 12, 1, 5, 0, 0).unwrap();
+        assert_eq!(
+            PuzzleDate::latest_of_year_by_date(2024, &first_unlock),
+            Some(PuzzleDate::new(2024, 1).unwrap())
+        );
+
+        let after_event = Utc.with_ymd_and_hms(2025, 1, 1, 4, 0, 0).unwrap();
+        assert_eq!(
+            PuzzleDate::latest_of_year_by_date(2024, &after_event),
+            Some(PuzzleDate::new(2024, 25).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_datetime_from_timestamp() {
+        assert_eq!(
+            datetime_from_timestamp(1733029200).unwrap().to_rfc3339(),
+            "2024-12-01T05:00:00+00:00"
+        );
+        assert_eq!(
+            datetime_from_timestamp(i64::MAX),
+            Err(AocModelError::InvalidTimestamp(i64::MAX))
+        );
+    }
+}
@@ -0,0 +1,270 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, Utc};
+
+use crate::model::{
+    aoc::{AocPuzzle, PuzzleDate},
+    game::{GameMembershipDto, GameState},
+    leaderboard::AocMemberId,
+};
+
+/// Full view of a game's card, as sent to live clients before any [GameCardDiff]
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct GameCardDto {
+    pub members: Vec<GameMembershipDto>,
+    pub cells: Vec<GameCardCellDto>,
+    pub scores: BTreeMap<AocMemberId, u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct GameCardCellDto {
+    pub puzzle: AocPuzzle,
+    /// Ordered by completion time, in lockout mode the first member holds the cell
+    pub completions: Vec<GameCardCompletionDto>,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct GameCardCompletionDto {
+    pub member_id: AocMemberId,
+    pub completed_at: DateTime<Utc>,
+}
+
+/// A single change to a [GameCardDto]
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum GameCardDiff {
+    MemberAdded {
+        membership: GameMembershipDto,
+    },
+    MemberRemoved {
+        member_id: AocMemberId,
+    },
+    CellAdded {
+        cell: GameCardCellDto,
+    },
+    CellRemoved {
+        puzzle: AocPuzzle,
+    },
+    CellCompleted {
+        puzzle: AocPuzzle,
+        completion: GameCardCompletionDto,
+    },
+    ScoreChanged {
+        member_id: AocMemberId,
+        score: u32,
+    },
+}
+
+impl From<&GameState> for GameCardDto {
+    fn from(state: &GameState) -> Self {
+        let mut cells: Vec<GameCardCellDto> = state
+            .puzzles
+            .iter()
+            .map(|puzzle| GameCardCellDto {
+                puzzle: puzzle.clone(),
+                completions: vec![],
+            })
+            .collect();
+        cells.sort_by(|a, b| a.puzzle.cmp(&b.puzzle));
+
+        let index: HashMap<AocPuzzle, usize> = cells
+            .iter()
+            .enumerate()
+            .map(|(i, cell)| (cell.puzzle.clone(), i))
+            .collect();
+        for (&member_id, completions) in &state.completions {
+            for (year, day, part, completed_at) in completions {
+                let puzzle = PuzzleDate {
+                    year: *year,
+                    day: *day,
+                }
+                .to_aoc_puzzle_part(part.clone());
+                if let Some(&i) = index.get(&puzzle) {
+                    cells[i].completions.push(GameCardCompletionDto {
+                        member_id,
+                        completed_at: *completed_at,
+                    });
+                }
+            }
+        }
+        for cell in &mut cells {
+            cell.completions
+                .sort_by_key(|completion| (completion.completed_at, completion.member_id));
+        }
+
+        GameCardDto {
+            members: state.members.clone(),
+            cells,
+            scores: state.scores().into_iter().collect(),
+        }
+    }
+}
+
+impl GameCardDto {
+    /// Changes that turn [self] into [next]
+    pub fn diff(&self, next: &GameCardDto) -> Vec<GameCardDiff> {
+        let mut diffs = Vec::new();
+
+        for member in &self.members {
+            if !next.members.iter().any(|m| m.member_id == member.member_id) {
+                diffs.push(GameCardDiff::MemberRemoved {
+                    member_id: member.member_id,
+                });
+            }
+        }
+        for member in &next.members {
+            if !self.members.iter().any(|m| m.member_id == member.member_id) {
+                diffs.push(GameCardDiff::MemberAdded {
+                    membership: member.clone(),
+                });
+            }
+        }
+
+        for cell in &self.cells {
+            if !next.cells.iter().any(|c| c.puzzle == cell.puzzle) {
+                diffs.push(GameCardDiff::CellRemoved {
+                    puzzle: cell.puzzle.clone(),
+                });
+            }
+        }
+        for cell in &next.cells {
+            match self.cells.iter().find(|c| c.puzzle == cell.puzzle) {
+                None => diffs.push(GameCardDiff::CellAdded { cell: cell.clone() }),
+                Some(previous) => {
+                    for completion in &cell.completions {
+                        if !previous.completions.contains(completion) {
+                            diffs.push(GameCardDiff::CellCompleted {
+                                puzzle: cell.puzzle.clone(),
+                                completion: completion.clone(),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        for (&member_id, &score) in &next.scores {
+            if self.scores.get(&member_id) != Some(&score) {
+                diffs.push(GameCardDiff::ScoreChanged { member_id, score });
+            }
+        }
+
+        diffs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, TimeZone, Utc};
+
+    use super::{GameCardCompletionDto, GameCardDiff, GameCardDto};
+    use crate::model::{
+        aoc::{AocPart, PuzzleDate},
+        game::{
+            GameCompletions, GameDto, GameMembershipDto, GameState, GameStatus, GameVisibility,
+        },
+    };
+
+    fn at(hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 12, 2, hour, 0, 0).unwrap()
+    }
+
+    fn state(members: &[u32], days: &[u32], completions: &[(u32, u32, u32)]) -> GameState {
+        let mut game_completions = GameCompletions::new();
+        for &(member_id, day, hour) in completions {
+            game_completions.entry(member_id).or_default().insert((
+                2024,
+                day,
+                AocPart::One,
+                at(hour),
+            ));
+        }
+        GameState {
+            game: GameDto {
+                id: "abcd1234".to_string(),
+                leaderboard_id: 42,
+                session_token: "token".to_string(),
+                starts_at: at(0),
+                ends_at: None,
+                status: GameStatus::Running,
+                title: None,
+                description: None,
+                creator_name: None,
+                visibility: GameVisibility::Unlisted,
+                created_at: at(0),
+                updated_at: at(0),
+            },
+            members: members
+                .iter()
+                .map(|&member_id| GameMembershipDto {
+                    id: member_id,
+                    game_id: "abcd1234".to_string(),
+                    member_id,
+                    member_name: format!("Member {}", member_id),
+                    created_at: at(0),
+                })
+                .collect(),
+            puzzles: days
+                .iter()
+                .map(|&day| {
+                    PuzzleDate::new(2024, day)
+                        .unwrap()
+                        .to_aoc_puzzle_part(AocPart::One)
+                })
+                .collect(),
+            completions: game_completions,
+            session_expired: false,
+        }
+    }
+
+    #[test]
+    fn test_card_from_state() {
+        let card = GameCardDto::from(&state(&[1, 2], &[2, 1], &[(2, 1, 6), (1, 1, 7)]));
+        assert_eq!(card.cells.len(), 2);
+        assert_eq!(card.cells[0].puzzle.date.day, 1);
+        // First completion holds the cell
+        assert_eq!(card.cells[0].completions[0].member_id, 2);
+        assert_eq!(card.cells[0].completions[1].member_id, 1);
+        assert!(card.cells[1].completions.is_empty());
+        assert_eq!(card.scores[&2], 1);
+    }
+
+    #[test]
+    fn test_card_diff() {
+        let previous = GameCardDto::from(&state(&[1, 2], &[1, 2], &[(1, 1, 6)]));
+        let next = GameCardDto::from(&state(&[1, 3], &[1, 3], &[(1, 1, 6), (3, 1, 7)]));
+
+        let puzzle = |day| {
+            PuzzleDate::new(2024, day)
+                .unwrap()
+                .to_aoc_puzzle_part(AocPart::One)
+        };
+        let diffs = previous.diff(&next);
+        assert_eq!(diffs[0], GameCardDiff::MemberRemoved { member_id: 2 });
+        assert!(
+            matches!(&diffs[1], GameCardDiff::MemberAdded { membership } if membership.member_id == 3)
+        );
+        assert_eq!(diffs[2], GameCardDiff::CellRemoved { puzzle: puzzle(2) });
+        assert_eq!(
+            diffs[3],
+            GameCardDiff::CellCompleted {
+                puzzle: puzzle(1),
+                completion: GameCardCompletionDto {
+                    member_id: 3,
+                    completed_at: at(7),
+                },
+            }
+        );
+        assert!(matches!(&diffs[4], GameCardDiff::CellAdded { cell } if cell.puzzle == puzzle(3)));
+        assert_eq!(
+            diffs[5],
+            GameCardDiff::ScoreChanged {
+                member_id: 3,
+                score: 0
+            }
+        );
+        assert_eq!(diffs.len(), 6);
+
+        assert!(next.diff(&next).is_empty());
+    }
+}
@@ -1,19 +1,197 @@
-use crate::model::leaderboard::{AocLeaderboardId, AocMemberId};
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+};
+
+use crate::model::{
+    aoc::{AocPart, AocPuzzle, PuzzleDate},
+    leaderboard::{AocLeaderboardId, AocMemberId, Day, Year},
+};
 use chrono::{DateTime, Utc};
 
 /// 8-character alphanumeric game ID
 pub type GameId = String;
 
-#[derive(Debug, Clone, serde::Serialize)]
+/// Where a game is in its lifecycle, games only ever move forward
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    serde::Serialize,
+    serde::Deserialize,
+    utoipa::ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum GameStatus {
+    /// Before `starts_at`, stars do not count yet
+    Scheduled,
+    Running,
+    /// After `ends_at`, the game's result is frozen
+    Finished,
+    /// Put away by its owner, frozen like a finished game and no longer refreshed
+    Archived,
+}
+
+impl GameStatus {
+    /// Whether the game's state is frozen and served from its stored result
+    pub fn is_frozen(&self) -> bool {
+        *self >= GameStatus::Finished
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GameStatus::Scheduled => "scheduled",
+            GameStatus::Running => "running",
+            GameStatus::Finished => "finished",
+            GameStatus::Archived => "archived",
+        }
+    }
+}
+
+impl FromStr for GameStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "scheduled" => Ok(GameStatus::Scheduled),
+            "running" => Ok(GameStatus::Running),
+            "finished" => Ok(GameStatus::Finished),
+            "archived" => Ok(GameStatus::Archived),
+            _ => Err(format!("Unknown game status: {}", s)),
+        }
+    }
+}
+
+/// Who can find a game besides those who know its ID
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    serde::Serialize,
+    serde::Deserialize,
+    utoipa::ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum GameVisibility {
+    /// Only reachable through its ID
+    #[default]
+    Unlisted,
+    /// Listed to the members of the game's leaderboard
+    Listed,
+}
+
+impl GameVisibility {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GameVisibility::Unlisted => "unlisted",
+            GameVisibility::Listed => "listed",
+        }
+    }
+}
+
+impl FromStr for GameVisibility {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "unlisted" => Ok(GameVisibility::Unlisted),
+            "listed" => Ok(GameVisibility::Listed),
+            _ => Err(format!("Unknown game visibility: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, utoipa::ToSchema)]
 pub struct GameDto {
     pub id: GameId,
     pub leaderboard_id: AocLeaderboardId,
     pub session_token: String,
+    /// Stars earned before this do not count
+    pub starts_at: DateTime<Utc>,
+    /// Stars earned after this do not count, `None` for games that never end
+    pub ends_at: Option<DateTime<Utc>>,
+    pub status: GameStatus,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    /// Display name of whoever created the game
+    pub creator_name: Option<String>,
+    pub visibility: GameVisibility,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Clone, serde::Serialize)]
+/// Everything the creator of a game may choose besides its leaderboard
+#[derive(Debug, Clone, Default, serde::Deserialize, utoipa::ToSchema)]
+pub struct GameOptions {
+    /// When stars start to count, now by default
+    #[serde(default)]
+    pub starts_at: Option<DateTime<Utc>>,
+    /// When the game finishes and its result is frozen, never by default
+    #[serde(default)]
+    pub ends_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub creator_name: Option<String>,
+    #[serde(default)]
+    pub visibility: GameVisibility,
+}
+
+/// Changes to the descriptive fields of a game. Fields left out stay as they are, empty strings
+/// clear them.
+#[derive(Debug, Clone, Default, serde::Deserialize, utoipa::ToSchema)]
+pub struct GameMetadataPatch {
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub creator_name: Option<String>,
+    #[serde(default)]
+    pub visibility: Option<GameVisibility>,
+}
+
+impl GameDto {
+    /// Status the game is due to be in at [now], never behind its stored status
+    pub fn status_at(&self, now: &DateTime<Utc>) -> GameStatus {
+        let due = if *now < self.starts_at {
+            GameStatus::Scheduled
+        } else if self.ends_at.is_some_and(|ends_at| *now >= ends_at) {
+            GameStatus::Finished
+        } else {
+            GameStatus::Running
+        };
+        due.max(self.status)
+    }
+
+    /// Whether [session_token] is the token the game was created with, which only its owner
+    /// knows. Compares in constant time.
+    pub fn is_owned_by(&self, session_token: &str) -> bool {
+        let (expected, given) = (self.session_token.as_bytes(), session_token.as_bytes());
+        expected.len() == given.len()
+            && expected
+                .iter()
+                .zip(given)
+                .fold(0, |diff, (a, b)| diff | (a ^ b))
+                == 0
+    }
+
+    /// Whether a star earned at [at] is within the game's scoring window
+    pub fn counts_at(&self, at: &DateTime<Utc>) -> bool {
+        *at >= self.starts_at && self.ends_at.is_none_or(|ends_at| *at < ends_at)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
 pub struct GameMembershipDto {
     pub id: u32,
     pub game_id: GameId,
@@ -22,8 +200,557 @@ pub struct GameMembershipDto {
     pub created_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Clone, serde::Serialize)]
+#[derive(Debug, Clone, serde::Serialize, utoipa::ToSchema)]
 pub struct GameLeaderboardMemberDto {
     pub id: AocMemberId,
     pub name: String,
 }
+
+/// A puzzle a member completed: year, day, part and when the star was earned
+pub type Completion = (Year, Day, AocPart, DateTime<Utc>);
+
+/// Completions on the game's card, per game member
+pub type GameCompletions = HashMap<AocMemberId, HashSet<Completion>>;
+
+/// Everything known about a running game at one point in time
+#[derive(Debug, Clone)]
+pub struct GameState {
+    pub game: GameDto,
+    pub members: Vec<GameMembershipDto>,
+    /// The game's card, i.e. all puzzles that count in this game
+    pub puzzles: Vec<AocPuzzle>,
+    pub completions: GameCompletions,
+    /// AoC rejected the game's session token, so the state may be outdated
+    pub session_expired: bool,
+}
+
+/// Place of a member in a game, members with the same score share a rank
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct StandingDto {
+    pub rank: u32,
+    pub member_id: AocMemberId,
+    pub member_name: String,
+    pub score: u32,
+    /// Number of puzzles on the card the member completed
+    pub stars: u32,
+    pub bingo: bool,
+}
+
+/// Final standings of a finished game, as frozen when it finished
+#[derive(Debug, Clone, PartialEq, serde::Serialize, utoipa::ToSchema)]
+pub struct GameResultDto {
+    pub game_id: GameId,
+    pub finished_at: DateTime<Utc>,
+    /// First member to complete the whole card, if anyone did
+    pub winner_id: Option<AocMemberId>,
+    /// Ordered by rank, then member ID
+    pub standings: Vec<StandingDto>,
+}
+
+/// A game as listed to the members of its leaderboard, without its session token
+#[derive(Debug, Clone, serde::Serialize, utoipa::ToSchema)]
+pub struct GameSummaryDto {
+    pub id: GameId,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub creator_name: Option<String>,
+    pub status: GameStatus,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub member_count: u32,
+    /// Leading members, empty if the standings could not be computed
+    pub standings: Vec<StandingDto>,
+}
+
+/// The parts of a [GameState] stored with the result of a finished game, enough to serve it
+/// without looking at the leaderboards again
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct FrozenGameState {
+    pub members: Vec<GameMembershipDto>,
+    pub puzzles: Vec<AocPuzzle>,
+    pub completions: GameCompletions,
+}
+
+impl GameState {
+    /// State of a finished game from what was frozen when it finished
+    pub fn from_frozen(game: GameDto, frozen: FrozenGameState) -> Self {
+        GameState {
+            game,
+            members: frozen.members,
+            puzzles: frozen.puzzles,
+            completions: frozen.completions,
+            session_expired: false,
+        }
+    }
+
+    pub fn frozen(&self) -> FrozenGameState {
+        FrozenGameState {
+            members: self.members.clone(),
+            puzzles: self.puzzles.clone(),
+            completions: self.completions.clone(),
+        }
+    }
+
+    /// Every member ordered by score, highest first
+    pub fn standings(&self) -> Vec<StandingDto> {
+        let scores = self.scores();
+        let mut standings: Vec<StandingDto> = self
+            .members
+            .iter()
+            .map(|member| StandingDto {
+                rank: 0,
+                member_id: member.member_id,
+                member_name: member.member_name.clone(),
+                score: scores.get(&member.member_id).copied().unwrap_or(0),
+                stars: self
+                    .completions
+                    .get(&member.member_id)
+                    .map_or(0, |completions| completions.len() as u32),
+                bingo: self.has_bingo(member.member_id),
+            })
+            .collect();
+        standings.sort_by(|a, b| b.score.cmp(&a.score).then(a.member_id.cmp(&b.member_id)));
+
+        for i in 0..standings.len() {
+            standings[i].rank = if i > 0 && standings[i - 1].score == standings[i].score {
+                standings[i - 1].rank
+            } else {
+                i as u32 + 1
+            };
+        }
+        standings
+    }
+
+    /// Points per member: for every puzzle a member completed, one point for each other member
+    /// who has not completed it or completed it later
+    pub fn scores(&self) -> HashMap<AocMemberId, u32> {
+        let mut completed_at =
+            HashMap::<(Year, Day, &AocPart), Vec<(AocMemberId, DateTime<Utc>)>>::new();
+        for (&member_id, completions) in &self.completions {
+            for (year, day, part, at) in completions {
+                completed_at
+                    .entry((*year, *day, part))
+                    .or_default()
+                    .push((member_id, *at));
+            }
+        }
+
+        let mut scores = HashMap::new();
+        for member in &self.members {
+            let mut score = 0;
+            for (year, day, part, at) in self
+                .completions
+                .get(&member.member_id)
+                .into_iter()
+                .flatten()
+            {
+                let others = &completed_at[&(*year, *day, part)];
+                let beaten_by = others
+                    .iter()
+                    .filter(|(other_id, other_at)| *other_id != member.member_id && other_at <= at)
+                    .count();
+                score += (self.members.len() - 1).saturating_sub(beaten_by) as u32;
+            }
+            scores.insert(member.member_id, score);
+        }
+        scores
+    }
+
+    /// Whether [member_id] completed every puzzle on the card
+    pub fn has_bingo(&self, member_id: AocMemberId) -> bool {
+        let Some(completions) = self.completions.get(&member_id) else {
+            return false;
+        };
+        !self.puzzles.is_empty()
+            && self.puzzles.iter().all(|puzzle| {
+                completions.iter().any(|(year, day, part, _)| {
+                    *year == puzzle.date.year && *day == puzzle.date.day && *part == puzzle.part
+                })
+            })
+    }
+
+    /// The first member to complete the whole card, with the time they did
+    pub fn winner(&self) -> Option<(AocMemberId, DateTime<Utc>)> {
+        self.members
+            .iter()
+            .filter(|member| self.has_bingo(member.member_id))
+            .filter_map(|member| {
+                let completed_at = self
+                    .completions
+                    .get(&member.member_id)?
+                    .iter()
+                    .filter(|(year, day, part, _)| {
+                        self.puzzles.iter().any(|puzzle| {
+                            puzzle.date.year == *year
+                                && puzzle.date.day == *day
+                                && puzzle.part == *part
+                        })
+                    })
+                    .map(|(_, _, _, at)| *at)
+                    .max()?;
+                Some((member.member_id, completed_at))
+            })
+            .min_by_key(|(member_id, at)| (*at, *member_id))
+    }
+
+    /// Events that happened between [previous] and this state.
+    ///
+    /// Stars of members who were not part of [previous] are not reported, they were earned before
+    /// the member joined and are announced by [GameEvent::MemberJoined] instead.
+    pub fn events_since(&self, previous: &GameState) -> Vec<GameEvent> {
+        let mut events = Vec::new();
+        let previous_scores = previous.scores();
+        let scores = self.scores();
+
+        for member in &self.members {
+            let member_id = member.member_id;
+            let was_member = previous.members.iter().any(|m| m.member_id == member_id);
+
+            if was_member {
+                let known = previous.completions.get(&member_id);
+                let mut new_stars: Vec<_> = self
+                    .completions
+                    .get(&member_id)
+                    .into_iter()
+                    .flatten()
+                    .filter(|completion| !known.is_some_and(|known| known.contains(*completion)))
+                    .collect();
+                new_stars.sort_by_key(|(year, day, part, at)| (*at, *year, *day, part.clone()));
+                for (year, day, part, at) in new_stars {
+                    events.push(GameEvent::StarEarned {
+                        member_id,
+                        puzzle: PuzzleDate {
+                            year: *year,
+                            day: *day,
+                        }
+                        .to_aoc_puzzle_part(part.clone()),
+                        completed_at: *at,
+                    });
+                }
+            }
+
+            let score = scores.get(&member_id).copied().unwrap_or(0);
+            let previous_score = previous_scores.get(&member_id).copied().unwrap_or(0);
+            if score != previous_score {
+                events.push(GameEvent::ScoreChanged {
+                    member_id,
+                    score,
+                    previous_score,
+                });
+            }
+
+            if self.has_bingo(member_id) && !previous.has_bingo(member_id) {
+                events.push(GameEvent::BingoAchieved { member_id });
+            }
+        }
+
+        if previous.winner().is_none()
+            && let Some((member_id, _)) = self.winner()
+        {
+            events.push(GameEvent::GameWon { member_id });
+        }
+        if self.session_expired && !previous.session_expired {
+            events.push(GameEvent::SessionExpired);
+        }
+        events
+    }
+}
+
+/// Something that happened in a game, pushed to clients following the game live
+#[derive(Debug, Clone, PartialEq, serde::Serialize, utoipa::ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum GameEvent {
+    StarEarned {
+        member_id: AocMemberId,
+        puzzle: AocPuzzle,
+        completed_at: DateTime<Utc>,
+    },
+    ScoreChanged {
+        member_id: AocMemberId,
+        score: u32,
+        previous_score: u32,
+    },
+    BingoAchieved {
+        member_id: AocMemberId,
+    },
+    MemberJoined {
+        membership: GameMembershipDto,
+    },
+    /// First member to complete the whole card
+    GameWon {
+        member_id: AocMemberId,
+    },
+    /// AoC rejected the game's session token, the game no longer updates
+    SessionExpired,
+}
+
+impl GameEvent {
+    /// All event names, see [GameEvent::name]
+    pub const NAMES: [&'static str; 6] = [
+        "star_earned",
+        "score_changed",
+        "bingo_achieved",
+        "member_joined",
+        "game_won",
+        "session_expired",
+    ];
+
+    /// Name of the event, as used for the SSE `event:` field
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameEvent::StarEarned { .. } => "star_earned",
+            GameEvent::ScoreChanged { .. } => "score_changed",
+            GameEvent::BingoAchieved { .. } => "bingo_achieved",
+            GameEvent::MemberJoined { .. } => "member_joined",
+            GameEvent::GameWon { .. } => "game_won",
+            GameEvent::SessionExpired => "session_expired",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, TimeZone, Utc};
+
+    use super::{
+        GameCompletions, GameDto, GameEvent, GameMembershipDto, GameState, GameStatus,
+        GameVisibility, StandingDto,
+    };
+    use crate::model::aoc::{AocPart, PuzzleDate};
+
+    fn at(hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 12, 2, hour, 0, 0).unwrap()
+    }
+
+    fn membership(member_id: u32) -> GameMembershipDto {
+        GameMembershipDto {
+            id: member_id,
+            game_id: "abcd1234".to_string(),
+            member_id,
+            member_name: format!("Member {}", member_id),
+            created_at: at(0),
+        }
+    }
+
+    fn state(members: &[u32], completions: &[(u32, u32, AocPart, u32)]) -> GameState {
+        let mut game_completions = GameCompletions::new();
+        for (member_id, day, part, hour) in completions {
+            game_completions.entry(*member_id).or_default().insert((
+                2024,
+                *day,
+                part.clone(),
+                at(*hour),
+            ));
+        }
+        GameState {
+            game: GameDto {
+                id: "abcd1234".to_string(),
+                leaderboard_id: 42,
+                session_token: "token".to_string(),
+                starts_at: at(0),
+                ends_at: None,
+                status: GameStatus::Running,
+                title: None,
+                description: None,
+                creator_name: None,
+                visibility: GameVisibility::Unlisted,
+                created_at: at(0),
+                updated_at: at(0),
+            },
+            members: members.iter().map(|&id| membership(id)).collect(),
+            puzzles: vec![
+                PuzzleDate::new(2024, 1)
+                    .unwrap()
+                    .to_aoc_puzzle_part(AocPart::One),
+                PuzzleDate::new(2024, 1)
+                    .unwrap()
+                    .to_aoc_puzzle_part(AocPart::Two),
+            ],
+            completions: game_completions,
+            session_expired: false,
+        }
+    }
+
+    #[test]
+    fn test_scores() {
+        let state = state(
+            &[1, 2, 3],
+            &[
+                (1, 1, AocPart::One, 6),
+                (2, 1, AocPart::One, 7),
+                (2, 1, AocPart::Two, 8),
+            ],
+        );
+        let scores = state.scores();
+        // Member 1 beat both others on part one
+        assert_eq!(scores[&1], 2);
+        // Member 2 beat member 3 on part one and both others on part two
+        assert_eq!(scores[&2], 3);
+        assert_eq!(scores[&3], 0);
+    }
+
+    #[test]
+    fn test_standings() {
+        let state = state(
+            &[1, 2, 3],
+            &[
+                (1, 1, AocPart::One, 6),
+                (2, 1, AocPart::One, 6),
+                (2, 1, AocPart::Two, 8),
+            ],
+        );
+        let standings: Vec<_> = state
+            .standings()
+            .into_iter()
+            .map(|s| (s.rank, s.member_id, s.score, s.stars, s.bingo))
+            .collect();
+        assert_eq!(
+            standings,
+            vec![(1, 2, 3, 2, true), (2, 1, 1, 1, false), (3, 3, 0, 0, false)]
+        );
+
+        let tied = self::state(&[1, 2], &[]).standings();
+        assert_eq!(
+            tied[1],
+            StandingDto {
+                rank: 1,
+                member_id: 2,
+                member_name: "Member 2".to_string(),
+                score: 0,
+                stars: 0,
+                bingo: false,
+            }
+        );
+    }
+
+    #[test]
+    fn test_status_and_scoring_window() {
+        let mut game = state(&[], &[]).game;
+        game.starts_at = at(6);
+        game.ends_at = Some(at(12));
+        game.status = GameStatus::Scheduled;
+
+        assert_eq!(game.status_at(&at(5)), GameStatus::Scheduled);
+        assert_eq!(game.status_at(&at(6)), GameStatus::Running);
+        assert_eq!(game.status_at(&at(12)), GameStatus::Finished);
+        assert!(!game.counts_at(&at(5)));
+        assert!(game.counts_at(&at(6)));
+        assert!(!game.counts_at(&at(12)));
+
+        // A finished game stays finished
+        game.status = GameStatus::Finished;
+        assert_eq!(game.status_at(&at(7)), GameStatus::Finished);
+        game.status = GameStatus::Archived;
+        assert_eq!(game.status_at(&at(13)), GameStatus::Archived);
+        assert!(game.status.is_frozen());
+    }
+
+    #[test]
+    fn test_is_owned_by() {
+        let game = state(&[], &[]).game;
+        assert!(game.is_owned_by("token"));
+        assert!(!game.is_owned_by("tokem"));
+        assert!(!game.is_owned_by("token2"));
+        assert!(!game.is_owned_by(""));
+    }
+
+    #[test]
+    fn test_has_bingo() {
+        let state = state(
+            &[1, 2],
+            &[
+                (1, 1, AocPart::One, 6),
+                (2, 1, AocPart::One, 7),
+                (2, 1, AocPart::Two, 8),
+            ],
+        );
+        assert!(!state.has_bingo(1));
+        assert!(state.has_bingo(2));
+    }
+
+    #[test]
+    fn test_events_since() {
+        let previous = state(&[1, 2], &[(1, 1, AocPart::One, 6)]);
+        let current = state(
+            &[1, 2, 3],
+            &[
+                (1, 1, AocPart::One, 6),
+                (1, 1, AocPart::Two, 9),
+                (2, 1, AocPart::One, 7),
+                (3, 1, AocPart::One, 5),
+            ],
+        );
+
+        let events = current.events_since(&previous);
+        assert_eq!(
+            events,
+            vec![
+                GameEvent::StarEarned {
+                    member_id: 1,
+                    puzzle: PuzzleDate::new(2024, 1)
+                        .unwrap()
+                        .to_aoc_puzzle_part(AocPart::Two),
+                    completed_at: at(9),
+                },
+                GameEvent::ScoreChanged {
+                    member_id: 1,
+                    score: 3,
+                    previous_score: 1,
+                },
+                GameEvent::BingoAchieved { member_id: 1 },
+                GameEvent::StarEarned {
+                    member_id: 2,
+                    puzzle: PuzzleDate::new(2024, 1)
+                        .unwrap()
+                        .to_aoc_puzzle_part(AocPart::One),
+                    completed_at: at(7),
+                },
+                // Member 3 joined and was already faster than everyone, stars are not replayed
+                GameEvent::ScoreChanged {
+                    member_id: 3,
+                    score: 2,
+                    previous_score: 0,
+                },
+                GameEvent::GameWon { member_id: 1 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_winner() {
+        let state = state(
+            &[1, 2],
+            &[
+                (1, 1, AocPart::One, 6),
+                (1, 1, AocPart::Two, 10),
+                (2, 1, AocPart::One, 7),
+                (2, 1, AocPart::Two, 8),
+            ],
+        );
+        assert_eq!(state.winner(), Some((2, at(8))));
+        assert_eq!(state.events_since(&state), vec![]);
+    }
+
+    #[test]
+    fn test_session_expired_event() {
+        let previous = state(&[1], &[]);
+        let mut current = previous.clone();
+        current.session_expired = true;
+        assert_eq!(
+            current.events_since(&previous),
+            vec![GameEvent::SessionExpired]
+        );
+        assert_eq!(current.events_since(&current), vec![]);
+    }
+
+    #[test]
+    fn test_event_serialization() {
+        let event = GameEvent::BingoAchieved { member_id: 7 };
+        assert_eq!(event.name(), "bingo_achieved");
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"type":"bingo_achieved","member_id":7}"#
+        );
+    }
+}
@@ -0,0 +1,61 @@
+use std::collections::BTreeMap;
+
+/// Outcome of a health check, ordered from best to worst
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, utoipa::ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Pass,
+    /// Degraded, but still able to serve requests
+    Warn,
+    /// Not able to serve requests
+    Fail,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, utoipa::ToSchema)]
+pub struct HealthCheckDto {
+    pub status: CheckStatus,
+    pub message: String,
+    /// Check specific observations, such as ages and pending migrations
+    pub details: Option<serde_json::Value>,
+}
+
+impl HealthCheckDto {
+    pub fn new(status: CheckStatus, message: impl Into<String>) -> Self {
+        HealthCheckDto {
+            status,
+            message: message.into(),
+            details: None,
+        }
+    }
+
+    pub fn with_details(mut self, details: serde_json::Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, utoipa::ToSchema)]
+pub struct ReadinessDto {
+    /// The worst status of all checks
+    pub status: CheckStatus,
+    /// Result of every check by name
+    pub checks: BTreeMap<String, HealthCheckDto>,
+}
+
+impl ReadinessDto {
+    pub fn new(checks: BTreeMap<String, HealthCheckDto>) -> Self {
+        let status = checks
+            .values()
+            .map(|check| check.status)
+            .max()
+            .unwrap_or(CheckStatus::Pass);
+        ReadinessDto { status, checks }
+    }
+
+    /// Whether traffic should be routed to this instance, degraded checks still count as ready
+    pub fn is_ready(&self) -> bool {
+        self.status != CheckStatus::Fail
+    }
+}
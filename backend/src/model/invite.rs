@@ -0,0 +1,91 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+
+use crate::model::{game::GameId, leaderboard::AocMemberId};
+
+/// Where a redemption stands, redemptions of invites without approval are approved right away
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, utoipa::ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum RedemptionStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+impl RedemptionStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RedemptionStatus::Pending => "pending",
+            RedemptionStatus::Approved => "approved",
+            RedemptionStatus::Rejected => "rejected",
+        }
+    }
+}
+
+impl FromStr for RedemptionStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(RedemptionStatus::Pending),
+            "approved" => Ok(RedemptionStatus::Approved),
+            "rejected" => Ok(RedemptionStatus::Rejected),
+            _ => Err(format!("Unknown redemption status: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, utoipa::ToSchema)]
+pub struct GameInviteDto {
+    pub id: u32,
+    pub game_id: GameId,
+    /// Secret part of the invite, anyone who knows it may redeem the invite
+    pub code: String,
+    /// `None` for invites that never expire
+    pub expires_at: Option<DateTime<Utc>>,
+    /// `None` for invites that may be redeemed any number of times
+    pub max_uses: Option<u32>,
+    /// Redemptions so far, rejected ones excluded
+    pub uses: u32,
+    /// Redeeming only asks to join, the owner approves or rejects each redemption
+    pub requires_approval: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+impl GameInviteDto {
+    pub fn is_expired_at(&self, now: &DateTime<Utc>) -> bool {
+        self.expires_at.is_some_and(|expires_at| *now >= expires_at)
+    }
+
+    pub fn is_used_up(&self) -> bool {
+        self.max_uses.is_some_and(|max_uses| self.uses >= max_uses)
+    }
+}
+
+/// Everything the owner may choose when creating an invite
+#[derive(Debug, Clone, Default, serde::Deserialize, utoipa::ToSchema)]
+pub struct InviteOptions {
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub max_uses: Option<u32>,
+    #[serde(default)]
+    pub requires_approval: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, utoipa::ToSchema)]
+pub struct InviteRedemptionDto {
+    pub id: u32,
+    pub invite_id: u32,
+    pub game_id: GameId,
+    pub member_id: AocMemberId,
+    pub member_name: String,
+    pub status: RedemptionStatus,
+    /// Membership the redemption created, `None` until approved or once the member left
+    pub membership_id: Option<u32>,
+    pub created_at: DateTime<Utc>,
+    pub decided_at: Option<DateTime<Utc>>,
+}
@@ -16,7 +16,7 @@ pub type Year = u32;
 pub type Day = u32;
 pub type Part = u32;
 
-#[derive(Debug, Clone, serde::Serialize)]
+#[derive(Debug, Clone, serde::Serialize, utoipa::ToSchema)]
 pub struct LeaderboardDto {
     pub id: AocLeaderboardYearId,
     pub year: Year,
@@ -26,7 +26,7 @@ pub struct LeaderboardDto {
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Clone, serde::Serialize)]
+#[derive(Debug, Clone, serde::Serialize, utoipa::ToSchema)]
 pub struct ShuffleLeaderboardDto {
     pub board_id: AocLeaderboardId,
     pub data: ShuffleLeaderboardDataDto,
@@ -34,27 +34,27 @@ pub struct ShuffleLeaderboardDto {
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Clone, serde::Serialize)]
+#[derive(Debug, Clone, serde::Serialize, utoipa::ToSchema)]
 pub struct ShuffleMemberDto {
     pub id: AocMemberId,
     pub name: String,
 }
 
-#[derive(Debug, Clone, serde::Serialize)]
+#[derive(Debug, Clone, serde::Serialize, utoipa::ToSchema)]
 pub struct ShuffleLeaderboardDataDto {
     pub players: Vec<ShuffleMemberDto>,
     pub days: Vec<ShuffleLeaderboardDayDto>,
     pub members: HashMap<AocMemberId, ShuffleMemberDataDto>,
 }
 
-#[derive(Debug, Clone, serde::Serialize)]
+#[derive(Debug, Clone, serde::Serialize, utoipa::ToSchema)]
 pub struct ShuffleLeaderboardDayDto {
     pub year: Year,
     pub day: Day,
     pub part: Part,
 }
 
-#[derive(Debug, Clone, serde::Serialize)]
+#[derive(Debug, Clone, serde::Serialize, utoipa::ToSchema)]
 pub struct ShuffleMemberDataDto {
     pub day: ShuffleLeaderboardDayDto,
     pub completed_at: DateTime<Utc>,
@@ -1,3 +1,9 @@
 pub mod aoc;
+pub mod card;
 pub mod game;
+pub mod health;
+pub mod invite;
 pub mod leaderboard;
+pub mod series;
+pub mod timeline;
+pub mod webhook;
@@ -0,0 +1,300 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Days, FixedOffset, NaiveDateTime, TimeDelta, TimeZone, Timelike, Utc};
+use chrono_tz::Tz;
+
+use crate::model::{game::GameState, leaderboard::AocMemberId};
+
+/// Upper bound on the number of buckets in a series, to keep responses reasonably small
+pub const MAX_BUCKETS: usize = 5000;
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SeriesBucket {
+    Hour,
+    Day,
+}
+
+impl FromStr for SeriesBucket {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hour" => Ok(SeriesBucket::Hour),
+            "day" => Ok(SeriesBucket::Day),
+            _ => Err(format!("Unknown bucket: {}, expected hour or day", s)),
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum SeriesError {
+    #[error("Too many buckets, at most {0} are supported")]
+    TooManyBuckets(usize),
+}
+
+/// Cumulative standings of a game over time, one value per bucket and member
+#[derive(Debug, Clone, PartialEq, serde::Serialize, utoipa::ToSchema)]
+pub struct ScoreSeriesDto {
+    pub bucket: SeriesBucket,
+    pub timezone: String,
+    /// Start of every bucket in the requested time zone
+    pub buckets: Vec<DateTime<FixedOffset>>,
+    pub members: Vec<MemberSeriesDto>,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, utoipa::ToSchema)]
+pub struct MemberSeriesDto {
+    pub member_id: AocMemberId,
+    pub member_name: String,
+    /// Score at the end of each bucket
+    pub scores: Vec<u32>,
+    /// Stars on the card at the end of each bucket
+    pub stars: Vec<u32>,
+}
+
+/// First instant of the local [time] in [tz], skipping forward over gaps from DST changes
+fn local_start(tz: &Tz, time: NaiveDateTime) -> DateTime<Tz> {
+    let mut time = time;
+    loop {
+        if let Some(start) = tz.from_local_datetime(&time).earliest() {
+            return start;
+        }
+        time += TimeDelta::minutes(30);
+    }
+}
+
+impl SeriesBucket {
+    /// Start of the bucket containing [time]
+    fn start(&self, time: DateTime<Tz>) -> DateTime<Tz> {
+        let local = time.naive_local();
+        match self {
+            // Truncating in absolute time keeps the offset of [time], which matters for the
+            // hour repeated when clocks go back
+            SeriesBucket::Hour => {
+                time - TimeDelta::minutes(local.minute().into())
+                    - TimeDelta::seconds(local.second().into())
+                    - TimeDelta::nanoseconds(local.nanosecond().into())
+            }
+            SeriesBucket::Day => local_start(&time.timezone(), local.date().into()),
+        }
+    }
+
+    /// Start of the bucket following the one starting at [start]
+    fn next(&self, start: DateTime<Tz>) -> DateTime<Tz> {
+        match self {
+            // Hours are stepped in absolute time so repeated and skipped hours around DST
+            // changes come out as one bucket per hour that actually passed
+            SeriesBucket::Hour => start + TimeDelta::hours(1),
+            SeriesBucket::Day => {
+                let date = start.date_naive() + Days::new(1);
+                local_start(&start.timezone(), date.into())
+            }
+        }
+    }
+}
+
+impl GameState {
+    /// Score and star count of every member at the end of each [bucket] from the start of the
+    /// game until it ends or [now], whichever is earlier, with buckets aligned to [tz]. Stars
+    /// earned before the game started count towards the first bucket.
+    pub fn score_series(
+        &self,
+        bucket: SeriesBucket,
+        tz: Tz,
+        now: &DateTime<Utc>,
+    ) -> Result<ScoreSeriesDto, SeriesError> {
+        let until = match self.game.ends_at {
+            Some(ends_at) => ends_at.min(*now),
+            None => *now,
+        };
+        let mut starts = vec![bucket.start(self.game.starts_at.with_timezone(&tz))];
+        loop {
+            let next = bucket.next(*starts.last().unwrap());
+            if next > until {
+                break;
+            }
+            if starts.len() >= MAX_BUCKETS {
+                return Err(SeriesError::TooManyBuckets(MAX_BUCKETS));
+            }
+            starts.push(next);
+        }
+        let ends: Vec<DateTime<Utc>> = starts
+            .iter()
+            .map(|start| bucket.next(*start).with_timezone(&Utc))
+            .collect();
+
+        let timeline = self.timeline();
+        let members = self
+            .members
+            .iter()
+            .map(|member| {
+                let entries: Vec<_> = timeline
+                    .iter()
+                    .filter(|entry| entry.member_id == member.member_id)
+                    .collect();
+                let mut scores = Vec::with_capacity(ends.len());
+                let mut stars = Vec::with_capacity(ends.len());
+                for end in &ends {
+                    let earned = entries.iter().take_while(|entry| entry.completed_at < *end);
+                    stars.push(earned.clone().count() as u32);
+                    scores.push(earned.last().map(|entry| entry.score).unwrap_or(0));
+                }
+                MemberSeriesDto {
+                    member_id: member.member_id,
+                    member_name: member.member_name.clone(),
+                    scores,
+                    stars,
+                }
+            })
+            .collect();
+
+        Ok(ScoreSeriesDto {
+            bucket,
+            timezone: tz.name().to_string(),
+            buckets: starts.iter().map(|start| start.fixed_offset()).collect(),
+            members,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, TimeZone, Utc};
+    use chrono_tz::Tz;
+
+    use super::SeriesBucket;
+    use crate::model::{
+        aoc::{AocPart, PuzzleDate},
+        game::{
+            GameCompletions, GameDto, GameMembershipDto, GameState, GameStatus, GameVisibility,
+        },
+    };
+
+    fn at(day: u32, hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 12, day, hour, 0, 0).unwrap()
+    }
+
+    fn state(starts_at: DateTime<Utc>, completions: &[(u32, u32, DateTime<Utc>)]) -> GameState {
+        let mut game_completions = GameCompletions::new();
+        for &(member_id, day, completed_at) in completions {
+            game_completions.entry(member_id).or_default().insert((
+                2024,
+                day,
+                AocPart::One,
+                completed_at,
+            ));
+        }
+        GameState {
+            game: GameDto {
+                id: "abcd1234".to_string(),
+                leaderboard_id: 42,
+                session_token: "token".to_string(),
+                starts_at,
+                ends_at: None,
+                status: GameStatus::Running,
+                title: None,
+                description: None,
+                creator_name: None,
+                visibility: GameVisibility::Unlisted,
+                created_at: starts_at,
+                updated_at: starts_at,
+            },
+            members: [1, 2]
+                .iter()
+                .map(|&member_id| GameMembershipDto {
+                    id: member_id,
+                    game_id: "abcd1234".to_string(),
+                    member_id,
+                    member_name: format!("Member {}", member_id),
+                    created_at: starts_at,
+                })
+                .collect(),
+            puzzles: (1..=2)
+                .map(|day| {
+                    PuzzleDate::new(2024, day)
+                        .unwrap()
+                        .to_aoc_puzzle_part(AocPart::One)
+                })
+                .collect(),
+            completions: game_completions,
+            session_expired: false,
+        }
+    }
+
+    #[test]
+    fn test_daily_series_in_time_zone() {
+        let state = state(
+            at(1, 12),
+            &[(1, 1, at(1, 10)), (2, 1, at(1, 23)), (2, 2, at(2, 5))],
+        );
+        let tz: Tz = "America/New_York".parse().unwrap();
+        let series = state
+            .score_series(SeriesBucket::Day, tz, &at(3, 12))
+            .unwrap();
+
+        // Local days in New York start at 05:00 UTC in December
+        let buckets: Vec<_> = series.buckets.iter().map(|b| b.to_rfc3339()).collect();
+        assert_eq!(
+            buckets,
+            vec![
+                "2024-12-01T00:00:00-05:00",
+                "2024-12-02T00:00:00-05:00",
+                "2024-12-03T00:00:00-05:00",
+            ]
+        );
+        assert_eq!(series.members[0].stars, vec![1, 1, 1]);
+        assert_eq!(series.members[0].scores, vec![1, 1, 1]);
+        // The star at 05:00 UTC on the 2nd belongs to the next local day
+        assert_eq!(series.members[1].stars, vec![1, 2, 2]);
+        assert_eq!(series.members[1].scores, vec![0, 1, 1]);
+    }
+
+    #[test]
+    fn test_hourly_series_across_dst_change() {
+        // Clocks in Berlin went back from 03:00 to 02:00 on 2024-10-27, at 01:00 UTC
+        let starts_at = Utc.with_ymd_and_hms(2024, 10, 26, 23, 30, 0).unwrap();
+        let now = Utc.with_ymd_and_hms(2024, 10, 27, 2, 30, 0).unwrap();
+        let tz: Tz = "Europe/Berlin".parse().unwrap();
+        let series = state(starts_at, &[])
+            .score_series(SeriesBucket::Hour, tz, &now)
+            .unwrap();
+
+        let buckets: Vec<_> = series.buckets.iter().map(|b| b.to_rfc3339()).collect();
+        assert_eq!(
+            buckets,
+            vec![
+                "2024-10-27T01:00:00+02:00",
+                "2024-10-27T02:00:00+02:00",
+                "2024-10-27T02:00:00+01:00",
+                "2024-10-27T03:00:00+01:00",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ended_game() {
+        let mut state = state(at(1, 12), &[(1, 1, at(1, 10)), (1, 2, at(2, 5))]);
+        state.game.ends_at = Some(at(2, 12));
+
+        // A year later the series still stops at the end of the game
+        let now = Utc.with_ymd_and_hms(2025, 12, 3, 12, 0, 0).unwrap();
+        let series = state
+            .score_series(SeriesBucket::Hour, Tz::UTC, &now)
+            .unwrap();
+        assert_eq!(series.buckets.len(), 25);
+        assert_eq!(series.buckets[0], at(1, 12).fixed_offset());
+        assert_eq!(series.buckets[24], at(2, 12).fixed_offset());
+        assert_eq!(series.members[0].stars.last(), Some(&2));
+    }
+
+    #[test]
+    fn test_too_many_buckets() {
+        let state = state(Utc.with_ymd_and_hms(2015, 12, 1, 0, 0, 0).unwrap(), &[]);
+        assert!(
+            state
+                .score_series(SeriesBucket::Hour, Tz::UTC, &at(1, 0))
+                .is_err()
+        );
+    }
+}
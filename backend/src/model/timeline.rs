@@ -0,0 +1,241 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+
+use crate::model::{
+    aoc::{AocPuzzle, PuzzleDate},
+    game::GameState,
+    leaderboard::AocMemberId,
+};
+
+/// A star earned on a game's card, with its effect on the standings
+#[derive(Debug, Clone, PartialEq, serde::Serialize, utoipa::ToSchema)]
+pub struct GameTimelineEntryDto {
+    /// Position in the chronological feed, starting at 1
+    pub sequence: u32,
+    pub member_id: AocMemberId,
+    pub member_name: String,
+    pub puzzle: AocPuzzle,
+    pub completed_at: DateTime<Utc>,
+    /// Points awarded for this star, see [GameState::scores]
+    pub points: u32,
+    /// Score of the member after this star
+    pub score: u32,
+    /// Every member whose rank changed with this star, members with equal scores share a rank
+    pub rank_changes: Vec<RankChangeDto>,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, utoipa::ToSchema)]
+pub struct RankChangeDto {
+    pub member_id: AocMemberId,
+    pub previous_rank: u32,
+    pub rank: u32,
+}
+
+/// Rank per member, members with equal scores share a rank
+fn ranks(scores: &HashMap<AocMemberId, u32>) -> HashMap<AocMemberId, u32> {
+    scores
+        .iter()
+        .map(|(&member_id, score)| {
+            let ahead = scores.values().filter(|other| *other > score).count();
+            (member_id, ahead as u32 + 1)
+        })
+        .collect()
+}
+
+impl GameState {
+    /// Every star earned on the card in chronological order.
+    ///
+    /// A star is worth one point for each other member who had not earned it yet, so adding up
+    /// the points of a member's entries gives their score from [GameState::scores].
+    pub fn timeline(&self) -> Vec<GameTimelineEntryDto> {
+        let mut stars: Vec<_> = self
+            .members
+            .iter()
+            .flat_map(|member| {
+                self.completions
+                    .get(&member.member_id)
+                    .into_iter()
+                    .flatten()
+                    .map(move |completion| (member, completion))
+            })
+            .collect();
+        stars.sort_by_key(|(member, (year, day, part, at))| {
+            (*at, *year, *day, part.clone(), member.member_id)
+        });
+
+        let mut scores: HashMap<AocMemberId, u32> =
+            self.members.iter().map(|m| (m.member_id, 0)).collect();
+        let mut current_ranks = ranks(&scores);
+
+        let mut entries = Vec::with_capacity(stars.len());
+        for (sequence, (member, (year, day, part, at))) in stars.iter().enumerate() {
+            let beaten_by = stars
+                .iter()
+                .filter(|(other, (other_year, other_day, other_part, other_at))| {
+                    other.member_id != member.member_id
+                        && other_year == year
+                        && other_day == day
+                        && other_part == part
+                        && other_at <= at
+                })
+                .count();
+            let points = (self.members.len() - 1).saturating_sub(beaten_by) as u32;
+
+            let score = scores.entry(member.member_id).or_default();
+            *score += points;
+            let score = *score;
+
+            let next_ranks = ranks(&scores);
+            let mut rank_changes: Vec<_> = next_ranks
+                .iter()
+                .filter(|(member_id, rank)| current_ranks.get(*member_id) != Some(*rank))
+                .map(|(&member_id, &rank)| RankChangeDto {
+                    member_id,
+                    previous_rank: current_ranks[&member_id],
+                    rank,
+                })
+                .collect();
+            rank_changes.sort_by_key(|change| (change.rank, change.member_id));
+            current_ranks = next_ranks;
+
+            entries.push(GameTimelineEntryDto {
+                sequence: sequence as u32 + 1,
+                member_id: member.member_id,
+                member_name: member.member_name.clone(),
+                puzzle: PuzzleDate {
+                    year: *year,
+                    day: *day,
+                }
+                .to_aoc_puzzle_part(part.clone()),
+                completed_at: *at,
+                points,
+                score,
+                rank_changes,
+            });
+        }
+        entries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use chrono::{DateTime, TimeZone, Utc};
+
+    use super::RankChangeDto;
+    use crate::model::{
+        aoc::{AocPart, PuzzleDate},
+        game::{
+            GameCompletions, GameDto, GameMembershipDto, GameState, GameStatus, GameVisibility,
+        },
+    };
+
+    fn at(hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 12, 2, hour, 0, 0).unwrap()
+    }
+
+    fn state(members: &[u32], completions: &[(u32, u32, u32)]) -> GameState {
+        let mut game_completions = GameCompletions::new();
+        for &(member_id, day, hour) in completions {
+            game_completions.entry(member_id).or_default().insert((
+                2024,
+                day,
+                AocPart::One,
+                at(hour),
+            ));
+        }
+        GameState {
+            game: GameDto {
+                id: "abcd1234".to_string(),
+                leaderboard_id: 42,
+                session_token: "token".to_string(),
+                starts_at: at(0),
+                ends_at: None,
+                status: GameStatus::Running,
+                title: None,
+                description: None,
+                creator_name: None,
+                visibility: GameVisibility::Unlisted,
+                created_at: at(0),
+                updated_at: at(0),
+            },
+            members: members
+                .iter()
+                .map(|&member_id| GameMembershipDto {
+                    id: member_id,
+                    game_id: "abcd1234".to_string(),
+                    member_id,
+                    member_name: format!("Member {}", member_id),
+                    created_at: at(0),
+                })
+                .collect(),
+            puzzles: (1..=2)
+                .map(|day| {
+                    PuzzleDate::new(2024, day)
+                        .unwrap()
+                        .to_aoc_puzzle_part(AocPart::One)
+                })
+                .collect(),
+            completions: game_completions,
+            session_expired: false,
+        }
+    }
+
+    #[test]
+    fn test_timeline() {
+        let state = state(&[1, 2, 3], &[(2, 1, 6), (1, 1, 7), (1, 2, 8), (3, 2, 8)]);
+        let timeline = state.timeline();
+
+        let summary: Vec<_> = timeline
+            .iter()
+            .map(|entry| {
+                (
+                    entry.sequence,
+                    entry.member_id,
+                    entry.puzzle.date.day,
+                    entry.points,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![(1, 2, 1, 2), (2, 1, 1, 1), (3, 1, 2, 1), (4, 3, 2, 1)]
+        );
+
+        // Member 2 takes the lead, then member 1 ties with them
+        assert_eq!(
+            timeline[0].rank_changes,
+            vec![
+                RankChangeDto {
+                    member_id: 1,
+                    previous_rank: 1,
+                    rank: 2
+                },
+                RankChangeDto {
+                    member_id: 3,
+                    previous_rank: 1,
+                    rank: 2
+                },
+            ]
+        );
+        assert_eq!(
+            timeline[2].rank_changes,
+            vec![RankChangeDto {
+                member_id: 1,
+                previous_rank: 2,
+                rank: 1
+            }]
+        );
+
+        let mut totals = HashMap::new();
+        for entry in &timeline {
+            *totals.entry(entry.member_id).or_insert(0) += entry.points;
+            assert_eq!(totals[&entry.member_id], entry.score);
+        }
+        assert_eq!(totals[&1], state.scores()[&1]);
+        assert_eq!(totals[&2], state.scores()[&2]);
+        assert_eq!(totals[&3], state.scores()[&3]);
+    }
+}
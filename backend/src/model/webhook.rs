@@ -0,0 +1,68 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+
+use crate::model::game::GameId;
+
+/// Shape of the body posted to a webhook
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, utoipa::ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookFormat {
+    /// `{"game_id", "event", "sent_at"}` with the event as sent over SSE
+    Json,
+    /// Discord `execute webhook` message
+    Discord,
+    /// Slack incoming webhook message
+    Slack,
+}
+
+impl WebhookFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebhookFormat::Json => "json",
+            WebhookFormat::Discord => "discord",
+            WebhookFormat::Slack => "slack",
+        }
+    }
+}
+
+impl FromStr for WebhookFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(WebhookFormat::Json),
+            "discord" => Ok(WebhookFormat::Discord),
+            "slack" => Ok(WebhookFormat::Slack),
+            _ => Err(format!("Unknown webhook format: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, utoipa::ToSchema)]
+pub struct GameWebhookDto {
+    pub id: u32,
+    pub game_id: GameId,
+    pub url: String,
+    pub format: WebhookFormat,
+    /// Event types posted to this webhook, see [crate::model::game::GameEvent::name]
+    pub events: Vec<String>,
+    /// Key for the HMAC signature, only handed out once when the webhook is created
+    #[serde(skip_serializing)]
+    pub secret: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, utoipa::ToSchema)]
+pub struct WebhookDeliveryDto {
+    pub id: u32,
+    pub webhook_id: u32,
+    pub event: String,
+    pub attempt: u32,
+    /// HTTP status of the response, `None` if no response was received
+    pub status_code: Option<u16>,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
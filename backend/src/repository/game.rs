@@ -1,7 +1,11 @@
-use chrono::DateTime;
-use rusqlite::{params, Connection, Row};
+use chrono::{DateTime, Utc};
+use rusqlite::{Connection, OptionalExtension, Row, params, types::Type};
 
-use crate::model::game::{GameDto, GameMembershipDto};
+use super::{get_datetime, get_optional_datetime};
+use crate::model::game::{
+    FrozenGameState, GameDto, GameMembershipDto, GameOptions, GameResultDto, GameStatus,
+    GameVisibility, StandingDto,
+};
 use crate::model::leaderboard::{AocLeaderboardId, AocMemberId};
 
 pub struct GameRepository;
@@ -13,15 +17,30 @@ impl TryFrom<&Row<'_>> for GameDto {
         let id: String = row.get("id")?;
         let leaderboard_id: i64 = row.get("leaderboard_id")?;
         let session_token: String = row.get("session_token")?;
-        let created_at: i64 = row.get("created_at")?;
-        let updated_at: i64 = row.get("updated_at")?;
+        let status_idx = row.as_ref().column_index("status")?;
+        let status: String = row.get(status_idx)?;
+        let status = status.parse::<GameStatus>().map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(status_idx, Type::Text, e.into())
+        })?;
+        let visibility_idx = row.as_ref().column_index("visibility")?;
+        let visibility: String = row.get(visibility_idx)?;
+        let visibility = visibility.parse::<GameVisibility>().map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(visibility_idx, Type::Text, e.into())
+        })?;
 
         Ok(GameDto {
             id,
             leaderboard_id: leaderboard_id as AocLeaderboardId,
             session_token,
-            created_at: DateTime::from_timestamp(created_at, 0).unwrap(),
-            updated_at: DateTime::from_timestamp(updated_at, 0).unwrap(),
+            starts_at: get_datetime(row, "starts_at")?,
+            ends_at: get_optional_datetime(row, "ends_at")?,
+            status,
+            title: row.get("title")?,
+            description: row.get("description")?,
+            creator_name: row.get("creator_name")?,
+            visibility,
+            created_at: get_datetime(row, "created_at")?,
+            updated_at: get_datetime(row, "updated_at")?,
         })
     }
 }
@@ -34,71 +53,251 @@ impl TryFrom<&Row<'_>> for GameMembershipDto {
         let game_id: String = row.get("game_id")?;
         let member_id: i64 = row.get("member_id")?;
         let member_name: String = row.get("member_name")?;
-        let created_at: i64 = row.get("created_at")?;
 
         Ok(GameMembershipDto {
             id: id as u32,
             game_id,
             member_id: member_id as AocMemberId,
             member_name,
-            created_at: DateTime::from_timestamp(created_at, 0).unwrap(),
+            created_at: get_datetime(row, "created_at")?,
         })
     }
 }
 
+impl TryFrom<&Row<'_>> for GameResultDto {
+    type Error = rusqlite::Error;
+
+    fn try_from(row: &Row) -> Result<Self, Self::Error> {
+        let game_id: String = row.get("game_id")?;
+        let winner_id: Option<i64> = row.get("winner_id")?;
+        let standings_idx = row.as_ref().column_index("standings")?;
+        let standings: String = row.get(standings_idx)?;
+        let standings = serde_json::from_str::<Vec<StandingDto>>(&standings).map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(standings_idx, Type::Text, e.into())
+        })?;
+
+        Ok(GameResultDto {
+            game_id,
+            finished_at: get_datetime(row, "finished_at")?,
+            winner_id: winner_id.map(|id| id as AocMemberId),
+            standings,
+        })
+    }
+}
+
+impl Default for GameRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl GameRepository {
     pub fn new() -> Self {
         GameRepository
     }
 
-    /// Create a new game with the given ID
+    /// Create a new game with the given ID, starting now unless [options] say otherwise and
+    /// scheduled if it starts in the future
     pub fn create_game(
         &self,
         conn: &Connection,
         id: &str,
         leaderboard_id: u32,
         session_token: &str,
+        options: &GameOptions,
     ) -> Result<GameDto, rusqlite::Error> {
         let mut statement = conn.prepare(
-            "INSERT INTO games (id, leaderboard_id, session_token)
-             VALUES (?1, ?2, ?3)
+            "INSERT INTO games (id, leaderboard_id, session_token, starts_at, ends_at, status,
+                                title, description, creator_name, visibility)
+             VALUES (?1, ?2, ?3, COALESCE(?4, unixepoch()), ?5,
+                     CASE WHEN ?4 > unixepoch() THEN 'scheduled' ELSE 'running' END,
+                     ?6, ?7, ?8, ?9)
              RETURNING *;",
         )?;
-        let mut rows = statement.query(params![id, leaderboard_id as i64, session_token])?;
+        let mut rows = statement.query(params![
+            id,
+            leaderboard_id as i64,
+            session_token,
+            options.starts_at.map(|at| at.timestamp()),
+            options.ends_at.map(|at| at.timestamp()),
+            options.title,
+            options.description,
+            options.creator_name,
+            options.visibility.as_str(),
+        ])?;
 
-        if let Some(row) = rows.next()? {
-            GameDto::try_from(row)
-        } else {
-            panic!("Expected game to be returned after insert.")
+        match rows.next()? {
+            Some(row) => GameDto::try_from(row),
+            None => Err(rusqlite::Error::QueryReturnedNoRows),
         }
     }
 
     /// Get a game by its ID
-    pub fn get_game(&self, conn: &Connection, id: &str) -> Option<GameDto> {
-        let mut statement = conn.prepare("SELECT * FROM games WHERE id = ?1;").ok()?;
-        let mut rows = statement.query(params![id]).ok()?;
-        rows.next().ok()?.and_then(|row| GameDto::try_from(row).ok())
+    pub fn get_game(
+        &self,
+        conn: &Connection,
+        id: &str,
+    ) -> Result<Option<GameDto>, rusqlite::Error> {
+        let mut statement = conn.prepare("SELECT * FROM games WHERE id = ?1;")?;
+        let mut rows = statement.query(params![id])?;
+        match rows.next()? {
+            Some(row) => GameDto::try_from(row).map(Some),
+            None => Ok(None),
+        }
     }
 
     /// Get all games (optional - for listing/debugging)
-    pub fn get_all_games(&self, conn: &Connection) -> Vec<GameDto> {
-        let mut statement = conn
-            .prepare("SELECT * FROM games ORDER BY created_at DESC;")
-            .unwrap();
-        let rows = statement
-            .query_map([], |row| GameDto::try_from(row))
-            .unwrap();
+    pub fn get_all_games(&self, conn: &Connection) -> Result<Vec<GameDto>, rusqlite::Error> {
+        let mut statement = conn.prepare("SELECT * FROM games ORDER BY created_at DESC;")?;
+        let rows = statement.query_map([], |row| GameDto::try_from(row))?;
 
         let mut games = Vec::new();
         for row_result in rows {
             match row_result {
                 Ok(dto) => games.push(dto),
                 Err(e) => {
-                    eprintln!("Failed to convert row to GameDto: {:?}", e);
+                    tracing::warn!(error = ?e, "Failed to convert row to GameDto");
                 }
             }
         }
-        games
+        Ok(games)
+    }
+
+    /// Delete a game, its memberships, webhooks and result go with it
+    pub fn delete_game(&self, conn: &Connection, id: &str) -> Result<(), rusqlite::Error> {
+        conn.execute("DELETE FROM games WHERE id = ?1;", params![id])?;
+        Ok(())
+    }
+
+    /// Delete every game whose result is older than [before], returning how many were deleted
+    pub fn delete_games_finished_before(
+        &self,
+        conn: &Connection,
+        before: DateTime<Utc>,
+    ) -> Result<usize, rusqlite::Error> {
+        conn.execute(
+            "DELETE FROM games
+             WHERE id IN (SELECT game_id FROM game_results WHERE finished_at < ?1);",
+            params![before.timestamp()],
+        )
+    }
+
+    /// Replace the descriptive fields of a game
+    pub fn update_metadata(
+        &self,
+        conn: &Connection,
+        id: &str,
+        title: Option<&str>,
+        description: Option<&str>,
+        creator_name: Option<&str>,
+        visibility: GameVisibility,
+    ) -> Result<(), rusqlite::Error> {
+        conn.execute(
+            "UPDATE games SET title = ?2, description = ?3, creator_name = ?4, visibility = ?5
+             WHERE id = ?1;",
+            params![id, title, description, creator_name, visibility.as_str()],
+        )?;
+        Ok(())
+    }
+
+    /// Move a game to [status]
+    pub fn update_status(
+        &self,
+        conn: &Connection,
+        id: &str,
+        status: GameStatus,
+    ) -> Result<(), rusqlite::Error> {
+        conn.execute(
+            "UPDATE games SET status = ?2 WHERE id = ?1;",
+            params![id, status.as_str()],
+        )?;
+        Ok(())
+    }
+
+    /// Store the result of a finished game, keeping the result already stored if there is one
+    pub fn save_result(
+        &self,
+        conn: &Connection,
+        result: &GameResultDto,
+        final_state: &FrozenGameState,
+    ) -> Result<(), rusqlite::Error> {
+        let to_json = |value: serde_json::Result<String>| {
+            value.map_err(|e| rusqlite::Error::ToSqlConversionFailure(e.into()))
+        };
+        conn.execute(
+            "INSERT OR IGNORE INTO game_results (game_id, finished_at, winner_id, standings, final_state)
+             VALUES (?1, ?2, ?3, ?4, ?5);",
+            params![
+                result.game_id,
+                result.finished_at.timestamp(),
+                result.winner_id.map(|id| id as i64),
+                to_json(serde_json::to_string(&result.standings))?,
+                to_json(serde_json::to_string(final_state))?,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Get the result of a finished game
+    pub fn get_result(
+        &self,
+        conn: &Connection,
+        game_id: &str,
+    ) -> Result<Option<GameResultDto>, rusqlite::Error> {
+        let mut statement = conn.prepare("SELECT * FROM game_results WHERE game_id = ?1;")?;
+        let mut rows = statement.query(params![game_id])?;
+        match rows.next()? {
+            Some(row) => GameResultDto::try_from(row).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Get the members, card and completions a finished game was frozen at
+    pub fn get_final_state(
+        &self,
+        conn: &Connection,
+        game_id: &str,
+    ) -> Result<Option<FrozenGameState>, rusqlite::Error> {
+        let final_state: Option<String> = conn
+            .query_row(
+                "SELECT final_state FROM game_results WHERE game_id = ?1;",
+                params![game_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        final_state
+            .map(|json| {
+                serde_json::from_str(&json).map_err(|e| {
+                    rusqlite::Error::FromSqlConversionFailure(0, Type::Text, Box::new(e))
+                })
+            })
+            .transpose()
+    }
+
+    /// Get the games on a leaderboard, newest first
+    pub fn get_games_by_leaderboard(
+        &self,
+        conn: &Connection,
+        leaderboard_id: AocLeaderboardId,
+    ) -> Result<Vec<GameDto>, rusqlite::Error> {
+        let mut statement = conn.prepare(
+            "SELECT * FROM games WHERE leaderboard_id = ?1 ORDER BY created_at DESC, id ASC;",
+        )?;
+        let rows =
+            statement.query_map(params![leaderboard_id as i64], |row| GameDto::try_from(row))?;
+        rows.collect()
+    }
+
+    /// Number of games and of memberships across all games
+    pub fn count_games_and_memberships(
+        &self,
+        conn: &Connection,
+    ) -> Result<(u64, u64), rusqlite::Error> {
+        conn.query_row(
+            "SELECT (SELECT COUNT(*) FROM games), (SELECT COUNT(*) FROM game_memberships);",
+            [],
+            |row| Ok((row.get::<_, i64>(0)? as u64, row.get::<_, i64>(1)? as u64)),
+        )
     }
 
     /// Create a game membership
@@ -116,34 +315,48 @@ impl GameRepository {
         )?;
         let mut rows = statement.query(params![game_id, member_id as i64, member_name])?;
 
-        if let Some(row) = rows.next()? {
-            GameMembershipDto::try_from(row)
-        } else {
-            panic!("Expected membership to be returned after insert.")
+        match rows.next()? {
+            Some(row) => GameMembershipDto::try_from(row),
+            None => Err(rusqlite::Error::QueryReturnedNoRows),
         }
     }
 
-    /// Delete a specific membership by its ID
+    /// Delete a specific membership by its ID. Touches the game's `updated_at`, as no other
+    /// timestamp records that the game state changed.
     pub fn delete_membership(
         &self,
         conn: &Connection,
         membership_id: u32,
     ) -> Result<(), rusqlite::Error> {
-        conn.execute("DELETE FROM game_memberships WHERE id = ?1;", params![membership_id as i64])?;
+        conn.execute(
+            "UPDATE games SET updated_at = unixepoch()
+             WHERE id = (SELECT game_id FROM game_memberships WHERE id = ?1);",
+            params![membership_id as i64],
+        )?;
+        conn.execute(
+            "DELETE FROM game_memberships WHERE id = ?1;",
+            params![membership_id as i64],
+        )?;
         Ok(())
     }
 
-    /// Delete a membership by game_id and member_id
+    /// Delete a membership by game_id and member_id, touching the game's `updated_at`
     pub fn delete_membership_by_game_and_member(
         &self,
         conn: &Connection,
         game_id: &str,
         member_id: u32,
     ) -> Result<(), rusqlite::Error> {
-        conn.execute(
+        let deleted = conn.execute(
             "DELETE FROM game_memberships WHERE game_id = ?1 AND member_id = ?2;",
             params![game_id, member_id as i64],
         )?;
+        if deleted > 0 {
+            conn.execute(
+                "UPDATE games SET updated_at = unixepoch() WHERE id = ?1;",
+                params![game_id],
+            )?;
+        }
         Ok(())
     }
 
@@ -152,23 +365,131 @@ impl GameRepository {
         &self,
         conn: &Connection,
         game_id: &str,
-    ) -> Vec<GameMembershipDto> {
-        let mut statement = conn
-            .prepare("SELECT * FROM game_memberships WHERE game_id = ?1 ORDER BY created_at ASC;")
-            .unwrap();
-        let rows = statement
-            .query_map(params![game_id], |row| GameMembershipDto::try_from(row))
-            .unwrap();
+    ) -> Result<Vec<GameMembershipDto>, rusqlite::Error> {
+        let mut statement = conn.prepare(
+            "SELECT * FROM game_memberships WHERE game_id = ?1 ORDER BY created_at ASC;",
+        )?;
+        let rows = statement.query_map(params![game_id], |row| GameMembershipDto::try_from(row))?;
 
         let mut memberships = Vec::new();
         for row_result in rows {
             match row_result {
                 Ok(dto) => memberships.push(dto),
                 Err(e) => {
-                    eprintln!("Failed to convert row to GameMembershipDto: {:?}", e);
+                    tracing::warn!(error = ?e, "Failed to convert row to GameMembershipDto");
                 }
             }
         }
-        memberships
+        Ok(memberships)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use rusqlite::params;
+
+    use super::GameRepository;
+    use crate::{
+        db::test_connection,
+        model::game::{FrozenGameState, GameOptions, GameResultDto, GameStatus, GameVisibility},
+    };
+
+    #[test]
+    fn test_get_game_with_out_of_range_timestamp() {
+        let conn = test_connection();
+        conn.execute(
+            "INSERT INTO games (id, leaderboard_id, session_token, starts_at, created_at) VALUES ('abcd1234', 42, 'token', 0, ?1);",
+            params![i64::MIN],
+        )
+        .unwrap();
+
+        let result = GameRepository::new().get_game(&conn, "abcd1234");
+        assert!(matches!(
+            result,
+            Err(rusqlite::Error::FromSqlConversionFailure(..))
+        ));
+    }
+
+    #[test]
+    fn test_get_memberships_skips_malformed_rows() {
+        let conn = test_connection();
+        let repo = GameRepository::new();
+        repo.create_game(&conn, "abcd1234", 42, "token", &Default::default())
+            .unwrap();
+        repo.create_membership(&conn, "abcd1234", 1, "Alice")
+            .unwrap();
+        conn.execute(
+            "INSERT INTO game_memberships (game_id, member_id, member_name, created_at) VALUES ('abcd1234', 2, 'Bob', ?1);",
+            params![i64::MAX],
+        )
+        .unwrap();
+
+        let memberships = repo.get_memberships_by_game(&conn, "abcd1234").unwrap();
+        assert_eq!(memberships.len(), 1);
+        assert_eq!(memberships[0].member_name, "Alice");
+    }
+
+    #[test]
+    fn test_create_scheduled_game() {
+        let conn = test_connection();
+        let repo = GameRepository::new();
+        let starts_at = Utc::now() + chrono::TimeDelta::days(1);
+        let ends_at = Utc.timestamp_opt(starts_at.timestamp() + 3600, 0).unwrap();
+
+        let options = GameOptions {
+            starts_at: Some(starts_at),
+            ends_at: Some(ends_at),
+            title: Some("Speedrun".to_string()),
+            visibility: GameVisibility::Listed,
+            ..GameOptions::default()
+        };
+
+        let game = repo
+            .create_game(&conn, "abcd1234", 42, "token", &options)
+            .unwrap();
+        assert_eq!(game.status, GameStatus::Scheduled);
+        assert_eq!(game.title.as_deref(), Some("Speedrun"));
+        assert_eq!(game.visibility, GameVisibility::Listed);
+        assert_eq!(game.starts_at.timestamp(), starts_at.timestamp());
+        assert_eq!(game.ends_at, Some(ends_at));
+
+        repo.update_status(&conn, "abcd1234", GameStatus::Running)
+            .unwrap();
+        let game = repo.get_game(&conn, "abcd1234").unwrap().unwrap();
+        assert_eq!(game.status, GameStatus::Running);
+    }
+
+    #[test]
+    fn test_result_is_only_saved_once() {
+        let conn = test_connection();
+        let repo = GameRepository::new();
+        repo.create_game(&conn, "abcd1234", 42, "token", &Default::default())
+            .unwrap();
+        let result = |winner_id| GameResultDto {
+            game_id: "abcd1234".to_string(),
+            finished_at: Utc.with_ymd_and_hms(2024, 12, 26, 0, 0, 0).unwrap(),
+            winner_id,
+            standings: vec![],
+        };
+        let final_state = FrozenGameState {
+            members: vec![],
+            puzzles: vec![],
+            completions: Default::default(),
+        };
+
+        assert_eq!(repo.get_result(&conn, "abcd1234").unwrap(), None);
+        repo.save_result(&conn, &result(Some(1)), &final_state)
+            .unwrap();
+        repo.save_result(&conn, &result(Some(2)), &final_state)
+            .unwrap();
+        assert_eq!(
+            repo.get_result(&conn, "abcd1234").unwrap(),
+            Some(result(Some(1)))
+        );
+        assert_eq!(
+            repo.get_final_state(&conn, "abcd1234").unwrap(),
+            Some(final_state)
+        );
     }
 }
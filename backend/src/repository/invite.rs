@@ -0,0 +1,326 @@
+use rusqlite::{Connection, OptionalExtension, Row, params, types::Type};
+
+use super::{get_datetime, get_optional_datetime};
+use crate::model::{
+    game::GameId,
+    invite::{GameInviteDto, InviteOptions, InviteRedemptionDto, RedemptionStatus},
+    leaderboard::AocMemberId,
+};
+
+/// Invites with the number of redemptions that count against their `max_uses`
+const SELECT_INVITES: &str = "SELECT game_invites.*,
+    (SELECT COUNT(*) FROM invite_redemptions
+     WHERE invite_redemptions.invite_id = game_invites.id AND status != 'rejected') AS uses
+    FROM game_invites";
+
+pub struct InviteRepository;
+
+impl TryFrom<&Row<'_>> for GameInviteDto {
+    type Error = rusqlite::Error;
+
+    fn try_from(row: &Row) -> Result<Self, Self::Error> {
+        let id: i64 = row.get("id")?;
+        let game_id: GameId = row.get("game_id")?;
+        let code: String = row.get("code")?;
+        let max_uses: Option<i64> = row.get("max_uses")?;
+        let uses: i64 = row.get("uses")?;
+        let requires_approval: bool = row.get("requires_approval")?;
+
+        Ok(GameInviteDto {
+            id: id as u32,
+            game_id,
+            code,
+            expires_at: get_optional_datetime(row, "expires_at")?,
+            max_uses: max_uses.map(|max_uses| max_uses as u32),
+            uses: uses as u32,
+            requires_approval,
+            created_at: get_datetime(row, "created_at")?,
+        })
+    }
+}
+
+impl TryFrom<&Row<'_>> for InviteRedemptionDto {
+    type Error = rusqlite::Error;
+
+    fn try_from(row: &Row) -> Result<Self, Self::Error> {
+        let id: i64 = row.get("id")?;
+        let invite_id: i64 = row.get("invite_id")?;
+        let game_id: GameId = row.get("game_id")?;
+        let member_id: i64 = row.get("member_id")?;
+        let member_name: String = row.get("member_name")?;
+        let status_idx = row.as_ref().column_index("status")?;
+        let status: String = row.get(status_idx)?;
+        let status = status.parse::<RedemptionStatus>().map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(status_idx, Type::Text, e.into())
+        })?;
+        let membership_id: Option<i64> = row.get("membership_id")?;
+
+        Ok(InviteRedemptionDto {
+            id: id as u32,
+            invite_id: invite_id as u32,
+            game_id,
+            member_id: member_id as AocMemberId,
+            member_name,
+            status,
+            membership_id: membership_id.map(|id| id as u32),
+            created_at: get_datetime(row, "created_at")?,
+            decided_at: get_optional_datetime(row, "decided_at")?,
+        })
+    }
+}
+
+impl Default for InviteRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InviteRepository {
+    pub fn new() -> Self {
+        InviteRepository
+    }
+
+    /// Create an invite to a game
+    pub fn create_invite(
+        &self,
+        conn: &Connection,
+        game_id: &str,
+        code: &str,
+        options: &InviteOptions,
+    ) -> Result<GameInviteDto, rusqlite::Error> {
+        let id: i64 = conn.query_row(
+            "INSERT INTO game_invites (game_id, code, expires_at, max_uses, requires_approval)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             RETURNING id;",
+            params![
+                game_id,
+                code,
+                options.expires_at.map(|at| at.timestamp()),
+                options.max_uses.map(|max_uses| max_uses as i64),
+                options.requires_approval
+            ],
+            |row| row.get(0),
+        )?;
+        self.get_invite(conn, game_id, id as u32)?
+            .ok_or(rusqlite::Error::QueryReturnedNoRows)
+    }
+
+    /// Get an invite of a game by its ID
+    pub fn get_invite(
+        &self,
+        conn: &Connection,
+        game_id: &str,
+        invite_id: u32,
+    ) -> Result<Option<GameInviteDto>, rusqlite::Error> {
+        conn.query_row(
+            &format!("{} WHERE game_id = ?1 AND id = ?2;", SELECT_INVITES),
+            params![game_id, invite_id as i64],
+            |row| GameInviteDto::try_from(row),
+        )
+        .optional()
+    }
+
+    /// Get an invite by its code
+    pub fn get_invite_by_code(
+        &self,
+        conn: &Connection,
+        code: &str,
+    ) -> Result<Option<GameInviteDto>, rusqlite::Error> {
+        conn.query_row(
+            &format!("{} WHERE code = ?1;", SELECT_INVITES),
+            params![code],
+            |row| GameInviteDto::try_from(row),
+        )
+        .optional()
+    }
+
+    /// Get all invites of a game, unreadable rows are logged and skipped
+    pub fn get_invites_by_game(
+        &self,
+        conn: &Connection,
+        game_id: &str,
+    ) -> Result<Vec<GameInviteDto>, rusqlite::Error> {
+        let mut statement = conn.prepare(&format!(
+            "{} WHERE game_id = ?1 ORDER BY id ASC;",
+            SELECT_INVITES
+        ))?;
+        let rows = statement.query_map(params![game_id], |row| GameInviteDto::try_from(row))?;
+
+        let mut invites = Vec::new();
+        for row_result in rows {
+            match row_result {
+                Ok(dto) => invites.push(dto),
+                Err(e) => {
+                    tracing::warn!(error = ?e, "Failed to convert row to GameInviteDto");
+                }
+            }
+        }
+        Ok(invites)
+    }
+
+    /// Delete an invite of a game together with its redemptions, returns whether it existed
+    pub fn delete_invite(
+        &self,
+        conn: &Connection,
+        game_id: &str,
+        invite_id: u32,
+    ) -> Result<bool, rusqlite::Error> {
+        let deleted = conn.execute(
+            "DELETE FROM game_invites WHERE game_id = ?1 AND id = ?2;",
+            params![game_id, invite_id as i64],
+        )?;
+        Ok(deleted > 0)
+    }
+
+    /// Record a redemption of [invite], decided right away unless it is pending
+    pub fn create_redemption(
+        &self,
+        conn: &Connection,
+        invite: &GameInviteDto,
+        member_id: AocMemberId,
+        member_name: &str,
+        status: RedemptionStatus,
+        membership_id: Option<u32>,
+    ) -> Result<InviteRedemptionDto, rusqlite::Error> {
+        conn.query_row(
+            "INSERT INTO invite_redemptions
+                (invite_id, game_id, member_id, member_name, status, membership_id, decided_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6,
+                CASE WHEN ?5 = 'pending' THEN NULL ELSE unixepoch() END)
+             RETURNING *;",
+            params![
+                invite.id as i64,
+                invite.game_id,
+                member_id as i64,
+                member_name,
+                status.as_str(),
+                membership_id.map(|id| id as i64)
+            ],
+            |row| InviteRedemptionDto::try_from(row),
+        )
+    }
+
+    /// Get a redemption of an invite to a game by its ID
+    pub fn get_redemption(
+        &self,
+        conn: &Connection,
+        game_id: &str,
+        redemption_id: u32,
+    ) -> Result<Option<InviteRedemptionDto>, rusqlite::Error> {
+        conn.query_row(
+            "SELECT * FROM invite_redemptions WHERE game_id = ?1 AND id = ?2;",
+            params![game_id, redemption_id as i64],
+            |row| InviteRedemptionDto::try_from(row),
+        )
+        .optional()
+    }
+
+    /// Whether [member_id] redeemed an invite to a game that still awaits approval
+    pub fn has_pending_redemption(
+        &self,
+        conn: &Connection,
+        game_id: &str,
+        member_id: AocMemberId,
+    ) -> Result<bool, rusqlite::Error> {
+        conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM invite_redemptions
+             WHERE game_id = ?1 AND member_id = ?2 AND status = 'pending');",
+            params![game_id, member_id as i64],
+            |row| row.get(0),
+        )
+    }
+
+    /// Get all redemptions of invites to a game, newest first. Unreadable rows are logged and
+    /// skipped.
+    pub fn get_redemptions_by_game(
+        &self,
+        conn: &Connection,
+        game_id: &str,
+    ) -> Result<Vec<InviteRedemptionDto>, rusqlite::Error> {
+        let mut statement =
+            conn.prepare("SELECT * FROM invite_redemptions WHERE game_id = ?1 ORDER BY id DESC;")?;
+        let rows =
+            statement.query_map(params![game_id], |row| InviteRedemptionDto::try_from(row))?;
+
+        let mut redemptions = Vec::new();
+        for row_result in rows {
+            match row_result {
+                Ok(dto) => redemptions.push(dto),
+                Err(e) => {
+                    tracing::warn!(error = ?e, "Failed to convert row to InviteRedemptionDto");
+                }
+            }
+        }
+        Ok(redemptions)
+    }
+
+    /// Approve or reject a pending redemption
+    pub fn decide_redemption(
+        &self,
+        conn: &Connection,
+        redemption_id: u32,
+        status: RedemptionStatus,
+        membership_id: Option<u32>,
+    ) -> Result<InviteRedemptionDto, rusqlite::Error> {
+        conn.query_row(
+            "UPDATE invite_redemptions
+             SET status = ?2, membership_id = ?3, decided_at = unixepoch()
+             WHERE id = ?1
+             RETURNING *;",
+            params![
+                redemption_id as i64,
+                status.as_str(),
+                membership_id.map(|id| id as i64)
+            ],
+            |row| InviteRedemptionDto::try_from(row),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::InviteRepository;
+    use crate::{
+        db::test_connection,
+        model::invite::{InviteOptions, RedemptionStatus},
+        repository::GameRepository,
+    };
+
+    #[test]
+    fn test_uses_exclude_rejected_redemptions() {
+        let conn = test_connection();
+        GameRepository::new()
+            .create_game(&conn, "abcd1234", 42, "token", &Default::default())
+            .unwrap();
+        let repo = InviteRepository::new();
+        let options = InviteOptions {
+            max_uses: Some(2),
+            requires_approval: true,
+            ..Default::default()
+        };
+        let invite = repo
+            .create_invite(&conn, "abcd1234", "code", &options)
+            .unwrap();
+        assert_eq!(invite.uses, 0);
+        assert_eq!(invite.max_uses, Some(2));
+        assert!(invite.requires_approval);
+
+        let pending = repo
+            .create_redemption(&conn, &invite, 1, "Alice", RedemptionStatus::Pending, None)
+            .unwrap();
+        assert_eq!(pending.decided_at, None);
+        assert!(repo.has_pending_redemption(&conn, "abcd1234", 1).unwrap());
+        repo.create_redemption(&conn, &invite, 2, "Bob", RedemptionStatus::Pending, None)
+            .unwrap();
+        let rejected = repo
+            .decide_redemption(&conn, pending.id, RedemptionStatus::Rejected, None)
+            .unwrap();
+        assert_eq!(rejected.status, RedemptionStatus::Rejected);
+        assert!(rejected.decided_at.is_some());
+        assert!(!repo.has_pending_redemption(&conn, "abcd1234", 1).unwrap());
+
+        let invite = repo.get_invite_by_code(&conn, "code").unwrap().unwrap();
+        assert_eq!(invite.uses, 1);
+        assert!(!invite.is_used_up());
+    }
+}
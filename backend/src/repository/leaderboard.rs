@@ -1,6 +1,7 @@
-use chrono::DateTime;
-use rusqlite::{Connection, Row, params};
+use chrono::{DateTime, Utc};
+use rusqlite::{Connection, Row, params, types::Type};
 
+use super::get_datetime;
 use crate::model::leaderboard::{AocLeaderboardId, AocLeaderboardYearId, LeaderboardDto, Year};
 
 pub struct LeaderboardRepository;
@@ -12,21 +13,29 @@ impl TryFrom<&Row<'_>> for LeaderboardDto {
         let id: i64 = row.get("id")?;
         let year: i64 = row.get("year")?;
         let board_id: i64 = row.get("leaderboard_id")?;
-        let data: String = row.get("data")?;
-        let created_at: i64 = row.get("created_at")?;
-        let updated_at: i64 = row.get("updated_at")?;
+        let data_idx = row.as_ref().column_index("data")?;
+        let data: String = row.get(data_idx)?;
+        let data = serde_json::from_str(&data).map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(data_idx, Type::Text, Box::new(e))
+        })?;
 
         Ok(LeaderboardDto {
             id: id as AocLeaderboardYearId,
             year: year as Year,
             board_id: board_id as AocLeaderboardId,
-            data: serde_json::from_str(&data).unwrap(),
-            created_at: DateTime::from_timestamp(created_at, 0).unwrap(),
-            updated_at: DateTime::from_timestamp(updated_at, 0).unwrap(),
+            data,
+            created_at: get_datetime(row, "created_at")?,
+            updated_at: get_datetime(row, "updated_at")?,
         })
     }
 }
 
+impl Default for LeaderboardRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl LeaderboardRepository {
     pub fn new() -> Self {
         LeaderboardRepository
@@ -49,57 +58,139 @@ impl LeaderboardRepository {
 
         let mut rows = statement.query(params![year as i64, board_id as i64, data])?;
 
-        if let Some(row) = rows.next()? {
-            LeaderboardDto::try_from(row)
-        } else {
-            panic!("Expected leaderboard to be returned after insert/update.")
+        match rows.next()? {
+            Some(row) => LeaderboardDto::try_from(row),
+            None => Err(rusqlite::Error::QueryReturnedNoRows),
         }
     }
 
+    /// Returns `Ok(None)` if nothing is cached, and an error if the cached row is unreadable
     pub fn get_leaderboard(
         &self,
         conn: &Connection,
         year: u32,
         board_id: u32,
-    ) -> Option<LeaderboardDto> {
+    ) -> Result<Option<LeaderboardDto>, rusqlite::Error> {
         let mut statement = conn
-            .prepare("SELECT * FROM leaderboard_cache WHERE year = ?1 AND leaderboard_id = ?2;")
-            .ok()?;
-        let mut rows = statement
-            .query(params![year as i64, board_id as i64])
-            .ok()?;
-
-        rows.next()
-            .ok()?
-            .and_then(|row| LeaderboardDto::try_from(row).ok())
+            .prepare("SELECT * FROM leaderboard_cache WHERE year = ?1 AND leaderboard_id = ?2;")?;
+        let mut rows = statement.query(params![year as i64, board_id as i64])?;
+
+        match rows.next()? {
+            Some(row) => LeaderboardDto::try_from(row).map(Some),
+            None => Ok(None),
+        }
     }
 
+    /// When each cached year of a board was last fetched, without loading the cached data
+    pub fn get_cache_times(
+        &self,
+        conn: &Connection,
+        board_id: u32,
+    ) -> Result<Vec<(Year, DateTime<Utc>)>, rusqlite::Error> {
+        let mut statement = conn.prepare(
+            "SELECT year, updated_at FROM leaderboard_cache
+             WHERE leaderboard_id = ?1 ORDER BY year ASC;",
+        )?;
+        let rows = statement.query_map(params![board_id as i64], |row| {
+            let year: i64 = row.get("year")?;
+            Ok((year as Year, get_datetime(row, "updated_at")?))
+        })?;
+        rows.collect()
+    }
+
+    /// Unreadable rows are logged and skipped
     pub fn get_all_leaderboard_by_id(
         &self,
         conn: &Connection,
         board_id: u32,
-    ) -> Vec<LeaderboardDto> {
-        let mut statement = conn
-            .prepare("SELECT * FROM leaderboard_cache WHERE leaderboard_id = ?1 ORDER BY year ASC;")
-            .unwrap();
-        let rows = statement
-            .query_map(params![board_id as i64], |row| {
-                LeaderboardDto::try_from(row)
-            })
-            .unwrap();
+    ) -> Result<Vec<LeaderboardDto>, rusqlite::Error> {
+        let mut statement = conn.prepare(
+            "SELECT * FROM leaderboard_cache WHERE leaderboard_id = ?1 ORDER BY year ASC;",
+        )?;
+        let rows = statement.query_map(params![board_id as i64], |row| {
+            LeaderboardDto::try_from(row)
+        })?;
 
         let mut leaderboards = Vec::new();
         for row_result in rows {
             match row_result {
                 Ok(dto) => leaderboards.push(dto),
                 Err(e) => {
-                    eprintln!(
-                        "Failed to convert row to LeaderboardDto for board_id={}: {:?}",
-                        board_id, e
-                    );
+                    tracing::warn!(board_id, error = ?e, "Failed to convert row to LeaderboardDto");
                 }
             }
         }
-        leaderboards
+        Ok(leaderboards)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rusqlite::params;
+
+    use super::LeaderboardRepository;
+    use crate::db::test_connection;
+
+    const DATA: &str = r#"{"event":"2024","day1_ts":1733029200,"owner_id":1,"num_days":25,"members":{"1":{"id":1,"local_score":0,"name":null,"last_star_ts":0,"stars":0,"completion_day_level":{}}}}"#;
+
+    #[test]
+    fn test_save_and_get_leaderboard() {
+        let conn = test_connection();
+        let repo = LeaderboardRepository::new();
+        let saved = repo.save_leaderboard(&conn, 2024, 42, DATA).unwrap();
+        assert_eq!(saved.year, 2024);
+        assert_eq!(saved.board_id, 42);
+
+        let cached = repo.get_leaderboard(&conn, 2024, 42).unwrap().unwrap();
+        assert_eq!(cached.data.members.len(), 1);
+        assert!(repo.get_leaderboard(&conn, 2023, 42).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_get_leaderboard_with_malformed_data() {
+        let conn = test_connection();
+        conn.execute(
+            "INSERT INTO leaderboard_cache (year, leaderboard_id, data) VALUES (2024, 42, '{not json');",
+            [],
+        )
+        .unwrap();
+
+        let result = LeaderboardRepository::new().get_leaderboard(&conn, 2024, 42);
+        assert!(matches!(
+            result,
+            Err(rusqlite::Error::FromSqlConversionFailure(..))
+        ));
+    }
+
+    #[test]
+    fn test_get_leaderboard_with_out_of_range_timestamp() {
+        let conn = test_connection();
+        conn.execute(
+            "INSERT INTO leaderboard_cache (year, leaderboard_id, data, updated_at) VALUES (2024, 42, ?1, ?2);",
+            params![DATA, i64::MAX],
+        )
+        .unwrap();
+
+        let result = LeaderboardRepository::new().get_leaderboard(&conn, 2024, 42);
+        assert!(matches!(
+            result,
+            Err(rusqlite::Error::FromSqlConversionFailure(..))
+        ));
+    }
+
+    #[test]
+    fn test_get_all_leaderboard_by_id_skips_malformed_rows() {
+        let conn = test_connection();
+        let repo = LeaderboardRepository::new();
+        repo.save_leaderboard(&conn, 2024, 42, DATA).unwrap();
+        conn.execute(
+            "INSERT INTO leaderboard_cache (year, leaderboard_id, data) VALUES (2023, 42, '[]');",
+            [],
+        )
+        .unwrap();
+
+        let leaderboards = repo.get_all_leaderboard_by_id(&conn, 42).unwrap();
+        assert_eq!(leaderboards.len(), 1);
+        assert_eq!(leaderboards[0].year, 2024);
     }
 }
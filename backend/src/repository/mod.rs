@@ -1,5 +1,36 @@
 mod game;
+mod invite;
 mod leaderboard;
+mod webhook;
+
+use chrono::{DateTime, Utc};
+use rusqlite::{Row, types::Type};
+
+use crate::model::aoc::datetime_from_timestamp;
 
 pub use game::GameRepository;
+pub use invite::InviteRepository;
 pub use leaderboard::LeaderboardRepository;
+pub use webhook::WebhookRepository;
+
+/// Reads a unix timestamp column, failing the row conversion for out of range values
+fn get_datetime(row: &Row, column: &str) -> Result<DateTime<Utc>, rusqlite::Error> {
+    let idx = row.as_ref().column_index(column)?;
+    let ts: i64 = row.get(idx)?;
+    datetime_from_timestamp(ts)
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(idx, Type::Integer, Box::new(e)))
+}
+
+/// Reads a nullable unix timestamp column, see [get_datetime]
+fn get_optional_datetime(
+    row: &Row,
+    column: &str,
+) -> Result<Option<DateTime<Utc>>, rusqlite::Error> {
+    let idx = row.as_ref().column_index(column)?;
+    let ts: Option<i64> = row.get(idx)?;
+    ts.map(|ts| {
+        datetime_from_timestamp(ts)
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(idx, Type::Integer, Box::new(e)))
+    })
+    .transpose()
+}
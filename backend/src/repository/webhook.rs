@@ -0,0 +1,178 @@
+use rusqlite::{Connection, Row, params, types::Type};
+
+use super::get_datetime;
+use crate::model::{
+    game::GameId,
+    webhook::{GameWebhookDto, WebhookDeliveryDto, WebhookFormat},
+};
+
+pub struct WebhookRepository;
+
+impl TryFrom<&Row<'_>> for GameWebhookDto {
+    type Error = rusqlite::Error;
+
+    fn try_from(row: &Row) -> Result<Self, Self::Error> {
+        let id: i64 = row.get("id")?;
+        let game_id: GameId = row.get("game_id")?;
+        let url: String = row.get("url")?;
+        let format_idx = row.as_ref().column_index("format")?;
+        let format: String = row.get(format_idx)?;
+        let format = format.parse::<WebhookFormat>().map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(format_idx, Type::Text, e.into())
+        })?;
+        let events: Option<String> = row.get("events")?;
+        let secret: String = row.get("secret")?;
+
+        Ok(GameWebhookDto {
+            id: id as u32,
+            game_id,
+            url,
+            format,
+            events: events
+                .map(|events| events.split(',').map(str::to_string).collect())
+                .unwrap_or_default(),
+            secret,
+            created_at: get_datetime(row, "created_at")?,
+        })
+    }
+}
+
+impl TryFrom<&Row<'_>> for WebhookDeliveryDto {
+    type Error = rusqlite::Error;
+
+    fn try_from(row: &Row) -> Result<Self, Self::Error> {
+        let id: i64 = row.get("id")?;
+        let webhook_id: i64 = row.get("webhook_id")?;
+        let event: String = row.get("event")?;
+        let attempt: i64 = row.get("attempt")?;
+        let status_code: Option<i64> = row.get("status_code")?;
+        let error: Option<String> = row.get("error")?;
+
+        Ok(WebhookDeliveryDto {
+            id: id as u32,
+            webhook_id: webhook_id as u32,
+            event,
+            attempt: attempt as u32,
+            status_code: status_code.map(|code| code as u16),
+            error,
+            created_at: get_datetime(row, "created_at")?,
+        })
+    }
+}
+
+impl Default for WebhookRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WebhookRepository {
+    pub fn new() -> Self {
+        WebhookRepository
+    }
+
+    /// Create a webhook subscription for a game
+    pub fn create_webhook(
+        &self,
+        conn: &Connection,
+        game_id: &str,
+        url: &str,
+        format: WebhookFormat,
+        events: &[String],
+        secret: &str,
+    ) -> Result<GameWebhookDto, rusqlite::Error> {
+        let mut statement = conn.prepare(
+            "INSERT INTO game_webhooks (game_id, url, format, events, secret)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             RETURNING *;",
+        )?;
+        let mut rows = statement.query(params![
+            game_id,
+            url,
+            format.as_str(),
+            events.join(","),
+            secret
+        ])?;
+
+        match rows.next()? {
+            Some(row) => GameWebhookDto::try_from(row),
+            None => Err(rusqlite::Error::QueryReturnedNoRows),
+        }
+    }
+
+    /// Get all webhooks of a game, unreadable rows are logged and skipped
+    pub fn get_webhooks_by_game(
+        &self,
+        conn: &Connection,
+        game_id: &str,
+    ) -> Result<Vec<GameWebhookDto>, rusqlite::Error> {
+        let mut statement =
+            conn.prepare("SELECT * FROM game_webhooks WHERE game_id = ?1 ORDER BY id ASC;")?;
+        let rows = statement.query_map(params![game_id], |row| GameWebhookDto::try_from(row))?;
+
+        let mut webhooks = Vec::new();
+        for row_result in rows {
+            match row_result {
+                Ok(dto) => webhooks.push(dto),
+                Err(e) => {
+                    tracing::warn!(error = ?e, "Failed to convert row to GameWebhookDto");
+                }
+            }
+        }
+        Ok(webhooks)
+    }
+
+    /// Delete a webhook of a game, returns whether it existed
+    pub fn delete_webhook(
+        &self,
+        conn: &Connection,
+        game_id: &str,
+        webhook_id: u32,
+    ) -> Result<bool, rusqlite::Error> {
+        let deleted = conn.execute(
+            "DELETE FROM game_webhooks WHERE game_id = ?1 AND id = ?2;",
+            params![game_id, webhook_id as i64],
+        )?;
+        Ok(deleted > 0)
+    }
+
+    /// Record a single delivery attempt
+    pub fn log_delivery(
+        &self,
+        conn: &Connection,
+        webhook_id: u32,
+        event: &str,
+        attempt: u32,
+        status_code: Option<u16>,
+        error: Option<&str>,
+    ) -> Result<(), rusqlite::Error> {
+        conn.execute(
+            "INSERT INTO webhook_deliveries (webhook_id, event, attempt, status_code, error)
+             VALUES (?1, ?2, ?3, ?4, ?5);",
+            params![
+                webhook_id as i64,
+                event,
+                attempt as i64,
+                status_code.map(|code| code as i64),
+                error
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Most recent delivery attempts of a webhook, newest first
+    pub fn get_deliveries(
+        &self,
+        conn: &Connection,
+        webhook_id: u32,
+        limit: u32,
+    ) -> Result<Vec<WebhookDeliveryDto>, rusqlite::Error> {
+        let mut statement = conn.prepare(
+            "SELECT * FROM webhook_deliveries WHERE webhook_id = ?1 ORDER BY id DESC LIMIT ?2;",
+        )?;
+        let rows = statement.query_map(params![webhook_id as i64, limit as i64], |row| {
+            WebhookDeliveryDto::try_from(row)
+        })?;
+        rows.collect()
+    }
+}
@@ -1,54 +1,72 @@
-use chrono::{Datelike, Utc};
+use chrono::{Datelike, FixedOffset, Utc};
 
-use crate::model::aoc::{AocPart, AocPuzzle, PuzzleDate};
+use crate::{
+    model::aoc::{AocPart, AocPuzzle, PuzzleDate},
+    service::calendar::EventCalendar,
+};
 
 pub struct AocUtils;
 
 impl AocUtils {
     pub fn earliest_puzzle() -> PuzzleDate {
-        PuzzleDate { year: 2015, day: 1 }
+        PuzzleDate {
+            year: EventCalendar::global().first_year(),
+            day: 1,
+        }
+    }
+    /// Puzzles unlock at midnight US Eastern Standard Time. The event runs in December, so
+    /// daylight saving time never applies and a fixed UTC-5 offset is exact.
+    pub fn unlock_timezone() -> FixedOffset {
+        FixedOffset::west_opt(5 * 3600).unwrap()
     }
     pub fn latest_puzzle() -> PuzzleDate {
         let now = Utc::now();
         Self::latest_puzzle_by_date(&now)
     }
     fn latest_puzzle_by_date(date: &chrono::DateTime<Utc>) -> PuzzleDate {
-        let year = if date.month() == 12 {
-            date.year() as u32
+        let local = date.with_timezone(&Self::unlock_timezone());
+        let year = if local.month() == 12 {
+            local.year() as u32
         } else {
-            (date.year() - 1) as u32
+            (local.year() - 1) as u32
         };
-        PuzzleDate::latest_of_year_by_date(year, date).unwrap()
+        // Walk back in case the registry has no event for the most recent year
+        (Self::earliest_puzzle().year..=year)
+            .rev()
+            .find_map(|year| PuzzleDate::latest_of_year_by_date(year, date))
+            .unwrap_or_else(Self::earliest_puzzle)
+    }
+    /// All event years up to and including the one of [AocUtils::latest_puzzle]
+    pub fn event_years() -> Vec<u32> {
+        EventCalendar::global().years_until(Self::latest_puzzle().year)
     }
     pub fn get_calendar_size_of_year(year: u32) -> Result<u32, &'static str> {
-        if year < AocUtils::earliest_puzzle().year {
-            Err("Year must be 2015 or later.")
-        } else if year < 2025 {
-            Ok(25)
-        } else {
-            Ok(12)
-        }
+        EventCalendar::global()
+            .event(year)
+            .map(|event| event.days)
+            .ok_or("No Advent of Code event in this year.")
     }
     pub fn puzzle_days_for_years(years: &[u32]) -> Vec<PuzzleDate> {
         years
             .iter()
             .filter_map(|&year| PuzzleDate::latest_of_year(year))
             .flat_map(|latest_puzzle_date| {
-                (1..=latest_puzzle_date.day)
-                    .map(move |day| PuzzleDate::new(latest_puzzle_date.year, day))
+                (1..=latest_puzzle_date.day).map(move |day| PuzzleDate {
+                    year: latest_puzzle_date.year,
+                    day,
+                })
             })
             .collect()
     }
     pub fn puzzles_for_years(years: &[u32]) -> Vec<AocPuzzle> {
         Self::puzzle_days_for_years(years)
             .into_iter()
-            .map(|date| {
+            .flat_map(|date| {
                 vec![
                     date.to_aoc_puzzle_part(AocPart::One),
                     date.to_aoc_puzzle_part(AocPart::Two),
                 ]
             })
-            .flatten()
             .collect()
     }
     pub fn estimate_difficulty(puzzle: &AocPuzzle) -> u32 {
@@ -91,8 +109,14 @@ mod tests {
     fn test_puzzle_days_for_years() {
         let puzzles = super::AocUtils::puzzle_days_for_years(&[2015, 2024]);
         assert_eq!(puzzles.len(), 50);
-        assert_eq!(puzzles.first().unwrap(), &super::PuzzleDate::new(2015, 1));
-        assert_eq!(puzzles.last().unwrap(), &super::PuzzleDate::new(2024, 25));
+        assert_eq!(
+            puzzles.first().unwrap(),
+            &super::PuzzleDate::new(2015, 1).unwrap()
+        );
+        assert_eq!(
+            puzzles.last().unwrap(),
+            &super::PuzzleDate::new(2024, 25).unwrap()
+        );
     }
 
     #[test]
@@ -102,14 +126,14 @@ mod tests {
         assert_eq!(
             puzzles.first().unwrap(),
             &super::AocPuzzle {
-                date: super::PuzzleDate::new(2015, 1),
+                date: super::PuzzleDate::new(2015, 1).unwrap(),
                 part: super::AocPart::One
             }
         );
         assert_eq!(
             puzzles.last().unwrap(),
             &super::AocPuzzle {
-                date: super::PuzzleDate::new(2024, 25),
+                date: super::PuzzleDate::new(2024, 25).unwrap(),
                 part: super::AocPart::Two
             }
         );
@@ -120,14 +144,36 @@ mod tests {
         // using chrono::Utc.with_ymd_and_hms()
         let date = Utc.with_ymd_and_hms(2023, 11, 15, 0, 0, 0).unwrap();
         let latest_puzzle = super::AocUtils::latest_puzzle_by_date(&date);
-        assert_eq!(latest_puzzle, super::PuzzleDate::new(2022, 25));
+        assert_eq!(latest_puzzle, super::PuzzleDate::new(2022, 25).unwrap());
 
-        let date = Utc.with_ymd_and_hms(2023, 12, 10, 0, 0, 0).unwrap();
+        let date = Utc.with_ymd_and_hms(2023, 12, 10, 5, 0, 0).unwrap();
         let latest_puzzle = super::AocUtils::latest_puzzle_by_date(&date);
-        assert_eq!(latest_puzzle, super::PuzzleDate::new(2023, 10));
+        assert_eq!(latest_puzzle, super::PuzzleDate::new(2023, 10).unwrap());
 
         let date = Utc.with_ymd_and_hms(2025, 1, 5, 0, 0, 0).unwrap();
         let latest_puzzle = super::AocUtils::latest_puzzle_by_date(&date);
-        assert_eq!(latest_puzzle, super::PuzzleDate::new(2024, 25));
+        assert_eq!(latest_puzzle, super::PuzzleDate::new(2024, 25).unwrap());
+    }
+
+    #[test]
+    fn test_latest_puzzle_by_date_around_unlock() {
+        // Day 10 unlocks at 05:00 UTC, not at midnight UTC
+        let date = Utc.with_ymd_and_hms(2023, 12, 10, 4, 59, 59).unwrap();
+        let latest_puzzle = super::AocUtils::latest_puzzle_by_date(&date);
+        assert_eq!(latest_puzzle, super::PuzzleDate::new(2023, 9).unwrap());
+
+        // The first hours of December 1st in UTC still belong to the previous event
+        let date = Utc.with_ymd_and_hms(2023, 12, 1, 3, 0, 0).unwrap();
+        let latest_puzzle = super::AocUtils::latest_puzzle_by_date(&date);
+        assert_eq!(latest_puzzle, super::PuzzleDate::new(2022, 25).unwrap());
+
+        let date = Utc.with_ymd_and_hms(2023, 12, 1, 5, 0, 0).unwrap();
+        let latest_puzzle = super::AocUtils::latest_puzzle_by_date(&date);
+        assert_eq!(latest_puzzle, super::PuzzleDate::new(2023, 1).unwrap());
+
+        // New Year's Eve in New York is already January in UTC
+        let date = Utc.with_ymd_and_hms(2024, 1, 1, 2, 0, 0).unwrap();
+        let latest_puzzle = super::AocUtils::latest_puzzle_by_date(&date);
+        assert_eq!(latest_puzzle, super::PuzzleDate::new(2023, 25).unwrap());
     }
 }
@@ -0,0 +1,129 @@
+use std::{
+    collections::HashMap,
+    sync::{Mutex, OnceLock},
+};
+
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    db::DbPool,
+    model::leaderboard::AocLeaderboardId,
+    repository::GameRepository,
+    service::{
+        LeaderboardService,
+        aoc_utils::AocUtils,
+        leaderboard::{CACHE_TTL, LeaderboardError},
+    },
+};
+
+static ACCESS: OnceLock<BoardAccess> = OnceLock::new();
+
+/// Session tokens are only kept hashed
+type TokenHash = [u8; 32];
+
+/// Decides whether a session token proves membership of a private leaderboard.
+///
+/// A token proves membership if a game on the board was created with it, or if AoC shows the
+/// board to it. Confirmations from AoC are remembered for [CACHE_TTL], so checking the same
+/// token again does not fetch from AoC more often than the leaderboards themselves.
+pub struct BoardAccess {
+    confirmed: Mutex<HashMap<(AocLeaderboardId, TokenHash), DateTime<Utc>>>,
+}
+
+impl BoardAccess {
+    pub fn global() -> &'static BoardAccess {
+        ACCESS.get_or_init(|| BoardAccess {
+            confirmed: Mutex::new(HashMap::new()),
+        })
+    }
+
+    fn hash(session_token: &str) -> TokenHash {
+        Sha256::digest(session_token.as_bytes()).into()
+    }
+
+    /// Fails with [LeaderboardError::InvalidSession] if [session_token] does not prove
+    /// membership of [board_id]
+    pub async fn verify(
+        &self,
+        pool: &DbPool,
+        board_id: AocLeaderboardId,
+        session_token: &str,
+    ) -> Result<(), LeaderboardError> {
+        let key = (board_id, Self::hash(session_token));
+        let now = Utc::now();
+        if self.is_confirmed(&key, now) {
+            return Ok(());
+        }
+
+        let is_game_owner = {
+            let conn = pool.get()?;
+            GameRepository::new()
+                .get_games_by_leaderboard(&conn, board_id)?
+                .iter()
+                .any(|game| game.is_owned_by(session_token))
+        };
+        if !is_game_owner {
+            let Some(year) = AocUtils::event_years().last().copied() else {
+                return Err(LeaderboardError::InvalidSession);
+            };
+            LeaderboardService::new()
+                .fetch_leaderboard(pool, year, board_id, session_token)
+                .await?;
+        }
+
+        self.confirm(key, now);
+        Ok(())
+    }
+
+    fn is_confirmed(&self, key: &(AocLeaderboardId, TokenHash), now: DateTime<Utc>) -> bool {
+        self.confirmed
+            .lock()
+            .unwrap()
+            .get(key)
+            .is_some_and(|confirmed_at| now - *confirmed_at < CACHE_TTL)
+    }
+
+    fn confirm(&self, key: (AocLeaderboardId, TokenHash), now: DateTime<Utc>) {
+        let mut confirmed = self.confirmed.lock().unwrap();
+        confirmed.retain(|_, confirmed_at| now - *confirmed_at < CACHE_TTL);
+        confirmed.insert(key, now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeDelta, Utc};
+
+    use super::BoardAccess;
+    use crate::{db::test_pool, repository::GameRepository};
+
+    #[tokio::test]
+    async fn test_game_owner_is_member() {
+        let pool = test_pool();
+        {
+            let conn = pool.get().unwrap();
+            GameRepository::new()
+                .create_game(&conn, "abcd1234", 4242, "owner-token", &Default::default())
+                .unwrap();
+        }
+
+        BoardAccess::global()
+            .verify(&pool, 4242, "owner-token")
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    fn test_confirmations_expire() {
+        let access = BoardAccess::global();
+        let key = (4243, BoardAccess::hash("token"));
+        let now = Utc::now();
+
+        assert!(!access.is_confirmed(&key, now));
+        access.confirm(key, now);
+        assert!(access.is_confirmed(&key, now + TimeDelta::minutes(14)));
+        assert!(!access.is_confirmed(&key, now + TimeDelta::minutes(15)));
+        assert!(!access.is_confirmed(&(4244, key.1), now));
+    }
+}
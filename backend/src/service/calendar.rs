@@ -0,0 +1,432 @@
+use std::{collections::BTreeMap, sync::OnceLock};
+
+use chrono::{DateTime, TimeDelta, TimeZone, Utc};
+use thiserror::Error;
+
+use crate::{
+    client::model::leaderboard::LeaderboardResponse,
+    model::{
+        aoc::{AocPart, AocPuzzle, PuzzleDate, datetime_from_timestamp},
+        leaderboard::{Day, Year},
+    },
+    service::aoc_utils::AocUtils,
+};
+
+/// Environment variable pointing to a JSON file that replaces the embedded calendar
+pub const CALENDAR_FILE_ENV: &str = "AOC_CALENDAR_FILE";
+
+static EMBEDDED_CALENDAR: &str = include_str!("calendar.json");
+static CALENDAR: OnceLock<EventCalendar> = OnceLock::new();
+
+#[derive(Error, Debug)]
+pub enum CalendarError {
+    #[error("Failed to read calendar file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Failed to parse calendar: {0}")]
+    Parse(#[from] serde_json::Error),
+    #[error("Invalid calendar: {0}")]
+    Invalid(String),
+    #[error("Calendar has already been loaded")]
+    AlreadyLoaded,
+}
+
+/// Shape of a single event, either listed explicitly or used as template for upcoming years
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize)]
+pub struct EventRules {
+    pub days: Day,
+    /// Part two of the last day is awarded for free once all other stars are collected,
+    /// so it can never be played on its own.
+    #[serde(default = "default_free_last_star")]
+    pub free_last_star: bool,
+}
+
+fn default_free_last_star() -> bool {
+    true
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct CalendarFile {
+    events: Vec<CalendarFileEvent>,
+    upcoming: Option<EventRules>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct CalendarFileEvent {
+    year: Year,
+    #[serde(flatten)]
+    rules: EventRules,
+}
+
+/// Registry of Advent of Code events: which years exist and what their calendars look like
+#[derive(Debug, Clone)]
+pub struct EventCalendar {
+    events: BTreeMap<Year, EventRules>,
+    /// Rules for years after the last listed event, `None` if no further events are expected
+    upcoming: Option<EventRules>,
+}
+
+impl EventCalendar {
+    pub fn from_json(json: &str) -> Result<Self, CalendarError> {
+        let file: CalendarFile = serde_json::from_str(json)?;
+
+        let mut events = BTreeMap::new();
+        for event in file.events {
+            Self::validate(event.year, &event.rules)?;
+            if events.insert(event.year, event.rules).is_some() {
+                return Err(CalendarError::Invalid(format!(
+                    "year {} is listed twice",
+                    event.year
+                )));
+            }
+        }
+        if events.is_empty() {
+            return Err(CalendarError::Invalid("no events listed".to_string()));
+        }
+        if let Some(upcoming) = &file.upcoming {
+            Self::validate(Year::MAX, upcoming)?;
+        }
+
+        Ok(EventCalendar {
+            events,
+            upcoming: file.upcoming,
+        })
+    }
+
+    fn validate(year: Year, rules: &EventRules) -> Result<(), CalendarError> {
+        if !(1..=25).contains(&rules.days) {
+            return Err(CalendarError::Invalid(format!(
+                "event {} has {} days, expected 1 to 25",
+                year, rules.days
+            )));
+        }
+        Ok(())
+    }
+
+    pub fn embedded() -> Self {
+        Self::from_json(EMBEDDED_CALENDAR).expect("Embedded calendar is invalid")
+    }
+
+    /// Loads the calendar from [CALENDAR_FILE_ENV] if set, falling back to the embedded one
+    pub fn load() -> Result<Self, CalendarError> {
+        match std::env::var(CALENDAR_FILE_ENV) {
+            Ok(path) => Self::from_json(&std::fs::read_to_string(path)?),
+            Err(_) => Ok(Self::embedded()),
+        }
+    }
+
+    /// Loads the calendar used by [EventCalendar::global], should be called once at startup
+    pub fn init_global() -> Result<&'static Self, CalendarError> {
+        let calendar = Self::load()?;
+        CALENDAR
+            .set(calendar)
+            .map_err(|_| CalendarError::AlreadyLoaded)?;
+        Ok(Self::global())
+    }
+
+    /// The process wide calendar, the embedded one unless [EventCalendar::init_global] was called
+    pub fn global() -> &'static Self {
+        CALENDAR.get_or_init(Self::embedded)
+    }
+
+    pub fn first_year(&self) -> Year {
+        *self.events.keys().next().unwrap()
+    }
+
+    /// Rules for the event of [year], `None` if there is no such event
+    pub fn event(&self, year: Year) -> Option<&EventRules> {
+        let (&last_year, _) = self.events.last_key_value()?;
+        match self.events.get(&year) {
+            Some(rules) => Some(rules),
+            None if year > last_year => self.upcoming.as_ref(),
+            None => None,
+        }
+    }
+
+    /// All event years from the first one up to and including [until]
+    pub fn years_until(&self, until: Year) -> Vec<Year> {
+        (self.first_year()..=until)
+            .filter(|&year| self.event(year).is_some())
+            .collect()
+    }
+
+    /// Whether [puzzle] can be played on its own, i.e. is not a free star
+    pub fn is_playable(&self, puzzle: &AocPuzzle) -> bool {
+        self.schedule(puzzle.date.year)
+            .is_some_and(|schedule| schedule.is_playable(puzzle))
+    }
+
+    /// Schedule of the event of [year] as far as this calendar knows it
+    pub fn schedule(&self, year: Year) -> Option<EventSchedule> {
+        let rules = self.event(year)?.clone();
+        let day1 = AocUtils::unlock_timezone()
+            .with_ymd_and_hms(year as i32, 12, 1, 0, 0, 0)
+            .single()?
+            .to_utc();
+        Some(EventSchedule {
+            year,
+            rules,
+            day1,
+            conflicts: vec![],
+        })
+    }
+
+    /// Schedule of the event of [year], preferring the `num_days` and `day1_ts` AoC sent along
+    /// with a leaderboard over this calendar. Disagreements are recorded as conflicts.
+    pub fn schedule_for_leaderboard(
+        &self,
+        year: Year,
+        leaderboard: &LeaderboardResponse,
+    ) -> Option<EventSchedule> {
+        let local = self.schedule(year);
+        let upstream_days = leaderboard.num_days.filter(|days| (1..=25).contains(days));
+        let upstream_day1 = leaderboard
+            .day1_ts
+            .and_then(|ts| datetime_from_timestamp(ts as i64).ok());
+
+        let mut schedule = local.clone().or_else(|| {
+            Some(EventSchedule {
+                year,
+                rules: EventRules {
+                    days: upstream_days?,
+                    free_last_star: default_free_last_star(),
+                },
+                day1: upstream_day1?,
+                conflicts: vec![],
+            })
+        })?;
+
+        match &local {
+            None => schedule.conflicts.push(CalendarConflict::UnknownEvent),
+            Some(local) => {
+                if let Some(days) = upstream_days.filter(|&days| days != local.rules.days) {
+                    schedule.conflicts.push(CalendarConflict::Days {
+                        local: local.rules.days,
+                        upstream: days,
+                    });
+                }
+                if let Some(day1) = upstream_day1.filter(|&day1| day1 != local.day1) {
+                    schedule.conflicts.push(CalendarConflict::FirstUnlock {
+                        local: local.day1,
+                        upstream: day1,
+                    });
+                }
+            }
+        }
+
+        if let Some(days) = upstream_days {
+            schedule.rules.days = days;
+        }
+        if let Some(day1) = upstream_day1 {
+            schedule.day1 = day1;
+        }
+        Some(schedule)
+    }
+}
+
+/// A disagreement between the local calendar and what AoC reports for an event
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CalendarConflict {
+    /// AoC knows an event the local calendar does not
+    UnknownEvent,
+    Days {
+        local: Day,
+        upstream: Day,
+    },
+    FirstUnlock {
+        local: DateTime<Utc>,
+        upstream: DateTime<Utc>,
+    },
+}
+
+/// Exact calendar of a single event: how many days it has and when each of them unlocks
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventSchedule {
+    pub year: Year,
+    pub rules: EventRules,
+    /// Unlock instant of day 1, every following day unlocks 24 hours later
+    pub day1: DateTime<Utc>,
+    pub conflicts: Vec<CalendarConflict>,
+}
+
+impl EventSchedule {
+    /// Unlock instant of [day], `None` if the event has no such day
+    pub fn unlock_time(&self, day: Day) -> Option<DateTime<Utc>> {
+        if !(1..=self.rules.days).contains(&day) {
+            return None;
+        }
+        Some(self.day1 + TimeDelta::days(day as i64 - 1))
+    }
+
+    /// Whether [puzzle] belongs to this event and can be played on its own, i.e. is not a free star
+    pub fn is_playable(&self, puzzle: &AocPuzzle) -> bool {
+        puzzle.date.year == self.year
+            && (1..=self.rules.days).contains(&puzzle.date.day)
+            && !(self.rules.free_last_star
+                && puzzle.part == AocPart::Two
+                && puzzle.date.day == self.rules.days)
+    }
+
+    /// All playable puzzles of this event that are unlocked at [now]
+    pub fn playable_puzzles_at(&self, now: &DateTime<Utc>) -> Vec<AocPuzzle> {
+        (1..=self.rules.days)
+            .filter(|&day| self.unlock_time(day).is_some_and(|unlock| unlock <= *now))
+            .flat_map(|day| {
+                let date = PuzzleDate {
+                    year: self.year,
+                    day,
+                };
+                [
+                    date.to_aoc_puzzle_part(AocPart::One),
+                    date.to_aoc_puzzle_part(AocPart::Two),
+                ]
+            })
+            .filter(|puzzle| self.is_playable(puzzle))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use chrono::{TimeZone, Utc};
+
+    use super::{CalendarConflict, EventCalendar, EventRules};
+    use crate::{
+        client::model::leaderboard::LeaderboardResponse,
+        model::aoc::{AocPart, PuzzleDate},
+    };
+
+    fn leaderboard(num_days: Option<u32>, day1_ts: Option<u64>) -> LeaderboardResponse {
+        LeaderboardResponse {
+            event: "2024".to_string(),
+            day1_ts,
+            members: HashMap::new(),
+            owner_id: 1,
+            num_days,
+        }
+    }
+
+    #[test]
+    fn test_embedded_calendar() {
+        let calendar = EventCalendar::embedded();
+        assert_eq!(calendar.first_year(), 2015);
+        assert_eq!(calendar.event(2014), None);
+        assert_eq!(calendar.event(2024).unwrap().days, 25);
+        assert_eq!(calendar.event(2025).unwrap().days, 12);
+        assert_eq!(calendar.event(2030).unwrap().days, 12);
+        assert_eq!(calendar.years_until(2017), vec![2015, 2016, 2017]);
+    }
+
+    #[test]
+    fn test_custom_calendar() {
+        let calendar = EventCalendar::from_json(
+            r#"{"events":[{"year":2020,"days":10},{"year":2022,"days":5,"free_last_star":false}]}"#,
+        )
+        .unwrap();
+        assert_eq!(calendar.years_until(2030), vec![2020, 2022]);
+        assert_eq!(
+            calendar.event(2022),
+            Some(&EventRules {
+                days: 5,
+                free_last_star: false
+            })
+        );
+
+        let last_day = PuzzleDate::new(2020, 10).unwrap();
+        assert!(calendar.is_playable(&last_day.to_aoc_puzzle_part(AocPart::One)));
+        assert!(!calendar.is_playable(&last_day.to_aoc_puzzle_part(AocPart::Two)));
+        let last_day = PuzzleDate::new(2022, 5).unwrap();
+        assert!(calendar.is_playable(&last_day.to_aoc_puzzle_part(AocPart::Two)));
+        let past_end = PuzzleDate::new(2022, 6).unwrap();
+        assert!(!calendar.is_playable(&past_end.to_aoc_puzzle_part(AocPart::One)));
+    }
+
+    #[test]
+    fn test_invalid_calendar() {
+        assert!(EventCalendar::from_json(r#"{"events":[]}"#).is_err());
+        assert!(EventCalendar::from_json(r#"{"events":[{"year":2020,"days":26}]}"#).is_err());
+        assert!(
+            EventCalendar::from_json(
+                r#"{"events":[{"year":2020,"days":25},{"year":2020,"days":12}]}"#
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_schedule_unlock_times() {
+        let schedule = EventCalendar::embedded().schedule(2024).unwrap();
+        assert_eq!(
+            schedule.unlock_time(1),
+            Some(Utc.with_ymd_and_hms(2024, 12, 1, 5, 0, 0).unwrap())
+        );
+        assert_eq!(
+            schedule.unlock_time(25),
+            Some(Utc.with_ymd_and_hms(2024, 12, 25, 5, 0, 0).unwrap())
+        );
+        assert_eq!(schedule.unlock_time(26), None);
+
+        let now = Utc.with_ymd_and_hms(2024, 12, 3, 4, 59, 59).unwrap();
+        assert_eq!(schedule.playable_puzzles_at(&now).len(), 2 * 2);
+        let now = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+        assert_eq!(schedule.playable_puzzles_at(&now).len(), 2 * 25 - 1);
+    }
+
+    #[test]
+    fn test_schedule_for_leaderboard_matching_calendar() {
+        let calendar = EventCalendar::embedded();
+        let schedule = calendar
+            .schedule_for_leaderboard(2024, &leaderboard(Some(25), Some(1733029200)))
+            .unwrap();
+        assert_eq!(schedule, calendar.schedule(2024).unwrap());
+
+        let schedule = calendar
+            .schedule_for_leaderboard(2024, &leaderboard(None, None))
+            .unwrap();
+        assert_eq!(schedule, calendar.schedule(2024).unwrap());
+    }
+
+    #[test]
+    fn test_schedule_for_leaderboard_prefers_upstream() {
+        let calendar = EventCalendar::embedded();
+        // One hour later than the calendar expects, and fewer days
+        let schedule = calendar
+            .schedule_for_leaderboard(2024, &leaderboard(Some(20), Some(1733032800)))
+            .unwrap();
+        assert_eq!(schedule.rules.days, 20);
+        assert_eq!(
+            schedule.unlock_time(2),
+            Some(Utc.with_ymd_and_hms(2024, 12, 2, 6, 0, 0).unwrap())
+        );
+        assert_eq!(
+            schedule.conflicts,
+            vec![
+                CalendarConflict::Days {
+                    local: 25,
+                    upstream: 20
+                },
+                CalendarConflict::FirstUnlock {
+                    local: Utc.with_ymd_and_hms(2024, 12, 1, 5, 0, 0).unwrap(),
+                    upstream: Utc.with_ymd_and_hms(2024, 12, 1, 6, 0, 0).unwrap(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_schedule_for_leaderboard_unknown_event() {
+        let calendar = EventCalendar::from_json(r#"{"events":[{"year":2015,"days":25}]}"#).unwrap();
+        let schedule = calendar
+            .schedule_for_leaderboard(2024, &leaderboard(Some(25), Some(1733029200)))
+            .unwrap();
+        assert_eq!(schedule.rules.days, 25);
+        assert_eq!(schedule.conflicts, vec![CalendarConflict::UnknownEvent]);
+
+        assert!(
+            calendar
+                .schedule_for_leaderboard(2024, &leaderboard(None, Some(1733029200)))
+                .is_none()
+        );
+    }
+}
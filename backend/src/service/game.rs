@@ -1,3 +1,6 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, Utc};
 use rand::Rng;
 use rand::distributions::Alphanumeric;
 use thiserror::Error;
@@ -5,13 +8,28 @@ use thiserror::Error;
 use crate::{
     db::{DbConnection, DbPool, with_transaction},
     model::{
-        game::{GameDto, GameId, GameLeaderboardMemberDto, GameMembershipDto},
-        leaderboard::{AocLeaderboardId, AocMemberId},
+        aoc::AocPart,
+        game::{
+            GameCompletions, GameDto, GameId, GameLeaderboardMemberDto, GameMembershipDto,
+            GameMetadataPatch, GameOptions, GameResultDto, GameState, GameStatus, GameVisibility,
+        },
+        leaderboard::{AocLeaderboardId, AocMemberId, Year},
+    },
+    repository::{GameRepository, LeaderboardRepository},
+    service::{
+        LeaderboardService,
+        calendar::{EventCalendar, EventSchedule},
+        leaderboard::{BingoError, LeaderboardError},
     },
-    repository::GameRepository,
-    service::LeaderboardService,
 };
 
+/// Longest game title, in characters
+pub const MAX_TITLE_LEN: usize = 100;
+/// Longest game description, in characters
+pub const MAX_DESCRIPTION_LEN: usize = 2000;
+/// Longest creator display name, in characters
+pub const MAX_CREATOR_NAME_LEN: usize = 50;
+
 pub struct GameService {}
 
 #[derive(Error, Debug)]
@@ -24,11 +42,31 @@ pub enum GameError {
     IdGenerationFailed(u32),
     #[error("Leaderboard not found")]
     LeaderboardNotFound,
+    #[error("Invalid stored data: {0}")]
+    InvalidData(String),
+    #[error("Invalid schedule: {0}")]
+    InvalidSchedule(String),
+    #[error("Invalid {field}: {message}")]
+    InvalidField {
+        field: &'static str,
+        message: String,
+    },
+    #[error("Game has not finished yet: {0}")]
+    NotFinished(GameId),
+    #[error("Session token does not match the game: {0}")]
+    NotOwner(GameId),
+    #[error("{0}")]
+    Bingo(#[from] BingoError),
 }
 
 impl From<rusqlite::Error> for GameError {
     fn from(err: rusqlite::Error) -> Self {
-        GameError::DatabaseError(err.to_string())
+        match err {
+            rusqlite::Error::FromSqlConversionFailure(..) => {
+                GameError::InvalidData(err.to_string())
+            }
+            _ => GameError::DatabaseError(err.to_string()),
+        }
     }
 }
 
@@ -46,11 +84,24 @@ pub enum GameMembershipError {
     NotFound(u32),
     #[error("Game not found: {0}")]
     GameNotFound(GameId),
+    #[error("Not the owner of game {0}")]
+    NotOwner(GameId),
+    #[error("Member {0} already joined the game")]
+    AlreadyMember(AocMemberId),
+    #[error("Member {0} is not on the game's leaderboard")]
+    NotOnLeaderboard(AocMemberId),
+    #[error("Invalid stored data: {0}")]
+    InvalidData(String),
 }
 
 impl From<rusqlite::Error> for GameMembershipError {
     fn from(err: rusqlite::Error) -> Self {
-        GameMembershipError::DatabaseError(err.to_string())
+        match err {
+            rusqlite::Error::FromSqlConversionFailure(..) => {
+                GameMembershipError::InvalidData(err.to_string())
+            }
+            _ => GameMembershipError::DatabaseError(err.to_string()),
+        }
     }
 }
 
@@ -60,6 +111,12 @@ impl From<r2d2::Error> for GameMembershipError {
     }
 }
 
+impl Default for GameService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl GameService {
     pub fn new() -> Self {
         GameService {}
@@ -74,21 +131,78 @@ impl GameService {
             .collect()
     }
 
-    /// Create a new game with a randomly generated ID
+    /// Trimmed [value] of a descriptive field, `None` if it is empty. Fails if it is longer than
+    /// [max_len] characters or contains control characters, besides line breaks and tabs in
+    /// [multiline] fields.
+    fn normalize_text(
+        field: &'static str,
+        value: Option<&str>,
+        max_len: usize,
+        multiline: bool,
+    ) -> Result<Option<String>, GameError> {
+        let Some(value) = value.map(str::trim).filter(|v| !v.is_empty()) else {
+            return Ok(None);
+        };
+        if value.chars().count() > max_len {
+            return Err(GameError::InvalidField {
+                field,
+                message: format!("must be at most {} characters", max_len),
+            });
+        }
+        if value
+            .chars()
+            .any(|c| c.is_control() && !(multiline && matches!(c, '\n' | '\r' | '\t')))
+        {
+            return Err(GameError::InvalidField {
+                field,
+                message: "must not contain control characters".to_string(),
+            });
+        }
+        Ok(Some(value.to_string()))
+    }
+
+    /// Create a new game with a randomly generated ID, starting now unless [options] say otherwise
     /// Retries up to max_attempts times if there's an ID collision
     pub fn create_game(
         &self,
         conn: &DbConnection,
         leaderboard_id: AocLeaderboardId,
         session_token: &str,
+        options: &GameOptions,
         max_attempts: u32,
     ) -> Result<GameDto, GameError> {
         let repo = GameRepository::new();
+        let starts_at = options.starts_at.unwrap_or_else(Utc::now);
+        if let Some(ends_at) = options.ends_at
+            && ends_at <= starts_at
+        {
+            return Err(GameError::InvalidSchedule(
+                "ends_at must be after starts_at".to_string(),
+            ));
+        }
+        let options = GameOptions {
+            starts_at: Some(starts_at),
+            ends_at: options.ends_at,
+            title: Self::normalize_text("title", options.title.as_deref(), MAX_TITLE_LEN, false)?,
+            description: Self::normalize_text(
+                "description",
+                options.description.as_deref(),
+                MAX_DESCRIPTION_LEN,
+                true,
+            )?,
+            creator_name: Self::normalize_text(
+                "creator_name",
+                options.creator_name.as_deref(),
+                MAX_CREATOR_NAME_LEN,
+                false,
+            )?,
+            visibility: options.visibility,
+        };
 
         for attempt in 0..max_attempts {
             let id = Self::generate_game_id();
 
-            match repo.create_game(conn, &id, leaderboard_id, session_token) {
+            match repo.create_game(conn, &id, leaderboard_id, session_token, &options) {
                 Ok(game) => return Ok(game),
                 Err(rusqlite::Error::SqliteFailure(err, _))
                     if err.code == rusqlite::ErrorCode::ConstraintViolation
@@ -108,7 +222,7 @@ impl GameService {
     pub fn get_game(&self, conn: &DbConnection, id: &str) -> Result<GameDto, GameError> {
         let repo = GameRepository::new();
 
-        repo.get_game(conn, id)
+        repo.get_game(conn, id)?
             .ok_or_else(|| GameError::NotFound(id.to_string()))
     }
 
@@ -140,38 +254,415 @@ impl GameService {
             .values()
             .map(|member| GameLeaderboardMemberDto {
                 id: member.id,
-                name: member.name.clone(),
+                name: member.display_name(),
             })
             .collect())
     }
 
+    /// Compute the current state of a game: its card and what every member completed on it.
+    ///
+    /// Moves the game along its lifecycle first. A game that is due to finish gets its result
+    /// frozen, finished and archived games are served from that result from then on.
+    pub async fn get_game_state(&self, pool: &DbPool, id: &str) -> Result<GameState, GameError> {
+        // Get game and members (sync work)
+        let (game, members) = {
+            let mut conn = pool.get()?;
+            let game = self.get_game(&conn, id)?;
+            if game.status.is_frozen() {
+                return self.get_frozen_state(&conn, game);
+            }
+            let members = self.get_memberships(&mut conn, id)?;
+            (game, members)
+        };
+
+        let status = game.status_at(&Utc::now());
+        let mut state = self.compute_game_state(pool, game, members).await?;
+        if status != state.game.status {
+            let mut conn = pool.get()?;
+            self.advance_status(&mut conn, &mut state, status)?;
+        }
+        Ok(state)
+    }
+
+    /// Move a game forward to [status], freezing its result from [state] if it finished or was
+    /// archived
+    fn advance_status(
+        &self,
+        conn: &mut DbConnection,
+        state: &mut GameState,
+        status: GameStatus,
+    ) -> Result<(), GameError> {
+        let game_id = state.game.id.clone();
+        let now = Utc::now();
+        with_transaction(conn, |tx| {
+            let repo = GameRepository::new();
+            let current = repo
+                .get_game(tx, &game_id)?
+                .ok_or_else(|| GameError::NotFound(game_id.clone()))?;
+            // Another request may have moved the game already, games never move back
+            if current.status >= status {
+                return Ok(());
+            }
+            if status.is_frozen() {
+                let result = GameResultDto {
+                    game_id: game_id.clone(),
+                    finished_at: state.game.ends_at.map_or(now, |ends_at| ends_at.min(now)),
+                    winner_id: state.winner().map(|(member_id, _)| member_id),
+                    standings: state.standings(),
+                };
+                repo.save_result(tx, &result, &state.frozen())?;
+            }
+            repo.update_status(tx, &game_id, status)?;
+            tracing::info!(game_id, status = status.as_str(), "Game status changed");
+            Ok::<_, GameError>(())
+        })?;
+
+        state.game = self.get_game(conn, &game_id)?;
+        if state.game.status.is_frozen() {
+            // Another request may have frozen the game first, its result is the one that counts
+            *state = self.get_frozen_state(conn, state.game.clone())?;
+        }
+        Ok(())
+    }
+
+    /// State of a finished or archived game as frozen back then
+    fn get_frozen_state(&self, conn: &DbConnection, game: GameDto) -> Result<GameState, GameError> {
+        let final_state = GameRepository::new()
+            .get_final_state(conn, &game.id)?
+            .ok_or_else(|| GameError::InvalidData(format!("No result for game {}", game.id)))?;
+        Ok(GameState::from_frozen(game, final_state))
+    }
+
+    /// Final standings of a finished or archived game
+    pub async fn get_result(&self, pool: &DbPool, id: &str) -> Result<GameResultDto, GameError> {
+        // Finishes the game if it is due
+        let state = self.get_game_state(pool, id).await?;
+        if !state.game.status.is_frozen() {
+            return Err(GameError::NotFinished(id.to_string()));
+        }
+
+        let conn = pool.get()?;
+        GameRepository::new()
+            .get_result(&conn, id)?
+            .ok_or_else(|| GameError::InvalidData(format!("No result for game {}", id)))
+    }
+
+    /// State of a game that is not finished, computed from the current leaderboards. Only stars
+    /// earned within the game's scoring window count.
+    async fn compute_game_state(
+        &self,
+        pool: &DbPool,
+        game: GameDto,
+        members: Vec<GameMembershipDto>,
+    ) -> Result<GameState, GameError> {
+        let member_ids = members.iter().map(|m| m.member_id).collect::<Vec<_>>();
+
+        // Get bingo options (async work)
+        let lbs = LeaderboardService::new();
+        let puzzles = lbs
+            .get_bingo_options(
+                pool,
+                None,
+                game.leaderboard_id,
+                Some(&game.session_token),
+                Some(&member_ids),
+                Some(game.starts_at),
+            )
+            .await?;
+        let current_leaderboards = lbs
+            .get_or_create_all_leaderboards(pool, game.leaderboard_id, Some(&game.session_token))
+            .await;
+        // Puzzles unlocking after the game ended can't be completed in it. Like the card itself,
+        // this goes by the schedule AoC sent along with each leaderboard.
+        let puzzles: Vec<_> = match game.ends_at {
+            None => puzzles,
+            Some(ends_at) => {
+                let calendar = EventCalendar::global();
+                let schedules: HashMap<Year, EventSchedule> = puzzles
+                    .iter()
+                    .map(|puzzle| puzzle.date.year)
+                    .collect::<HashSet<_>>()
+                    .into_iter()
+                    .filter_map(|year| {
+                        let leaderboard = current_leaderboards
+                            .iter()
+                            .filter_map(|r| r.as_ref().ok())
+                            .find(|leaderboard| leaderboard.year == year);
+                        let schedule = match leaderboard {
+                            Some(leaderboard) => {
+                                calendar.schedule_for_leaderboard(year, &leaderboard.data)
+                            }
+                            None => calendar.schedule(year),
+                        };
+                        Some((year, schedule?))
+                    })
+                    .collect();
+                puzzles
+                    .into_iter()
+                    .filter(|puzzle| {
+                        schedules
+                            .get(&puzzle.date.year)
+                            .and_then(|schedule| schedule.unlock_time(puzzle.date.day))
+                            .is_some_and(|unlocked_at| unlocked_at < ends_at)
+                    })
+                    .collect()
+            }
+        };
+
+        let session_expired = current_leaderboards
+            .iter()
+            .any(|r| matches!(r, Err(LeaderboardError::InvalidSession)));
+
+        let mut completions = GameCompletions::new();
+
+        for leaderboard in current_leaderboards.into_iter().filter_map(|r| r.ok()) {
+            for member in leaderboard.data.members.values() {
+                if !member_ids.contains(&member.id) {
+                    continue;
+                }
+                for (&day, day_completion) in member.completion_day_level.iter() {
+                    for (&part, star_info) in day_completion.iter() {
+                        // Parts we don't know about can never be a bingo option
+                        let Ok(part) = AocPart::try_from(part) else {
+                            continue;
+                        };
+                        if !puzzles.iter().any(|option| {
+                            part == option.part
+                                && option.date.year == leaderboard.year
+                                && option.date.day == day
+                        }) {
+                            continue;
+                        }
+                        let completed_at = star_info
+                            .star_time()
+                            .map_err(|e| GameError::InvalidData(e.to_string()))?;
+                        if !game.counts_at(&completed_at) {
+                            continue;
+                        }
+                        completions.entry(member.id).or_default().insert((
+                            leaderboard.year as Year,
+                            day,
+                            part,
+                            completed_at,
+                        ));
+                    }
+                }
+            }
+        }
+
+        Ok(GameState {
+            game,
+            members,
+            puzzles,
+            completions,
+            session_expired,
+        })
+    }
+
+    /// Archive a game on behalf of its owner, freezing its current state if it has not finished
+    pub async fn archive_game(
+        &self,
+        pool: &DbPool,
+        id: &str,
+        session_token: &str,
+    ) -> Result<GameDto, GameError> {
+        {
+            let conn = pool.get()?;
+            self.get_owned_game(&conn, id, session_token)?;
+        }
+
+        let mut state = self.get_game_state(pool, id).await?;
+        if state.game.status != GameStatus::Archived {
+            let mut conn = pool.get()?;
+            self.advance_status(&mut conn, &mut state, GameStatus::Archived)?;
+        }
+        Ok(state.game)
+    }
+
+    /// Change the descriptive fields of a game on behalf of its owner
+    pub fn update_metadata(
+        &self,
+        conn: &mut DbConnection,
+        id: &str,
+        session_token: &str,
+        patch: &GameMetadataPatch,
+    ) -> Result<GameDto, GameError> {
+        with_transaction(conn, |tx| {
+            let game = self.get_owned_game(tx, id, session_token)?;
+            let title = match &patch.title {
+                Some(title) => Self::normalize_text("title", Some(title), MAX_TITLE_LEN, false)?,
+                None => game.title,
+            };
+            let description = match &patch.description {
+                Some(description) => Self::normalize_text(
+                    "description",
+                    Some(description),
+                    MAX_DESCRIPTION_LEN,
+                    true,
+                )?,
+                None => game.description,
+            };
+            let creator_name = match &patch.creator_name {
+                Some(name) => {
+                    Self::normalize_text("creator_name", Some(name), MAX_CREATOR_NAME_LEN, false)?
+                }
+                None => game.creator_name,
+            };
+            let visibility = patch.visibility.unwrap_or(game.visibility);
+
+            let repo = GameRepository::new();
+            repo.update_metadata(
+                tx,
+                id,
+                title.as_deref(),
+                description.as_deref(),
+                creator_name.as_deref(),
+                visibility,
+            )?;
+            repo.get_game(tx, id)?
+                .ok_or_else(|| GameError::NotFound(id.to_string()))
+        })
+    }
+
+    /// Delete a game on behalf of its owner, along with its members, webhooks and result
+    pub fn delete_game(
+        &self,
+        conn: &mut DbConnection,
+        id: &str,
+        session_token: &str,
+    ) -> Result<(), GameError> {
+        with_transaction(conn, |tx| {
+            self.get_owned_game(tx, id, session_token)?;
+            GameRepository::new().delete_game(tx, id)?;
+            tracing::info!(game_id = id, "Game deleted");
+            Ok(())
+        })
+    }
+
+    /// Delete games that finished or were archived before [cutoff], returning how many. Their
+    /// session tokens go with them.
+    pub fn purge_expired_games(
+        &self,
+        conn: &DbConnection,
+        cutoff: DateTime<Utc>,
+    ) -> Result<usize, GameError> {
+        GameRepository::new()
+            .delete_games_finished_before(conn, cutoff)
+            .map_err(Into::into)
+    }
+
+    /// A game, if [session_token] proves ownership of it
+    pub(crate) fn get_owned_game(
+        &self,
+        conn: &rusqlite::Connection,
+        id: &str,
+        session_token: &str,
+    ) -> Result<GameDto, GameError> {
+        let game = GameRepository::new()
+            .get_game(conn, id)?
+            .ok_or_else(|| GameError::NotFound(id.to_string()))?;
+        if !game.is_owned_by(session_token) {
+            return Err(GameError::NotOwner(id.to_string()));
+        }
+        Ok(game)
+    }
+
+    /// Games on a leaderboard its members may see, newest first
+    pub fn get_listed_games(
+        &self,
+        conn: &DbConnection,
+        leaderboard_id: AocLeaderboardId,
+    ) -> Result<Vec<GameDto>, GameError> {
+        let games = GameRepository::new().get_games_by_leaderboard(conn, leaderboard_id)?;
+        Ok(games
+            .into_iter()
+            .filter(|game| game.visibility == GameVisibility::Listed)
+            .collect())
+    }
+
     /// Get all games (optional - for debugging/admin)
-    pub fn get_all_games(&self, conn: &DbConnection) -> Vec<GameDto> {
+    pub fn get_all_games(&self, conn: &DbConnection) -> Result<Vec<GameDto>, GameError> {
         let repo = GameRepository::new();
-        repo.get_all_games(conn)
+        repo.get_all_games(conn).map_err(Into::into)
     }
 
-    /// Create a membership in a game
+    /// Add a member to a game owned by [session_token]
+    ///
+    /// Only members on a cached year of the game's leaderboard may join, and only once, under
+    /// their name on the leaderboard. Listing the possible members of a game caches its
+    /// leaderboard. Everyone else joins by redeeming an invite, see
+    /// [crate::service::InviteService::redeem].
     pub fn create_membership(
         &self,
         conn: &mut DbConnection,
         game_id: &str,
+        session_token: &str,
         member_id: AocMemberId,
-        member_name: &str,
     ) -> Result<GameMembershipDto, GameMembershipError> {
         with_transaction(conn, |tx| {
-            let repo = GameRepository::new();
-
-            // Verify game exists first (within transaction)
-            if repo.get_game(tx, game_id).is_none() {
-                return Err(GameMembershipError::GameNotFound(game_id.to_string()));
+            let game = GameRepository::new()
+                .get_game(tx, game_id)?
+                .ok_or_else(|| GameMembershipError::GameNotFound(game_id.to_string()))?;
+            if !game.is_owned_by(session_token) {
+                return Err(GameMembershipError::NotOwner(game_id.to_string()));
             }
-
-            repo.create_membership(tx, game_id, member_id, member_name)
-                .map_err(Into::into)
+            self.add_member(tx, game_id, member_id)
         })
     }
 
+    /// [Self::create_membership] within a transaction of the caller
+    pub(crate) fn add_member(
+        &self,
+        conn: &rusqlite::Connection,
+        game_id: &str,
+        member_id: AocMemberId,
+    ) -> Result<GameMembershipDto, GameMembershipError> {
+        let member_name = self.check_joinable(conn, game_id, member_id)?;
+
+        GameRepository::new()
+            .create_membership(conn, game_id, member_id, &member_name)
+            .map_err(|e| match e {
+                // Joined concurrently
+                rusqlite::Error::SqliteFailure(failure, _)
+                    if failure.extended_code == rusqlite::ffi::SQLITE_CONSTRAINT_UNIQUE =>
+                {
+                    GameMembershipError::AlreadyMember(member_id)
+                }
+                e => e.into(),
+            })
+    }
+
+    /// Name of [member_id] on the game's leaderboard. Fails unless they are on a cached year of
+    /// the leaderboard and did not join the game yet.
+    pub(crate) fn check_joinable(
+        &self,
+        conn: &rusqlite::Connection,
+        game_id: &str,
+        member_id: AocMemberId,
+    ) -> Result<String, GameMembershipError> {
+        let repo = GameRepository::new();
+        let Some(game) = repo.get_game(conn, game_id)? else {
+            return Err(GameMembershipError::GameNotFound(game_id.to_string()));
+        };
+
+        // Leaderboards are ordered by year, members may have renamed themselves since
+        let member_name = LeaderboardRepository::new()
+            .get_all_leaderboard_by_id(conn, game.leaderboard_id)?
+            .iter()
+            .rev()
+            .find_map(|leaderboard| leaderboard.data.members.get(&member_id))
+            .map(|member| member.display_name())
+            .ok_or(GameMembershipError::NotOnLeaderboard(member_id))?;
+        if repo
+            .get_memberships_by_game(conn, game_id)?
+            .iter()
+            .any(|membership| membership.member_id == member_id)
+        {
+            return Err(GameMembershipError::AlreadyMember(member_id));
+        }
+        Ok(member_name)
+    }
+
     /// Delete a membership by its ID
     pub fn delete_membership(
         &self,
@@ -183,19 +674,22 @@ impl GameService {
             .map_err(Into::into)
     }
 
-    /// Delete a membership by game_id and member_id
+    /// Delete a membership by game_id and member_id on behalf of the game's owner
     pub fn delete_membership_by_game_and_member(
         &self,
         conn: &mut DbConnection,
         game_id: &str,
+        session_token: &str,
         member_id: AocMemberId,
     ) -> Result<(), GameMembershipError> {
         with_transaction(conn, |tx| {
             let repo = GameRepository::new();
 
-            // Verify game exists first (within transaction)
-            if repo.get_game(tx, game_id).is_none() {
-                return Err(GameMembershipError::GameNotFound(game_id.to_string()));
+            let game = repo
+                .get_game(tx, game_id)?
+                .ok_or_else(|| GameMembershipError::GameNotFound(game_id.to_string()))?;
+            if !game.is_owned_by(session_token) {
+                return Err(GameMembershipError::NotOwner(game_id.to_string()));
             }
 
             repo.delete_membership_by_game_and_member(tx, game_id, member_id)
@@ -213,11 +707,195 @@ impl GameService {
             let repo = GameRepository::new();
 
             // Verify game exists (within transaction)
-            if repo.get_game(tx, game_id).is_none() {
+            if repo.get_game(tx, game_id)?.is_none() {
                 return Err(GameError::NotFound(game_id.to_string()));
             }
 
-            Ok(repo.get_memberships_by_game(tx, game_id))
+            repo.get_memberships_by_game(tx, game_id)
+                .map_err(Into::into)
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use chrono::{TimeZone, Utc};
+
+    use super::{GameMembershipError, GameService};
+    use crate::{
+        db::{DbPool, test_pool},
+        model::{
+            aoc::AocPart,
+            game::{GameOptions, GameStatus},
+        },
+        repository::{GameRepository, LeaderboardRepository},
+        service::aoc_utils::AocUtils,
+    };
+
+    const EMPTY: &str = r#"{"event":"2024","day1_ts":1733029200,"owner_id":1,"num_days":25,"members":{"1":{"id":1,"local_score":0,"name":null,"last_star_ts":0,"stars":0,"completion_day_level":{}}}}"#;
+
+    /// Member 1 solved 2024 day 1 part one during the game and day 2 part one [day_two_ts]
+    fn save_leaderboards(pool: &DbPool, day_two_ts: i64) {
+        let data = format!(
+            r#"{{"event":"2024","day1_ts":1733029200,"owner_id":1,"num_days":25,"members":{{"1":{{"id":1,"local_score":0,"name":null,"last_star_ts":0,"stars":2,"completion_day_level":{{"1":{{"1":{{"get_star_ts":1733032800,"star_index":0}}}},"2":{{"1":{{"get_star_ts":{},"star_index":1}}}}}}}}}}}}"#,
+            day_two_ts
+        );
+        let conn = pool.get().unwrap();
+        for year in AocUtils::event_years() {
+            let data = if year == 2024 { data.as_str() } else { EMPTY };
+            LeaderboardRepository::new()
+                .save_leaderboard(&conn, year, 42, data)
+                .unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_finished_game_is_frozen() {
+        let pool = test_pool();
+        {
+            let conn = pool.get().unwrap();
+            let repo = GameRepository::new();
+            let options = GameOptions {
+                starts_at: Some(Utc.with_ymd_and_hms(2024, 12, 1, 0, 0, 0).unwrap()),
+                ends_at: Some(Utc.with_ymd_and_hms(2024, 12, 2, 12, 0, 0).unwrap()),
+                ..GameOptions::default()
+            };
+            repo.create_game(&conn, "abcd1234", 42, "token", &options)
+                .unwrap();
+            repo.create_membership(&conn, "abcd1234", 1, "Alice")
+                .unwrap();
+        }
+        // Day 2 was solved after the game ended
+        save_leaderboards(&pool, 1733227200);
+
+        let service = GameService::new();
+        let state = service.get_game_state(&pool, "abcd1234").await.unwrap();
+        assert_eq!(state.game.status, GameStatus::Finished);
+        let completions: Vec<_> = state.completions[&1]
+            .iter()
+            .map(|(year, day, part, _)| (*year, *day, part.clone()))
+            .collect();
+        assert_eq!(completions, vec![(2024, 1, AocPart::One)]);
+        // Puzzles unlocking after the game ended are not on the card
+        assert!(
+            !state
+                .puzzles
+                .iter()
+                .any(|puzzle| puzzle.date.year == 2024 && puzzle.date.day == 3)
+        );
+
+        let result = service.get_result(&pool, "abcd1234").await.unwrap();
+        assert_eq!(result.finished_at, state.game.ends_at.unwrap());
+        assert_eq!(result.standings.len(), 1);
+        assert_eq!(result.standings[0].stars, 1);
+
+        // Stars showing up later, even within the game's window, no longer change anything
+        save_leaderboards(&pool, 1733133600);
+        let frozen = service.get_game_state(&pool, "abcd1234").await.unwrap();
+        assert_eq!(frozen.completions, state.completions);
+        assert_eq!(frozen.puzzles, state.puzzles);
+    }
+
+    #[tokio::test]
+    async fn test_result_of_running_game() {
+        let pool = test_pool();
+        {
+            let conn = pool.get().unwrap();
+            GameRepository::new()
+                .create_game(&conn, "abcd1234", 42, "token", &Default::default())
+                .unwrap();
+        }
+        save_leaderboards(&pool, 1733133600);
+
+        let service = GameService::new();
+        assert!(matches!(
+            service.get_result(&pool, "abcd1234").await,
+            Err(super::GameError::NotFinished(_))
+        ));
+        let state = service.get_game_state(&pool, "abcd1234").await.unwrap();
+        assert_eq!(state.game.status, GameStatus::Running);
+    }
+
+    #[tokio::test]
+    async fn test_card_ends_by_upstream_schedule() {
+        let pool = test_pool();
+        {
+            let conn = pool.get().unwrap();
+            let options = GameOptions {
+                starts_at: Some(Utc.with_ymd_and_hms(2024, 12, 1, 0, 0, 0).unwrap()),
+                ends_at: Some(Utc.with_ymd_and_hms(2024, 12, 2, 12, 0, 0).unwrap()),
+                ..GameOptions::default()
+            };
+            GameRepository::new()
+                .create_game(&conn, "abcd1234", 42, "token", &options)
+                .unwrap();
+            // AoC reports 2024 starting at 17:00 UTC instead of 05:00, so day 2 unlocked after
+            // the game ended although the local calendar says otherwise
+            let late = EMPTY.replace("1733029200", "1733072400");
+            for year in AocUtils::event_years() {
+                let data = if year == 2024 { late.as_str() } else { EMPTY };
+                LeaderboardRepository::new()
+                    .save_leaderboard(&conn, year, 42, data)
+                    .unwrap();
+            }
+        }
+
+        let state = GameService::new()
+            .get_game_state(&pool, "abcd1234")
+            .await
+            .unwrap();
+        let days: HashSet<_> = state
+            .puzzles
+            .iter()
+            .filter(|puzzle| puzzle.date.year == 2024)
+            .map(|puzzle| puzzle.date.day)
+            .collect();
+        assert_eq!(days, HashSet::from([1]));
+    }
+
+    #[test]
+    fn test_create_membership() {
+        let pool = test_pool();
+        {
+            let conn = pool.get().unwrap();
+            GameRepository::new()
+                .create_game(&conn, "abcd1234", 42, "token", &GameOptions::default())
+                .unwrap();
+        }
+        let service = GameService::new();
+        let mut conn = pool.get().unwrap();
+
+        // Nobody can join before the leaderboard is cached
+        assert!(matches!(
+            service.create_membership(&mut conn, "abcd1234", "token", 1),
+            Err(GameMembershipError::NotOnLeaderboard(1))
+        ));
+
+        LeaderboardRepository::new()
+            .save_leaderboard(&conn, 2024, 42, EMPTY)
+            .unwrap();
+        let membership = service
+            .create_membership(&mut conn, "abcd1234", "token", 1)
+            .unwrap();
+        assert_eq!(membership.member_id, 1);
+        assert_eq!(membership.member_name, "(anonymous user #1)");
+        assert!(matches!(
+            service.create_membership(&mut conn, "abcd1234", "token", 1),
+            Err(GameMembershipError::AlreadyMember(1))
+        ));
+        assert!(matches!(
+            service.create_membership(&mut conn, "abcd1234", "token", 2),
+            Err(GameMembershipError::NotOnLeaderboard(2))
+        ));
+        assert!(matches!(
+            service.create_membership(&mut conn, "zzzz9999", "token", 1),
+            Err(GameMembershipError::GameNotFound(_))
+        ));
+        assert!(matches!(
+            service.create_membership(&mut conn, "abcd1234", "other", 1),
+            Err(GameMembershipError::NotOwner(_))
+        ));
+    }
+}
@@ -0,0 +1,183 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    db::DbPool,
+    model::{
+        aoc::PuzzleDate,
+        game::{GameId, GameState, GameStatus},
+        leaderboard::{AocMemberId, Year},
+    },
+    repository::{GameRepository, LeaderboardRepository},
+    service::{GameService, aoc_utils::AocUtils, game::GameError, leaderboard::CACHE_TTL},
+};
+
+/// Everything a [GameState] is computed from, cheap to load compared to the state itself
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameStateVersion {
+    game_id: GameId,
+    game_updated_at: DateTime<Utc>,
+    /// Status the game is due to be in, which changes with time alone
+    status: GameStatus,
+    /// Membership ID, member and when they joined
+    memberships: Vec<(u32, AocMemberId, DateTime<Utc>)>,
+    /// When each cached leaderboard year was fetched
+    leaderboards: Vec<(Year, DateTime<Utc>)>,
+    /// The card grows whenever a puzzle unlocks
+    latest_puzzle: PuzzleDate,
+}
+
+impl GameStateVersion {
+    /// Load the version of a game as it is stored right now
+    pub fn load(pool: &DbPool, game_id: &str) -> Result<Self, GameError> {
+        let conn = pool.get()?;
+        let game = GameRepository::new()
+            .get_game(&conn, game_id)?
+            .ok_or_else(|| GameError::NotFound(game_id.to_string()))?;
+        let memberships = GameRepository::new()
+            .get_memberships_by_game(&conn, game_id)?
+            .into_iter()
+            .map(|m| (m.id, m.member_id, m.created_at))
+            .collect();
+        let leaderboards =
+            LeaderboardRepository::new().get_cache_times(&conn, game.leaderboard_id)?;
+
+        Ok(GameStateVersion {
+            status: game.status_at(&Utc::now()),
+            game_id: game.id,
+            game_updated_at: game.updated_at,
+            memberships,
+            leaderboards,
+            latest_puzzle: AocUtils::latest_puzzle(),
+        })
+    }
+
+    /// Strong entity tag of every response derived from this version of the game
+    pub fn etag(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(format!("{:?}", self));
+        let hash = hex::encode(hasher.finalize());
+        format!("\"{}\"", &hash[..32])
+    }
+
+    /// Latest change to any input of the game state
+    pub fn last_modified(&self) -> DateTime<Utc> {
+        let unlocked_at = self.latest_puzzle.unlock_time().ok();
+        self.memberships
+            .iter()
+            .map(|(_, _, joined_at)| *joined_at)
+            .chain(self.leaderboards.iter().map(|(_, fetched_at)| *fetched_at))
+            .chain(unlocked_at)
+            .fold(self.game_updated_at, DateTime::max)
+    }
+
+    /// Until when computing the state again would not fetch any leaderboard from AoC, `None` if
+    /// some event year is not cached at all
+    fn fresh_until(&self) -> Option<DateTime<Utc>> {
+        let mut fetched_at = Vec::new();
+        for year in AocUtils::event_years() {
+            let (_, at) = self.leaderboards.iter().find(|(y, _)| *y == year)?;
+            fetched_at.push(*at);
+        }
+        fetched_at.into_iter().min().map(|at| at + CACHE_TTL)
+    }
+
+    /// Whether the game itself and its members are unchanged, ignoring leaderboard refreshes
+    fn same_game(&self, other: &GameStateVersion) -> bool {
+        self.game_updated_at == other.game_updated_at && self.memberships == other.memberships
+    }
+}
+
+type CachedState = (GameStateVersion, Arc<GameState>);
+
+/// Computed game states, reused until a leaderboard is due to be fetched again or the game
+/// changes
+#[derive(Clone, Default)]
+pub struct GameStateCache {
+    entries: Arc<Mutex<HashMap<GameId, CachedState>>>,
+}
+
+impl GameStateCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Current state of a game and the version it was computed from
+    pub async fn get(&self, pool: &DbPool, game_id: &str) -> Result<CachedState, GameError> {
+        let before = match GameStateVersion::load(pool, game_id) {
+            Ok(version) => version,
+            Err(e) => {
+                if matches!(e, GameError::NotFound(_)) {
+                    self.entries.lock().unwrap().remove(game_id);
+                }
+                return Err(e);
+            }
+        };
+
+        let is_fresh = before.fresh_until().is_some_and(|until| Utc::now() < until);
+        if is_fresh
+            && let Some((version, state)) = self.entries.lock().unwrap().get(game_id)
+            && *version == before
+        {
+            return Ok((before, state.clone()));
+        }
+
+        let state = Arc::new(GameService::new().get_game_state(pool, game_id).await?);
+        // Computing the state may have refreshed leaderboards, which is part of the version
+        let after = GameStateVersion::load(pool, game_id)?;
+        // A member who joined while computing may be missing from the state, so only keep it if
+        // the game did not change in the meantime
+        if before.same_game(&after) {
+            self.entries
+                .lock()
+                .unwrap()
+                .insert(game_id.to_string(), (after.clone(), state.clone()));
+        }
+        Ok((after, state))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::GameStateVersion;
+    use crate::model::{aoc::PuzzleDate, game::GameStatus};
+
+    fn version() -> GameStateVersion {
+        GameStateVersion {
+            game_id: "abcd1234".to_string(),
+            game_updated_at: Utc.with_ymd_and_hms(2024, 11, 30, 12, 0, 0).unwrap(),
+            status: GameStatus::Running,
+            memberships: vec![(1, 7, Utc.with_ymd_and_hms(2024, 12, 1, 8, 0, 0).unwrap())],
+            leaderboards: vec![(2024, Utc.with_ymd_and_hms(2024, 12, 2, 6, 0, 0).unwrap())],
+            latest_puzzle: PuzzleDate::new(2024, 3).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_etag() {
+        let etag = version().etag();
+        assert_eq!(etag.len(), 34);
+        assert_eq!(etag, version().etag());
+
+        let mut refreshed = version();
+        refreshed.leaderboards[0].1 = Utc.with_ymd_and_hms(2024, 12, 2, 6, 15, 0).unwrap();
+        assert_ne!(etag, refreshed.etag());
+        assert!(version().same_game(&refreshed));
+    }
+
+    #[test]
+    fn test_last_modified() {
+        // Day 3 unlocked after the last leaderboard fetch
+        assert_eq!(
+            version().last_modified(),
+            Utc.with_ymd_and_hms(2024, 12, 3, 5, 0, 0).unwrap()
+        );
+    }
+}
@@ -0,0 +1,275 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    future::Future,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use chrono::Utc;
+use tokio::sync::broadcast;
+
+use crate::{
+    db::DbPool,
+    model::{
+        card::GameCardDto,
+        game::{GameEvent, GameId, GameState, GameStatus},
+        leaderboard::AocMemberId,
+    },
+    service::{GameService, health::HealthMonitor},
+};
+
+/// How often the refresher recomputes all games. Leaderboards are still only fetched from AoC
+/// when their cache expired, so this mostly bounds how late a cache refresh is noticed.
+pub const REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone)]
+pub struct GameEventMessage {
+    pub game_id: GameId,
+    pub event: GameEvent,
+}
+
+/// Someone following a game over a live connection
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct ViewerDto {
+    pub session_id: u64,
+    pub name: String,
+    /// Set if the viewer is playing as this member
+    pub member_id: Option<AocMemberId>,
+}
+
+#[derive(Debug, Clone)]
+pub struct GamePresenceMessage {
+    pub game_id: GameId,
+    pub viewers: Vec<ViewerDto>,
+}
+
+/// Fan-out of game events, game states and presence to everyone following games live
+#[derive(Clone)]
+pub struct GameEventHub {
+    sender: broadcast::Sender<GameEventMessage>,
+    states: broadcast::Sender<Arc<GameState>>,
+    presence: broadcast::Sender<GamePresenceMessage>,
+    viewers: Arc<Mutex<HashMap<GameId, BTreeMap<u64, ViewerDto>>>>,
+    next_session_id: Arc<AtomicU64>,
+}
+
+impl Default for GameEventHub {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GameEventHub {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(1024);
+        let (states, _) = broadcast::channel(256);
+        let (presence, _) = broadcast::channel(256);
+        GameEventHub {
+            sender,
+            states,
+            presence,
+            viewers: Arc::new(Mutex::new(HashMap::new())),
+            next_session_id: Arc::new(AtomicU64::new(1)),
+        }
+    }
+
+    /// Publish an event, it is dropped if nobody is listening
+    pub fn publish(&self, game_id: &str, event: GameEvent) {
+        let _ = self.sender.send(GameEventMessage {
+            game_id: game_id.to_string(),
+            event,
+        });
+    }
+
+    /// Receive events of all games published from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<GameEventMessage> {
+        self.sender.subscribe()
+    }
+
+    /// Publish a freshly computed game state, it is dropped if nobody is listening
+    pub fn publish_state(&self, state: Arc<GameState>) {
+        let _ = self.states.send(state);
+    }
+
+    /// Receive game states of all games published from now on
+    pub fn subscribe_states(&self) -> broadcast::Receiver<Arc<GameState>> {
+        self.states.subscribe()
+    }
+
+    /// Receive viewer lists of all games whenever someone joins or leaves
+    pub fn subscribe_presence(&self) -> broadcast::Receiver<GamePresenceMessage> {
+        self.presence.subscribe()
+    }
+
+    /// Unique id for a live connection
+    pub fn next_session_id(&self) -> u64 {
+        self.next_session_id.fetch_add(1, Ordering::Relaxed)
+    }
+
+    pub fn viewers(&self, game_id: &str) -> Vec<ViewerDto> {
+        let viewers = self.viewers.lock().unwrap();
+        viewers
+            .get(game_id)
+            .map(|viewers| viewers.values().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Register [viewer] as watching [game_id] and announce the new viewer list
+    pub fn join(&self, game_id: &str, viewer: ViewerDto) {
+        self.viewers
+            .lock()
+            .unwrap()
+            .entry(game_id.to_string())
+            .or_default()
+            .insert(viewer.session_id, viewer);
+        self.announce_presence(game_id);
+    }
+
+    /// Remove [session_id] from the viewers of [game_id] and announce the new viewer list
+    pub fn leave(&self, game_id: &str, session_id: u64) {
+        let removed = {
+            let mut viewers = self.viewers.lock().unwrap();
+            let removed = viewers
+                .get_mut(game_id)
+                .and_then(|game_viewers| game_viewers.remove(&session_id))
+                .is_some();
+            viewers.retain(|_, game_viewers| !game_viewers.is_empty());
+            removed
+        };
+        if removed {
+            self.announce_presence(game_id);
+        }
+    }
+
+    fn announce_presence(&self, game_id: &str) {
+        let _ = self.presence.send(GamePresenceMessage {
+            game_id: game_id.to_string(),
+            viewers: self.viewers(game_id),
+        });
+    }
+}
+
+/// Periodically recomputes every game and publishes what changed since the previous run
+pub struct GameRefresher {
+    pool: DbPool,
+    hub: GameEventHub,
+    snapshots: HashMap<GameId, GameState>,
+}
+
+impl GameRefresher {
+    pub fn new(pool: DbPool, hub: GameEventHub) -> Self {
+        GameRefresher {
+            pool,
+            hub,
+            snapshots: HashMap::new(),
+        }
+    }
+
+    /// Refresh every [REFRESH_INTERVAL] until [shutdown] resolves
+    pub async fn run(mut self, shutdown: impl Future<Output = ()>) {
+        tokio::pin!(shutdown);
+        HealthMonitor::global().refresher_started(Utc::now());
+        loop {
+            self.refresh_all().await;
+            tokio::select! {
+                _ = tokio::time::sleep(REFRESH_INTERVAL) => {}
+                _ = &mut shutdown => break,
+            }
+        }
+    }
+
+    pub async fn refresh_all(&mut self) {
+        let service = GameService::new();
+        let games = match self.pool.get() {
+            Ok(conn) => service.get_all_games(&conn),
+            Err(e) => Err(e.into()),
+        };
+        // Archived games are frozen and nobody expects news from them
+        let games = match games {
+            Ok(games) => games
+                .into_iter()
+                .filter(|game| game.status != GameStatus::Archived)
+                .collect::<Vec<_>>(),
+            Err(e) => {
+                tracing::error!(error = %e, "Failed to list games for refresh");
+                return;
+            }
+        };
+
+        self.snapshots
+            .retain(|id, _| games.iter().any(|game| &game.id == id));
+
+        for game in games {
+            match service.get_game_state(&self.pool, &game.id).await {
+                Ok(state) => self.update(state),
+                Err(e) => tracing::warn!(game_id = %game.id, error = %e, "Failed to refresh game"),
+            }
+            HealthMonitor::global().refresh_progressed(Utc::now());
+        }
+        HealthMonitor::global().refresh_completed(Utc::now());
+    }
+
+    /// Store the new state of a game and publish what changed. The first state seen of a game
+    /// only serves as baseline.
+    fn update(&mut self, state: GameState) {
+        let game_id = state.game.id.clone();
+        if let Some(previous) = self.snapshots.get(&game_id) {
+            for event in state.events_since(previous) {
+                self.hub.publish(&game_id, event);
+            }
+            if !GameCardDto::from(previous)
+                .diff(&GameCardDto::from(&state))
+                .is_empty()
+            {
+                self.hub.publish_state(Arc::new(state.clone()));
+            }
+        }
+        self.snapshots.insert(game_id, state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GameEventHub, ViewerDto};
+
+    fn viewer(session_id: u64) -> ViewerDto {
+        ViewerDto {
+            session_id,
+            name: format!("Viewer {}", session_id),
+            member_id: None,
+        }
+    }
+
+    #[test]
+    fn test_presence() {
+        let hub = GameEventHub::new();
+        let mut presence = hub.subscribe_presence();
+
+        hub.join("abcd1234", viewer(1));
+        hub.join("abcd1234", viewer(2));
+        hub.join("efgh5678", viewer(1));
+        assert_eq!(hub.viewers("abcd1234"), vec![viewer(1), viewer(2)]);
+
+        hub.leave("abcd1234", 1);
+        // Leaving a game twice does not announce anything
+        hub.leave("abcd1234", 1);
+        assert_eq!(hub.viewers("abcd1234"), vec![viewer(2)]);
+        assert_eq!(hub.viewers("efgh5678"), vec![viewer(1)]);
+
+        let announced: Vec<_> = std::iter::from_fn(|| presence.try_recv().ok())
+            .map(|message| (message.game_id, message.viewers.len()))
+            .collect();
+        assert_eq!(
+            announced,
+            vec![
+                ("abcd1234".to_string(), 1),
+                ("abcd1234".to_string(), 2),
+                ("efgh5678".to_string(), 1),
+                ("abcd1234".to_string(), 1),
+            ]
+        );
+    }
+}
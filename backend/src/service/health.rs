@@ -0,0 +1,243 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Mutex, OnceLock},
+    time::Duration,
+};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use serde_json::json;
+
+use crate::{
+    db::{DbPool, pending_migrations},
+    model::health::{CheckStatus, HealthCheckDto, ReadinessDto},
+    service::game_events::REFRESH_INTERVAL,
+};
+
+static MONITOR: OnceLock<HealthMonitor> = OnceLock::new();
+
+/// How long the readiness check waits for a database connection
+const DB_CHECKOUT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Number of missed refresher runs without progress after which the refresher counts as stuck
+const MISSED_REFRESHES: i32 = 3;
+
+#[derive(Debug, Default)]
+struct MonitorState {
+    refresher_started_at: Option<DateTime<Utc>>,
+    last_refresh_at: Option<DateTime<Utc>>,
+    /// Last game refreshed, a pass over many games may take longer than the check allows
+    last_progress_at: Option<DateTime<Utc>>,
+    last_upstream_success_at: Option<DateTime<Utc>>,
+    last_upstream_error: Option<(DateTime<Utc>, String)>,
+}
+
+/// What background work and AoC fetches report about themselves, for the readiness check
+#[derive(Debug, Default)]
+pub struct HealthMonitor {
+    state: Mutex<MonitorState>,
+}
+
+impl HealthMonitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The process wide monitor
+    pub fn global() -> &'static Self {
+        MONITOR.get_or_init(Self::new)
+    }
+
+    pub fn refresher_started(&self, at: DateTime<Utc>) {
+        self.state.lock().unwrap().refresher_started_at = Some(at);
+    }
+
+    /// The refresher is done with a game, whether or not refreshing it worked
+    pub fn refresh_progressed(&self, at: DateTime<Utc>) {
+        self.state.lock().unwrap().last_progress_at = Some(at);
+    }
+
+    /// The refresher went through every game
+    pub fn refresh_completed(&self, at: DateTime<Utc>) {
+        let mut state = self.state.lock().unwrap();
+        state.last_refresh_at = Some(at);
+        state.last_progress_at = Some(at);
+    }
+
+    /// AoC answered a leaderboard request, whether or not it accepted the session
+    pub fn upstream_succeeded(&self, at: DateTime<Utc>) {
+        self.state.lock().unwrap().last_upstream_success_at = Some(at);
+    }
+
+    pub fn upstream_failed(&self, at: DateTime<Utc>, error: impl ToString) {
+        self.state.lock().unwrap().last_upstream_error = Some((at, error.to_string()));
+    }
+
+    /// Run every readiness check, the refresher is only checked if it is [refresher_enabled]
+    pub fn readiness(
+        &self,
+        pool: &DbPool,
+        refresher_enabled: bool,
+        now: DateTime<Utc>,
+    ) -> ReadinessDto {
+        let (database, migrations) = Self::check_database(pool);
+        let mut checks = BTreeMap::new();
+        checks.insert("database".to_string(), database);
+        checks.insert("migrations".to_string(), migrations);
+        let refresher = if refresher_enabled {
+            self.check_refresher(now)
+        } else {
+            HealthCheckDto::new(CheckStatus::Pass, "Refresher is disabled")
+        };
+        checks.insert("refresher".to_string(), refresher);
+        checks.insert("upstream".to_string(), self.check_upstream());
+        ReadinessDto::new(checks)
+    }
+
+    /// Connection checkout and migrations, which can only be checked with a connection
+    fn check_database(pool: &DbPool) -> (HealthCheckDto, HealthCheckDto) {
+        let conn = match pool.get_timeout(DB_CHECKOUT_TIMEOUT) {
+            Ok(conn) => conn,
+            Err(e) => {
+                let database = HealthCheckDto::new(CheckStatus::Fail, e.to_string());
+                let migrations =
+                    HealthCheckDto::new(CheckStatus::Fail, "No database connection to check");
+                return (database, migrations);
+            }
+        };
+        let state = pool.state();
+        let database = HealthCheckDto::new(CheckStatus::Pass, "Connection checked out")
+            .with_details(json!({
+                "connections": state.connections,
+                "idle_connections": state.idle_connections,
+                "max_size": pool.max_size(),
+            }));
+
+        let migrations = match pending_migrations(&conn) {
+            Ok(pending) if pending.is_empty() => {
+                HealthCheckDto::new(CheckStatus::Pass, "All migrations applied")
+            }
+            Ok(pending) => HealthCheckDto::new(
+                CheckStatus::Fail,
+                format!("{} migrations not applied", pending.len()),
+            )
+            .with_details(json!({ "pending": pending })),
+            Err(e) => HealthCheckDto::new(CheckStatus::Fail, e.to_string()),
+        };
+        (database, migrations)
+    }
+
+    fn check_refresher(&self, now: DateTime<Utc>) -> HealthCheckDto {
+        let state = self.state.lock().unwrap();
+        let max_age = TimeDelta::from_std(REFRESH_INTERVAL).unwrap() * MISSED_REFRESHES;
+
+        let Some(started_at) = state.refresher_started_at else {
+            return HealthCheckDto::new(CheckStatus::Warn, "Refresher is not running");
+        };
+        let details = json!({
+            "started_at": started_at,
+            "last_run_at": state.last_refresh_at,
+            "last_progress_at": state.last_progress_at,
+            "max_age_seconds": max_age.num_seconds(),
+        });
+        // Before the first game is refreshed, the refresher is as old as its start
+        let age = now - state.last_progress_at.unwrap_or(started_at);
+        if age > max_age {
+            HealthCheckDto::new(
+                CheckStatus::Fail,
+                format!("No game refreshed for {} seconds", age.num_seconds()),
+            )
+            .with_details(details)
+        } else if state.last_refresh_at.is_none() {
+            HealthCheckDto::new(CheckStatus::Pass, "First run in progress").with_details(details)
+        } else {
+            HealthCheckDto::new(CheckStatus::Pass, "Running").with_details(details)
+        }
+    }
+
+    /// Failing AoC fetches degrade the service, cached leaderboards are still served
+    fn check_upstream(&self) -> HealthCheckDto {
+        let state = self.state.lock().unwrap();
+        let details = json!({
+            "last_success_at": state.last_upstream_success_at,
+            "last_error_at": state.last_upstream_error.as_ref().map(|(at, _)| at),
+            "last_error": state.last_upstream_error.as_ref().map(|(_, error)| error),
+        });
+        let status = match (&state.last_upstream_error, state.last_upstream_success_at) {
+            (Some((failed_at, _)), Some(succeeded_at)) if *failed_at < succeeded_at => {
+                CheckStatus::Pass
+            }
+            (Some(_), _) => CheckStatus::Warn,
+            (None, _) => CheckStatus::Pass,
+        };
+        let message = match status {
+            CheckStatus::Pass => "No recent AoC errors",
+            _ => "Most recent AoC fetch failed",
+        };
+        HealthCheckDto::new(status, message).with_details(details)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeDelta, TimeZone, Utc};
+
+    use super::HealthMonitor;
+    use crate::{db::test_pool, model::health::CheckStatus};
+
+    #[test]
+    fn test_readiness() {
+        let pool = test_pool();
+        let monitor = HealthMonitor::new();
+        let start = Utc.with_ymd_and_hms(2024, 12, 1, 5, 0, 0).unwrap();
+
+        let readiness = monitor.readiness(&pool, true, start);
+        assert_eq!(readiness.checks["database"].status, CheckStatus::Pass);
+        assert_eq!(readiness.checks["migrations"].status, CheckStatus::Pass);
+        assert_eq!(readiness.checks["refresher"].status, CheckStatus::Warn);
+        assert_eq!(readiness.status, CheckStatus::Warn);
+        assert!(readiness.is_ready());
+
+        monitor.refresher_started(start);
+        monitor.refresh_completed(start + TimeDelta::seconds(5));
+        monitor.upstream_failed(start, "connection refused");
+        let readiness = monitor.readiness(&pool, true, start + TimeDelta::minutes(1));
+        assert_eq!(readiness.checks["refresher"].status, CheckStatus::Pass);
+        assert_eq!(readiness.checks["upstream"].status, CheckStatus::Warn);
+
+        monitor.upstream_succeeded(start + TimeDelta::seconds(1));
+        let readiness = monitor.readiness(&pool, true, start + TimeDelta::minutes(10));
+        assert_eq!(readiness.checks["upstream"].status, CheckStatus::Pass);
+        assert_eq!(readiness.checks["refresher"].status, CheckStatus::Fail);
+        assert!(!readiness.is_ready());
+    }
+
+    #[test]
+    fn test_long_refresh_pass() {
+        let pool = test_pool();
+        let monitor = HealthMonitor::new();
+        let start = Utc.with_ymd_and_hms(2024, 12, 1, 5, 0, 0).unwrap();
+        monitor.refresher_started(start);
+
+        // The first pass is still going after ten minutes, but keeps refreshing games
+        for minute in 1..=10 {
+            monitor.refresh_progressed(start + TimeDelta::minutes(minute));
+        }
+        let readiness = monitor.readiness(&pool, true, start + TimeDelta::minutes(11));
+        assert_eq!(readiness.checks["refresher"].status, CheckStatus::Pass);
+
+        let readiness = monitor.readiness(&pool, true, start + TimeDelta::minutes(14));
+        assert_eq!(readiness.checks["refresher"].status, CheckStatus::Fail);
+    }
+
+    #[test]
+    fn test_pending_migrations() {
+        let pool = test_pool();
+        pool.get()
+            .unwrap()
+            .execute("DELETE FROM migrations WHERE rowid = 1;", [])
+            .unwrap();
+        let readiness = HealthMonitor::new().readiness(&pool, true, Utc::now());
+        assert_eq!(readiness.checks["migrations"].status, CheckStatus::Fail);
+        assert!(!readiness.is_ready());
+    }
+}
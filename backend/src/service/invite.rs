@@ -0,0 +1,401 @@
+use chrono::Utc;
+use rand::{Rng, distributions::Alphanumeric};
+use thiserror::Error;
+
+use crate::{
+    db::{DbConnection, with_transaction},
+    model::{
+        game::{GameDto, GameMembershipDto},
+        invite::{GameInviteDto, InviteOptions, InviteRedemptionDto, RedemptionStatus},
+        leaderboard::AocMemberId,
+    },
+    repository::{GameRepository, InviteRepository},
+    service::{
+        GameService,
+        game::{GameError, GameMembershipError},
+    },
+};
+
+/// Length of invite codes, long enough that they cannot be guessed
+const CODE_LEN: usize = 16;
+
+pub struct InviteService {}
+
+#[derive(Error, Debug)]
+pub enum InviteError {
+    #[error("Database error: {0}")]
+    DatabaseError(String),
+    #[error(transparent)]
+    Game(#[from] GameError),
+    #[error(transparent)]
+    Membership(#[from] GameMembershipError),
+    #[error("Invite not found: {0}")]
+    NotFound(String),
+    #[error("Invalid invite: {0}")]
+    InvalidOptions(String),
+    #[error("Invite expired")]
+    Expired,
+    #[error("Invite has no uses left")]
+    UsedUp,
+    #[error("Member {0} already awaits approval to join the game")]
+    AlreadyRequested(AocMemberId),
+    #[error("Redemption not found: {0}")]
+    RedemptionNotFound(u32),
+    #[error("Redemption {0} was already decided")]
+    AlreadyDecided(u32),
+}
+
+impl From<rusqlite::Error> for InviteError {
+    fn from(err: rusqlite::Error) -> Self {
+        InviteError::DatabaseError(err.to_string())
+    }
+}
+
+impl From<r2d2::Error> for InviteError {
+    fn from(err: r2d2::Error) -> Self {
+        InviteError::DatabaseError(err.to_string())
+    }
+}
+
+impl Default for InviteService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InviteService {
+    pub fn new() -> Self {
+        InviteService {}
+    }
+
+    /// Generate a random alphanumeric invite code
+    fn generate_code() -> String {
+        rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(CODE_LEN)
+            .map(char::from)
+            .collect()
+    }
+
+    /// Create an invite to a game owned by [session_token]
+    pub fn create_invite(
+        &self,
+        conn: &mut DbConnection,
+        game_id: &str,
+        session_token: &str,
+        options: &InviteOptions,
+    ) -> Result<GameInviteDto, InviteError> {
+        if options.max_uses == Some(0) {
+            return Err(InviteError::InvalidOptions(
+                "max_uses must be at least 1".to_string(),
+            ));
+        }
+        if options.expires_at.is_some_and(|at| at <= Utc::now()) {
+            return Err(InviteError::InvalidOptions(
+                "expires_at must be in the future".to_string(),
+            ));
+        }
+
+        with_transaction(conn, |tx| {
+            GameService::new().get_owned_game(tx, game_id, session_token)?;
+            let invite = InviteRepository::new().create_invite(
+                tx,
+                game_id,
+                &Self::generate_code(),
+                options,
+            )?;
+            tracing::info!(game_id, invite_id = invite.id, "Invite created");
+            Ok(invite)
+        })
+    }
+
+    /// Get all invites to a game owned by [session_token]
+    pub fn get_invites(
+        &self,
+        conn: &DbConnection,
+        game_id: &str,
+        session_token: &str,
+    ) -> Result<Vec<GameInviteDto>, InviteError> {
+        GameService::new().get_owned_game(conn, game_id, session_token)?;
+        InviteRepository::new()
+            .get_invites_by_game(conn, game_id)
+            .map_err(Into::into)
+    }
+
+    /// Delete an invite to a game owned by [session_token], its code can no longer be redeemed
+    pub fn delete_invite(
+        &self,
+        conn: &DbConnection,
+        game_id: &str,
+        session_token: &str,
+        invite_id: u32,
+    ) -> Result<(), InviteError> {
+        GameService::new().get_owned_game(conn, game_id, session_token)?;
+        if !InviteRepository::new().delete_invite(conn, game_id, invite_id)? {
+            return Err(InviteError::NotFound(invite_id.to_string()));
+        }
+        Ok(())
+    }
+
+    /// A redeemable invite and its game
+    pub fn get_invite(
+        &self,
+        conn: &rusqlite::Connection,
+        code: &str,
+    ) -> Result<(GameInviteDto, GameDto), InviteError> {
+        let invite = InviteRepository::new()
+            .get_invite_by_code(conn, code)?
+            .ok_or_else(|| InviteError::NotFound(code.to_string()))?;
+        if invite.is_expired_at(&Utc::now()) {
+            return Err(InviteError::Expired);
+        }
+        if invite.is_used_up() {
+            return Err(InviteError::UsedUp);
+        }
+        let game = GameRepository::new()
+            .get_game(conn, &invite.game_id)?
+            .ok_or_else(|| GameError::NotFound(invite.game_id.clone()))?;
+        Ok((invite, game))
+    }
+
+    /// Join the game of an invite as [member_id], or ask its owner to approve joining. The
+    /// member's name is taken from the game's leaderboard.
+    ///
+    /// Returns the membership if the member joined right away.
+    pub fn redeem(
+        &self,
+        conn: &mut DbConnection,
+        code: &str,
+        member_id: AocMemberId,
+    ) -> Result<(InviteRedemptionDto, Option<GameMembershipDto>), InviteError> {
+        with_transaction(conn, |tx| {
+            let (invite, game) = self.get_invite(tx, code)?;
+            let repo = InviteRepository::new();
+            let games = GameService::new();
+
+            if invite.requires_approval {
+                let member_name = games.check_joinable(tx, &game.id, member_id)?;
+                if repo.has_pending_redemption(tx, &game.id, member_id)? {
+                    return Err(InviteError::AlreadyRequested(member_id));
+                }
+                let redemption = repo.create_redemption(
+                    tx,
+                    &invite,
+                    member_id,
+                    &member_name,
+                    RedemptionStatus::Pending,
+                    None,
+                )?;
+                return Ok((redemption, None));
+            }
+
+            let membership = games.add_member(tx, &game.id, member_id)?;
+            let redemption = repo.create_redemption(
+                tx,
+                &invite,
+                member_id,
+                &membership.member_name,
+                RedemptionStatus::Approved,
+                Some(membership.id),
+            )?;
+            Ok((redemption, Some(membership)))
+        })
+    }
+
+    /// Get all redemptions of invites to a game owned by [session_token], newest first
+    pub fn get_redemptions(
+        &self,
+        conn: &DbConnection,
+        game_id: &str,
+        session_token: &str,
+    ) -> Result<Vec<InviteRedemptionDto>, InviteError> {
+        GameService::new().get_owned_game(conn, game_id, session_token)?;
+        InviteRepository::new()
+            .get_redemptions_by_game(conn, game_id)
+            .map_err(Into::into)
+    }
+
+    /// Approve or reject a pending redemption of an invite to a game owned by [session_token].
+    ///
+    /// Returns the membership if the member joined.
+    pub fn decide_redemption(
+        &self,
+        conn: &mut DbConnection,
+        game_id: &str,
+        session_token: &str,
+        redemption_id: u32,
+        approve: bool,
+    ) -> Result<(InviteRedemptionDto, Option<GameMembershipDto>), InviteError> {
+        with_transaction(conn, |tx| {
+            let games = GameService::new();
+            games.get_owned_game(tx, game_id, session_token)?;
+            let repo = InviteRepository::new();
+            let redemption = repo
+                .get_redemption(tx, game_id, redemption_id)?
+                .ok_or(InviteError::RedemptionNotFound(redemption_id))?;
+            if redemption.status != RedemptionStatus::Pending {
+                return Err(InviteError::AlreadyDecided(redemption_id));
+            }
+
+            if !approve {
+                let redemption =
+                    repo.decide_redemption(tx, redemption_id, RedemptionStatus::Rejected, None)?;
+                return Ok((redemption, None));
+            }
+
+            let membership = games.add_member(tx, game_id, redemption.member_id)?;
+            let redemption = repo.decide_redemption(
+                tx,
+                redemption_id,
+                RedemptionStatus::Approved,
+                Some(membership.id),
+            )?;
+            Ok((redemption, Some(membership)))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeDelta, Utc};
+
+    use super::{InviteError, InviteService};
+    use crate::{
+        db::{DbPool, test_pool},
+        model::invite::{InviteOptions, RedemptionStatus},
+        repository::{GameRepository, InviteRepository, LeaderboardRepository},
+        service::game::{GameError, GameMembershipError},
+    };
+
+    const DATA: &str = r#"{"event":"2024","day1_ts":1733029200,"owner_id":1,"num_days":25,"members":{"1":{"id":1,"local_score":0,"name":"Alice","last_star_ts":0,"stars":0,"completion_day_level":{}},"2":{"id":2,"local_score":0,"name":"Bob","last_star_ts":0,"stars":0,"completion_day_level":{}}}}"#;
+
+    fn pool() -> DbPool {
+        let pool = test_pool();
+        {
+            let conn = pool.get().unwrap();
+            GameRepository::new()
+                .create_game(&conn, "abcd1234", 42, "token", &Default::default())
+                .unwrap();
+            LeaderboardRepository::new()
+                .save_leaderboard(&conn, 2024, 42, DATA)
+                .unwrap();
+        }
+        pool
+    }
+
+    #[test]
+    fn test_redeem() {
+        let pool = pool();
+        let mut conn = pool.get().unwrap();
+        let service = InviteService::new();
+
+        let options = InviteOptions {
+            max_uses: Some(1),
+            ..Default::default()
+        };
+        assert!(matches!(
+            service.create_invite(&mut conn, "abcd1234", "other", &options),
+            Err(InviteError::Game(GameError::NotOwner(_)))
+        ));
+        let invite = service
+            .create_invite(&mut conn, "abcd1234", "token", &options)
+            .unwrap();
+        assert_eq!(invite.code.len(), 16);
+
+        assert!(matches!(
+            service.redeem(&mut conn, "unknown", 1),
+            Err(InviteError::NotFound(_))
+        ));
+        assert!(matches!(
+            service.redeem(&mut conn, &invite.code, 3),
+            Err(InviteError::Membership(
+                GameMembershipError::NotOnLeaderboard(3)
+            ))
+        ));
+
+        let (redemption, membership) = service.redeem(&mut conn, &invite.code, 1).unwrap();
+        assert_eq!(redemption.status, RedemptionStatus::Approved);
+        assert_eq!(redemption.membership_id, membership.map(|m| m.id));
+        assert!(matches!(
+            service.redeem(&mut conn, &invite.code, 2),
+            Err(InviteError::UsedUp)
+        ));
+
+        assert!(matches!(
+            service.create_invite(
+                &mut conn,
+                "abcd1234",
+                "token",
+                &InviteOptions {
+                    expires_at: Some(Utc::now()),
+                    ..Default::default()
+                }
+            ),
+            Err(InviteError::InvalidOptions(_))
+        ));
+        let expired = InviteRepository::new()
+            .create_invite(
+                &conn,
+                "abcd1234",
+                "expired",
+                &InviteOptions {
+                    expires_at: Some(Utc::now() - TimeDelta::minutes(1)),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert!(matches!(
+            service.redeem(&mut conn, &expired.code, 2),
+            Err(InviteError::Expired)
+        ));
+    }
+
+    #[test]
+    fn test_approval() {
+        let pool = pool();
+        let mut conn = pool.get().unwrap();
+        let service = InviteService::new();
+
+        let options = InviteOptions {
+            requires_approval: true,
+            ..Default::default()
+        };
+        let invite = service
+            .create_invite(&mut conn, "abcd1234", "token", &options)
+            .unwrap();
+
+        let (alice, membership) = service.redeem(&mut conn, &invite.code, 1).unwrap();
+        assert_eq!(alice.status, RedemptionStatus::Pending);
+        assert!(membership.is_none());
+        assert!(matches!(
+            service.redeem(&mut conn, &invite.code, 1),
+            Err(InviteError::AlreadyRequested(1))
+        ));
+        let (bob, _) = service.redeem(&mut conn, &invite.code, 2).unwrap();
+        assert_eq!(bob.member_name, "Bob");
+
+        let (alice, membership) = service
+            .decide_redemption(&mut conn, "abcd1234", "token", alice.id, true)
+            .unwrap();
+        assert_eq!(alice.status, RedemptionStatus::Approved);
+        assert_eq!(membership.unwrap().member_name, "Alice");
+        assert!(matches!(
+            service.decide_redemption(&mut conn, "abcd1234", "token", alice.id, false),
+            Err(InviteError::AlreadyDecided(_))
+        ));
+
+        let (bob, membership) = service
+            .decide_redemption(&mut conn, "abcd1234", "token", bob.id, false)
+            .unwrap();
+        assert_eq!(bob.status, RedemptionStatus::Rejected);
+        assert!(membership.is_none());
+
+        let members = GameRepository::new()
+            .get_memberships_by_game(&conn, "abcd1234")
+            .unwrap();
+        assert_eq!(members.len(), 1);
+        let redemptions = service.get_redemptions(&conn, "abcd1234", "token").unwrap();
+        assert_eq!(redemptions.len(), 2);
+        assert_eq!(redemptions[0].id, bob.id);
+    }
+}
@@ -1,18 +1,27 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, time::Instant};
 
 use thiserror::Error;
 
 use crate::{
-    client::AocClient,
+    client::{AocClient, AocClientError},
     db::DbPool,
     model::{
         aoc::{AocPart, AocPuzzle},
         leaderboard::{AocMemberId, LeaderboardDto},
     },
     repository::LeaderboardRepository,
-    service::aoc_utils::AocUtils,
+    service::{
+        aoc_utils::AocUtils,
+        calendar::EventCalendar,
+        health::HealthMonitor,
+        metrics::{FetchOutcome, Metrics},
+    },
 };
 
+/// How long a cached leaderboard is used before it is fetched from AoC again. AoC asks clients
+/// to not request a leaderboard more often than every 15 minutes.
+pub const CACHE_TTL: chrono::TimeDelta = chrono::TimeDelta::minutes(15);
+
 pub struct LeaderboardService {}
 
 #[derive(Error, Debug)]
@@ -23,13 +32,22 @@ pub enum LeaderboardError {
     DatabaseError(String),
     #[error("Failed to fetch leaderboard from AoC: {0}")]
     FetchError(#[from] reqwest::Error),
+    #[error("Session token is invalid or expired")]
+    InvalidSession,
     #[error("Failed to parse leaderboard data: {0}")]
     ParseError(#[from] serde_json::Error),
+    #[error("Invalid leaderboard data: {0}")]
+    InvalidData(String),
 }
 
 impl From<rusqlite::Error> for LeaderboardError {
     fn from(err: rusqlite::Error) -> Self {
-        LeaderboardError::DatabaseError(err.to_string())
+        match err {
+            rusqlite::Error::FromSqlConversionFailure(..) => {
+                LeaderboardError::InvalidData(err.to_string())
+            }
+            _ => LeaderboardError::DatabaseError(err.to_string()),
+        }
     }
 }
 
@@ -47,6 +65,12 @@ pub enum BingoError {
     LeaderboardError(#[from] LeaderboardError),
 }
 
+impl Default for LeaderboardService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl LeaderboardService {
     pub fn new() -> Self {
         LeaderboardService {}
@@ -65,42 +89,87 @@ impl LeaderboardService {
         // Check cache (get connection, use it, release it before async work)
         let cached_result = {
             let conn = pool.get()?;
-            lbr.get_leaderboard(&conn, year, board_id)
+            match lbr.get_leaderboard(&conn, year, board_id) {
+                // An unreadable cache row is overwritten by a fresh fetch if we can
+                Err(e @ rusqlite::Error::FromSqlConversionFailure(..))
+                    if session_token.is_some() =>
+                {
+                    tracing::warn!(
+                        year,
+                        board_id,
+                        error = %e,
+                        "Discarding unreadable cached leaderboard"
+                    );
+                    None
+                }
+                result => result?,
+            }
         };
 
         if let Some(cached) = cached_result {
-            if (chrono::Utc::now() - cached.updated_at).num_seconds() < 900
-                || session_token.is_none()
-            {
-                println!(
-                    "Using cached leaderboard for year {}, board {}, age {} seconds",
+            if chrono::Utc::now() - cached.updated_at < CACHE_TTL || session_token.is_none() {
+                tracing::debug!(
                     year,
                     board_id,
-                    (chrono::Utc::now() - cached.updated_at).num_seconds()
+                    age_seconds = (chrono::Utc::now() - cached.updated_at).num_seconds(),
+                    "Using cached leaderboard"
                 );
+                Metrics::global().leaderboard_cache_hit();
                 return Ok(cached);
             }
         }
+        Metrics::global().leaderboard_cache_miss();
 
-        if session_token.is_none() {
+        let Some(session_token) = session_token else {
             return Err(LeaderboardError::NotCached);
-        }
+        };
+        self.fetch_leaderboard(pool, year, board_id, session_token)
+            .await
+    }
 
-        println!(
-            "Fetching leaderboard for year {}, board {} from AoC",
-            year, board_id
-        );
+    /// Fetch a leaderboard from AoC regardless of the cache and store it in the cache
+    pub async fn fetch_leaderboard(
+        &self,
+        pool: &DbPool,
+        year: u32,
+        board_id: u32,
+        session_token: &str,
+    ) -> Result<LeaderboardDto, LeaderboardError> {
+        tracing::info!(year, board_id, "Fetching leaderboard from AoC");
 
         // Fetch from AoC API (async work without holding connection)
+        let started = Instant::now();
         let response = AocClient::new()
-            .fetch_leaderboard(year, board_id, session_token.unwrap())
-            .await
-            .map_err(LeaderboardError::FetchError)?;
+            .fetch_leaderboard(year, board_id, session_token)
+            .await;
+        let outcome = match &response {
+            Ok(_) => FetchOutcome::Success,
+            Err(AocClientError::InvalidSession(_)) => FetchOutcome::InvalidSession,
+            Err(AocClientError::Request(_)) => FetchOutcome::Error,
+        };
+        Metrics::global().observe_aoc_fetch(outcome, started.elapsed());
+        match &response {
+            Err(AocClientError::Request(e)) => {
+                HealthMonitor::global().upstream_failed(chrono::Utc::now(), e)
+            }
+            _ => HealthMonitor::global().upstream_succeeded(chrono::Utc::now()),
+        }
+        let response = response.map_err(|e| match e {
+            AocClientError::Request(e) => LeaderboardError::FetchError(e),
+            AocClientError::InvalidSession(_) => LeaderboardError::InvalidSession,
+        })?;
+
+        if let Some(schedule) = EventCalendar::global().schedule_for_leaderboard(year, &response) {
+            for conflict in &schedule.conflicts {
+                tracing::warn!(year, ?conflict, "Calendar conflict, using AoC values");
+            }
+        }
 
         // Save to database (get fresh connection)
         let data = serde_json::to_string(&response).map_err(LeaderboardError::ParseError)?;
         let conn = pool.get()?;
-        lbr.save_leaderboard(&conn, year, board_id, &data)
+        LeaderboardRepository::new()
+            .save_leaderboard(&conn, year, board_id, &data)
             .map_err(Into::into)
     }
 
@@ -130,8 +199,7 @@ impl LeaderboardService {
         board_id: u32,
         session_token: Option<&str>,
     ) -> Vec<Result<LeaderboardDto, LeaderboardError>> {
-        let years: Vec<u32> =
-            (AocUtils::earliest_puzzle().year..=AocUtils::latest_puzzle().year).collect();
+        let years = AocUtils::event_years();
         self.get_or_create_leaderboard_range(pool, &years, board_id, session_token)
             .await
     }
@@ -143,11 +211,11 @@ impl LeaderboardService {
         board_id: u32,
         session_token: Option<&str>,
         member_ids: Option<&[AocMemberId]>,
-        game_creation_date: Option<chrono::DateTime<chrono::Utc>>,
+        game_start: Option<chrono::DateTime<chrono::Utc>>,
     ) -> Result<Vec<AocPuzzle>, BingoError> {
         let years = match years {
             Some(y) => y.to_vec(),
-            None => (AocUtils::earliest_puzzle().year..=AocUtils::latest_puzzle().year).collect(),
+            None => AocUtils::event_years(),
         };
         let leaderboards = self
             .get_or_create_leaderboard_range(pool, &years, board_id, session_token)
@@ -156,15 +224,22 @@ impl LeaderboardService {
             .filter_map(|r| r.ok().map(|l| (l.year as u32, l)))
             .collect::<HashMap<_, _>>();
 
-        let all_puzzles = AocUtils::puzzles_for_years(&years);
+        // Prefer the calendar AoC sent along with the leaderboard, it knows the exact unlock times
+        let calendar = EventCalendar::global();
+        let now = chrono::Utc::now();
+        let all_puzzles = years
+            .iter()
+            .filter_map(|&year| match leaderboards.get(&year) {
+                Some(leaderboard) => calendar.schedule_for_leaderboard(year, &leaderboard.data),
+                None => calendar.schedule(year),
+            })
+            .flat_map(|schedule| schedule.playable_puzzles_at(&now))
+            .collect::<Vec<_>>();
 
         let mut bingo_options = Vec::<AocPuzzle>::new();
 
-        let solved_after_game_creation = |ts: u64| -> bool {
-            ts >= game_creation_date
-                .map(|d| d.timestamp() as u64)
-                .unwrap_or(0)
-        };
+        let solved_after_game_start =
+            |ts: u64| -> bool { ts >= game_start.map(|d| d.timestamp() as u64).unwrap_or(0) };
 
         for puzzle in all_puzzles {
             let year = puzzle.date.year;
@@ -189,7 +264,7 @@ impl LeaderboardService {
                                 .get(&day)
                                 .map(|day_completion| {
                                     match day_completion.get(&AocPart::One.into()) {
-                                        Some(t) => solved_after_game_creation(t.get_star_ts),
+                                        Some(t) => solved_after_game_start(t.get_star_ts),
                                         None => true,
                                     }
                                 })
@@ -200,10 +275,6 @@ impl LeaderboardService {
                         }
                     }
                     AocPart::Two => {
-                        if day == AocUtils::get_calendar_size_of_year(year).unwrap() {
-                            // Do not allow part two of the last day
-                            continue;
-                        }
                         // If part two is requested, ensure part one is solved for everyone
                         let nobody_solved_but_meeting_requirements =
                             member_data.iter().all(|member| {
@@ -213,9 +284,7 @@ impl LeaderboardService {
                                     .map(|day_completion| {
                                         day_completion.get(&AocPart::One.into()).is_some()
                                             && match day_completion.get(&AocPart::Two.into()) {
-                                                Some(t) => {
-                                                    solved_after_game_creation(t.get_star_ts)
-                                                }
+                                                Some(t) => solved_after_game_start(t.get_star_ts),
                                                 None => true,
                                             }
                                     })
@@ -228,10 +297,10 @@ impl LeaderboardService {
                                 .get(&day)
                                 .map(|day_completion| {
                                     (match day_completion.get(&AocPart::One.into()) {
-                                        Some(t) => solved_after_game_creation(t.get_star_ts),
+                                        Some(t) => solved_after_game_start(t.get_star_ts),
                                         None => true,
                                     }) && (match day_completion.get(&AocPart::Two.into()) {
-                                        Some(t) => solved_after_game_creation(t.get_star_ts),
+                                        Some(t) => solved_after_game_start(t.get_star_ts),
                                         None => true,
                                     })
                                 })
@@ -0,0 +1,195 @@
+use std::{sync::OnceLock, time::Duration};
+
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry,
+    TextEncoder,
+};
+
+use crate::{db::DbPool, repository::GameRepository};
+
+static METRICS: OnceLock<Metrics> = OnceLock::new();
+
+/// Latency buckets in seconds, from cache hits to slow AoC responses
+const LATENCY_BUCKETS: &[f64] = &[
+    0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Outcome of a leaderboard fetch from AoC
+#[derive(Debug, Clone, Copy)]
+pub enum FetchOutcome {
+    Success,
+    InvalidSession,
+    Error,
+}
+
+impl FetchOutcome {
+    fn label(&self) -> &'static str {
+        match self {
+            FetchOutcome::Success => "success",
+            FetchOutcome::InvalidSession => "invalid_session",
+            FetchOutcome::Error => "error",
+        }
+    }
+}
+
+/// Prometheus metrics of the process, exposed on `GET /metrics`
+pub struct Metrics {
+    registry: Registry,
+    http_requests: IntCounterVec,
+    http_request_duration: HistogramVec,
+    aoc_fetches: IntCounterVec,
+    aoc_fetch_duration: HistogramVec,
+    leaderboard_cache: IntCounterVec,
+    db_pool_connections: IntGaugeVec,
+    games: IntGauge,
+    game_members: IntGauge,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry =
+            Registry::new_custom(Some("bingo".to_string()), None).expect("Invalid metrics prefix");
+        let latency = |name: &str, help: &str| {
+            HistogramOpts::new(name, help).buckets(LATENCY_BUCKETS.to_vec())
+        };
+
+        let metrics = Metrics {
+            http_requests: IntCounterVec::new(
+                Opts::new("http_requests_total", "HTTP requests by route and status"),
+                &["method", "route", "status"],
+            )
+            .unwrap(),
+            http_request_duration: HistogramVec::new(
+                latency(
+                    "http_request_duration_seconds",
+                    "HTTP request latency by route",
+                ),
+                &["method", "route"],
+            )
+            .unwrap(),
+            aoc_fetches: IntCounterVec::new(
+                Opts::new(
+                    "aoc_fetches_total",
+                    "Leaderboard fetches from AoC by outcome",
+                ),
+                &["outcome"],
+            )
+            .unwrap(),
+            aoc_fetch_duration: HistogramVec::new(
+                latency(
+                    "aoc_fetch_duration_seconds",
+                    "Latency of leaderboard fetches from AoC",
+                ),
+                &["outcome"],
+            )
+            .unwrap(),
+            leaderboard_cache: IntCounterVec::new(
+                Opts::new(
+                    "leaderboard_cache_requests_total",
+                    "Leaderboard cache lookups, `hit` or `miss`",
+                ),
+                &["result"],
+            )
+            .unwrap(),
+            db_pool_connections: IntGaugeVec::new(
+                Opts::new(
+                    "db_pool_connections",
+                    "Database pool connections, `idle`, `in_use` or `max`",
+                ),
+                &["state"],
+            )
+            .unwrap(),
+            games: IntGauge::new("games", "Number of games").unwrap(),
+            game_members: IntGauge::new("game_members", "Number of memberships across all games")
+                .unwrap(),
+            registry,
+        };
+
+        let registry = &metrics.registry;
+        registry
+            .register(Box::new(metrics.http_requests.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(metrics.http_request_duration.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(metrics.aoc_fetches.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(metrics.aoc_fetch_duration.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(metrics.leaderboard_cache.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(metrics.db_pool_connections.clone()))
+            .unwrap();
+        registry.register(Box::new(metrics.games.clone())).unwrap();
+        registry
+            .register(Box::new(metrics.game_members.clone()))
+            .unwrap();
+        metrics
+    }
+
+    /// The process wide metrics
+    pub fn global() -> &'static Self {
+        METRICS.get_or_init(Self::new)
+    }
+
+    /// Record a handled request, [route] being the route pattern rather than the requested path
+    pub fn observe_request(&self, method: &str, route: &str, status: u16, elapsed: Duration) {
+        self.http_requests
+            .with_label_values(&[method, route, &status.to_string()])
+            .inc();
+        self.http_request_duration
+            .with_label_values(&[method, route])
+            .observe(elapsed.as_secs_f64());
+    }
+
+    pub fn observe_aoc_fetch(&self, outcome: FetchOutcome, elapsed: Duration) {
+        self.aoc_fetches.with_label_values(&[outcome.label()]).inc();
+        self.aoc_fetch_duration
+            .with_label_values(&[outcome.label()])
+            .observe(elapsed.as_secs_f64());
+    }
+
+    pub fn leaderboard_cache_hit(&self) {
+        self.leaderboard_cache.with_label_values(&["hit"]).inc();
+    }
+
+    pub fn leaderboard_cache_miss(&self) {
+        self.leaderboard_cache.with_label_values(&["miss"]).inc();
+    }
+
+    /// Sample the gauges from [pool] and render every metric in the Prometheus text format
+    pub fn render(&self, pool: &DbPool) -> Result<String, r2d2::Error> {
+        let state = pool.state();
+        let idle = i64::from(state.idle_connections);
+        let pool_gauges = [
+            ("idle", idle),
+            ("in_use", i64::from(state.connections) - idle),
+            ("max", i64::from(pool.max_size())),
+        ];
+        for (label, value) in pool_gauges {
+            self.db_pool_connections
+                .with_label_values(&[label])
+                .set(value);
+        }
+
+        let conn = pool.get()?;
+        match GameRepository::new().count_games_and_memberships(&conn) {
+            Ok((games, members)) => {
+                self.games.set(games as i64);
+                self.game_members.set(members as i64);
+            }
+            // Stale counts are better than no metrics at all
+            Err(e) => tracing::warn!(error = %e, "Failed to count games for metrics"),
+        }
+
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .expect("Failed to encode metrics");
+        Ok(String::from_utf8(buffer).expect("Metrics are not UTF-8"))
+    }
+}
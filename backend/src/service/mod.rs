@@ -1,6 +1,19 @@
 pub mod aoc_utils;
+pub mod board_access;
+pub mod calendar;
 pub mod game;
+pub mod game_cache;
+pub mod game_events;
+pub mod health;
+pub mod invite;
 pub mod leaderboard;
+pub mod metrics;
+pub mod retention;
+pub mod webhook;
 
 pub use game::GameService;
+pub use game_cache::GameStateCache;
+pub use game_events::GameEventHub;
+pub use invite::InviteService;
 pub use leaderboard::LeaderboardService;
+pub use webhook::WebhookService;
@@ -0,0 +1,100 @@
+use std::{future::Future, time::Duration};
+
+use chrono::{DateTime, TimeDelta, Utc};
+
+use crate::{
+    config::RetentionConfig,
+    db::DbPool,
+    service::{GameService, game::GameError},
+};
+
+/// Periodically deletes games that finished or were archived longer ago than configured
+pub struct RetentionJob {
+    pool: DbPool,
+    config: RetentionConfig,
+}
+
+impl RetentionJob {
+    pub fn new(pool: DbPool, config: RetentionConfig) -> Self {
+        RetentionJob { pool, config }
+    }
+
+    /// Purge every `interval_secs` until [shutdown] resolves
+    pub async fn run(self, shutdown: impl Future<Output = ()>) {
+        tokio::pin!(shutdown);
+        loop {
+            match self.purge(Utc::now()) {
+                Ok(0) => {}
+                Ok(deleted) => tracing::info!(deleted, "Deleted expired games"),
+                Err(e) => tracing::error!(error = %e, "Failed to delete expired games"),
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(Duration::from_secs(self.config.interval_secs)) => {}
+                _ = &mut shutdown => break,
+            }
+        }
+    }
+
+    /// Delete the games that expired as of [now], returning how many
+    pub fn purge(&self, now: DateTime<Utc>) -> Result<usize, GameError> {
+        let cutoff = now - TimeDelta::days(self.config.keep_finished_days.into());
+        let conn = self.pool.get()?;
+        GameService::new().purge_expired_games(&conn, cutoff)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeDelta, TimeZone, Utc};
+
+    use super::RetentionJob;
+    use crate::{
+        config::RetentionConfig,
+        db::test_pool,
+        model::game::{FrozenGameState, GameOptions, GameResultDto},
+        repository::GameRepository,
+    };
+
+    #[test]
+    fn test_purge() {
+        let pool = test_pool();
+        let finished_at = Utc.with_ymd_and_hms(2024, 12, 26, 0, 0, 0).unwrap();
+        {
+            let conn = pool.get().unwrap();
+            let repo = GameRepository::new();
+            for id in ["finished", "running1"] {
+                repo.create_game(&conn, id, 42, "token", &GameOptions::default())
+                    .unwrap();
+                repo.create_membership(&conn, id, 1, "Alice").unwrap();
+            }
+            let result = GameResultDto {
+                game_id: "finished".to_string(),
+                finished_at,
+                winner_id: None,
+                standings: vec![],
+            };
+            let final_state = FrozenGameState {
+                members: vec![],
+                puzzles: vec![],
+                completions: Default::default(),
+            };
+            repo.save_result(&conn, &result, &final_state).unwrap();
+        }
+        let job = RetentionJob::new(pool.clone(), RetentionConfig::default());
+
+        assert_eq!(job.purge(finished_at + TimeDelta::days(90)).unwrap(), 0);
+        assert_eq!(job.purge(finished_at + TimeDelta::days(91)).unwrap(), 1);
+
+        let conn = pool.get().unwrap();
+        let repo = GameRepository::new();
+        assert!(repo.get_game(&conn, "finished").unwrap().is_none());
+        assert!(repo.get_result(&conn, "finished").unwrap().is_none());
+        // Memberships went with the game
+        assert!(
+            repo.get_memberships_by_game(&conn, "finished")
+                .unwrap()
+                .is_empty()
+        );
+        assert!(repo.get_game(&conn, "running1").unwrap().is_some());
+    }
+}
@@ -0,0 +1,768 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    time::Duration,
+};
+
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use rand::{Rng, distributions::Alphanumeric};
+use reqwest::{StatusCode, Url};
+use sha2::Sha256;
+use thiserror::Error;
+use tokio::{sync::broadcast::error::RecvError, task::JoinHandle};
+
+use crate::{
+    db::{DbConnection, DbPool},
+    model::{
+        game::GameEvent,
+        leaderboard::AocMemberId,
+        webhook::{GameWebhookDto, WebhookDeliveryDto, WebhookFormat},
+    },
+    repository::{GameRepository, WebhookRepository},
+    service::{GameEventHub, GameService, game::GameError, game_events::GameEventMessage},
+};
+
+/// Events posted to a webhook created without an explicit event list
+pub const DEFAULT_EVENTS: [&str; 4] = [
+    "star_earned",
+    "bingo_achieved",
+    "game_won",
+    "session_expired",
+];
+
+/// Number of delivery attempts returned by [WebhookService::get_deliveries]
+const DELIVERY_LOG_LIMIT: u32 = 100;
+
+pub struct WebhookService {}
+
+#[derive(Error, Debug)]
+pub enum WebhookError {
+    #[error("Database error: {0}")]
+    DatabaseError(String),
+    #[error(transparent)]
+    Game(#[from] GameError),
+    #[error("Webhook not found: {0}")]
+    NotFound(u32),
+    #[error("Invalid webhook URL: {0}")]
+    InvalidUrl(String),
+    #[error("Webhook URL {0} points to a private address")]
+    PrivateAddress(String),
+    #[error("Unknown event: {0}")]
+    UnknownEvent(String),
+}
+
+impl From<rusqlite::Error> for WebhookError {
+    fn from(err: rusqlite::Error) -> Self {
+        WebhookError::DatabaseError(err.to_string())
+    }
+}
+
+impl From<r2d2::Error> for WebhookError {
+    fn from(err: r2d2::Error) -> Self {
+        WebhookError::DatabaseError(err.to_string())
+    }
+}
+
+impl Default for WebhookService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WebhookService {
+    pub fn new() -> Self {
+        WebhookService {}
+    }
+
+    /// Generate a random 32-character alphanumeric signing secret
+    fn generate_secret() -> String {
+        rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(32)
+            .map(char::from)
+            .collect()
+    }
+
+    /// Whether [ip] is only reachable from inside the server's own network
+    fn is_private(ip: IpAddr) -> bool {
+        match ip {
+            IpAddr::V4(ip) => Self::is_private_v4(ip),
+            IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+                Some(ip) => Self::is_private_v4(ip),
+                None => Self::is_private_v6(ip),
+            },
+        }
+    }
+
+    fn is_private_v4(ip: Ipv4Addr) -> bool {
+        ip.is_loopback()
+            || ip.is_private()
+            || ip.is_link_local()
+            || ip.is_unspecified()
+            || ip.is_broadcast()
+    }
+
+    fn is_private_v6(ip: Ipv6Addr) -> bool {
+        ip.is_loopback()
+            || ip.is_unspecified()
+            || ip.is_unique_local()
+            || ip.is_unicast_link_local()
+    }
+
+    /// Only http(s) URLs whose host resolves to public addresses, so that webhooks cannot
+    /// reach services on the server's network
+    fn validate_url(url: &str) -> Result<(), WebhookError> {
+        let parsed = match Url::parse(url) {
+            Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => parsed,
+            _ => return Err(WebhookError::InvalidUrl(url.to_string())),
+        };
+        let addrs = parsed
+            .socket_addrs(|| None)
+            .map_err(|_| WebhookError::InvalidUrl(url.to_string()))?;
+        if addrs.is_empty() {
+            return Err(WebhookError::InvalidUrl(url.to_string()));
+        }
+        if addrs.iter().any(|addr| Self::is_private(addr.ip())) {
+            return Err(WebhookError::PrivateAddress(url.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Subscribe [url] to events of a game owned by [session_token], [events] defaults to
+    /// [DEFAULT_EVENTS]
+    pub fn create_webhook(
+        &self,
+        conn: &DbConnection,
+        game_id: &str,
+        session_token: &str,
+        url: &str,
+        format: WebhookFormat,
+        events: Option<Vec<String>>,
+    ) -> Result<GameWebhookDto, WebhookError> {
+        let events = match events {
+            Some(events) if !events.is_empty() => events,
+            _ => DEFAULT_EVENTS.iter().map(|e| e.to_string()).collect(),
+        };
+        if let Some(unknown) = events
+            .iter()
+            .find(|e| !GameEvent::NAMES.contains(&e.as_str()))
+        {
+            return Err(WebhookError::UnknownEvent(unknown.clone()));
+        }
+
+        GameService::new().get_owned_game(conn, game_id, session_token)?;
+        // Resolved only after the ownership check, so others cannot probe hosts through it
+        Self::validate_url(url)?;
+
+        WebhookRepository::new()
+            .create_webhook(
+                conn,
+                game_id,
+                url,
+                format,
+                &events,
+                &Self::generate_secret(),
+            )
+            .map_err(Into::into)
+    }
+
+    /// Get all webhooks of a game owned by [session_token]
+    pub fn get_webhooks(
+        &self,
+        conn: &DbConnection,
+        game_id: &str,
+        session_token: &str,
+    ) -> Result<Vec<GameWebhookDto>, WebhookError> {
+        GameService::new().get_owned_game(conn, game_id, session_token)?;
+        WebhookRepository::new()
+            .get_webhooks_by_game(conn, game_id)
+            .map_err(Into::into)
+    }
+
+    /// Delete a webhook of a game owned by [session_token] together with its delivery log
+    pub fn delete_webhook(
+        &self,
+        conn: &DbConnection,
+        game_id: &str,
+        session_token: &str,
+        webhook_id: u32,
+    ) -> Result<(), WebhookError> {
+        GameService::new().get_owned_game(conn, game_id, session_token)?;
+        if WebhookRepository::new().delete_webhook(conn, game_id, webhook_id)? {
+            Ok(())
+        } else {
+            Err(WebhookError::NotFound(webhook_id))
+        }
+    }
+
+    /// Most recent delivery attempts of a webhook of a game owned by [session_token], newest
+    /// first
+    pub fn get_deliveries(
+        &self,
+        conn: &DbConnection,
+        game_id: &str,
+        session_token: &str,
+        webhook_id: u32,
+    ) -> Result<Vec<WebhookDeliveryDto>, WebhookError> {
+        let webhooks = self.get_webhooks(conn, game_id, session_token)?;
+        if !webhooks.iter().any(|webhook| webhook.id == webhook_id) {
+            return Err(WebhookError::NotFound(webhook_id));
+        }
+        WebhookRepository::new()
+            .get_deliveries(conn, webhook_id, DELIVERY_LOG_LIMIT)
+            .map_err(Into::into)
+    }
+}
+
+/// How often and how patiently a delivery is retried
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    /// Wait after the first failed attempt, doubled after every further failure
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub timeout: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 5,
+            initial_backoff: Duration::from_secs(2),
+            max_backoff: Duration::from_secs(300),
+            timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Wait before the attempt following [attempt], counting from 1
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(self.max_backoff)
+    }
+}
+
+/// Result of a single delivery attempt
+enum Attempt {
+    Delivered,
+    Retry,
+    GiveUp,
+}
+
+/// HMAC-SHA256 of `{timestamp}.{body}` with the webhook secret, hex encoded
+pub fn signature(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(format!("{}.{}", timestamp, body).as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Human readable description of an event for chat formats
+fn describe(game_id: &str, event: &GameEvent, names: &HashMap<AocMemberId, String>) -> String {
+    let name = |member_id: &AocMemberId| {
+        names
+            .get(member_id)
+            .cloned()
+            .unwrap_or_else(|| format!("Member #{}", member_id))
+    };
+    let text = match event {
+        GameEvent::StarEarned {
+            member_id, puzzle, ..
+        } => format!(
+            "⭐ {} solved {} day {} part {}",
+            name(member_id),
+            puzzle.date.year,
+            puzzle.date.day,
+            u32::from(puzzle.part.clone())
+        ),
+        GameEvent::ScoreChanged {
+            member_id,
+            score,
+            previous_score,
+        } => format!(
+            "{} now has {} points (was {})",
+            name(member_id),
+            score,
+            previous_score
+        ),
+        GameEvent::BingoAchieved { member_id } => {
+            format!("🎉 {} completed the card", name(member_id))
+        }
+        GameEvent::MemberJoined { membership } => {
+            format!("{} joined the game", membership.member_name)
+        }
+        GameEvent::GameWon { member_id } => format!("🏆 {} won the game", name(member_id)),
+        GameEvent::SessionExpired => {
+            "⚠️ AoC rejected the session token, the game no longer updates".to_string()
+        }
+    };
+    format!("[Bingo {}] {}", game_id, text)
+}
+
+/// Body posted to a webhook for an event
+pub fn payload(
+    format: WebhookFormat,
+    message: &GameEventMessage,
+    names: &HashMap<AocMemberId, String>,
+) -> serde_json::Value {
+    match format {
+        WebhookFormat::Json => serde_json::json!({
+            "game_id": message.game_id,
+            "event": message.event,
+            "sent_at": Utc::now(),
+        }),
+        WebhookFormat::Discord => serde_json::json!({
+            "content": describe(&message.game_id, &message.event, names),
+        }),
+        WebhookFormat::Slack => serde_json::json!({
+            "text": describe(&message.game_id, &message.event, names),
+        }),
+    }
+}
+
+/// Posts game events published on the [GameEventHub] to the webhooks of their game
+#[derive(Clone)]
+pub struct WebhookDispatcher {
+    pool: DbPool,
+    client: reqwest::Client,
+    retry: RetryPolicy,
+}
+
+impl WebhookDispatcher {
+    pub fn new(pool: DbPool, retry: RetryPolicy) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(retry.timeout)
+            .build()
+            .expect("Failed to build webhook HTTP client");
+        WebhookDispatcher {
+            pool,
+            client,
+            retry,
+        }
+    }
+
+    /// Dispatch events from [hub] until [shutdown] resolves. Deliveries still being retried
+    /// at shutdown are abandoned.
+    pub async fn run(self, hub: GameEventHub, shutdown: impl Future<Output = ()>) {
+        let mut receiver = hub.subscribe();
+        tokio::pin!(shutdown);
+        loop {
+            let message = tokio::select! {
+                message = receiver.recv() => match message {
+                    Ok(message) => message,
+                    Err(RecvError::Lagged(missed)) => {
+                        tracing::warn!(missed, "Webhook dispatcher missed events");
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+                _ = &mut shutdown => break,
+            };
+            self.dispatch(&message);
+        }
+    }
+
+    /// Start delivering [message] to every webhook of its game subscribed to the event.
+    /// Each handle resolves to whether its webhook accepted the event.
+    pub fn dispatch(&self, message: &GameEventMessage) -> Vec<JoinHandle<bool>> {
+        let (webhooks, names) = match self.load(&message.game_id) {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                tracing::error!(game_id = %message.game_id, error = %e, "Failed to load webhooks");
+                return Vec::new();
+            }
+        };
+
+        webhooks
+            .into_iter()
+            .filter(|webhook| webhook.events.iter().any(|e| e == message.event.name()))
+            .map(|webhook| {
+                let body = payload(webhook.format, message, &names).to_string();
+                let event = message.event.name();
+                let dispatcher = self.clone();
+                tokio::spawn(async move { dispatcher.deliver(&webhook, event, &body).await })
+            })
+            .collect()
+    }
+
+    /// Webhooks of a game and the names of its members
+    fn load(
+        &self,
+        game_id: &str,
+    ) -> Result<(Vec<GameWebhookDto>, HashMap<AocMemberId, String>), WebhookError> {
+        let conn = self.pool.get()?;
+        let webhooks = WebhookRepository::new().get_webhooks_by_game(&conn, game_id)?;
+        if webhooks.is_empty() {
+            return Ok((webhooks, HashMap::new()));
+        }
+        let names = GameRepository::new()
+            .get_memberships_by_game(&conn, game_id)?
+            .into_iter()
+            .map(|membership| (membership.member_id, membership.member_name))
+            .collect();
+        Ok((webhooks, names))
+    }
+
+    /// Post [body] to [webhook], retrying according to the [RetryPolicy]
+    #[tracing::instrument(skip_all, fields(webhook_id = webhook.id, game_id = %webhook.game_id, event))]
+    pub async fn deliver(&self, webhook: &GameWebhookDto, event: &str, body: &str) -> bool {
+        for attempt in 1..=self.retry.max_attempts {
+            match self.attempt(webhook, event, body, attempt).await {
+                Attempt::Delivered => return true,
+                Attempt::GiveUp => return false,
+                Attempt::Retry if attempt < self.retry.max_attempts => {
+                    tokio::time::sleep(self.retry.backoff(attempt)).await;
+                }
+                Attempt::Retry => {}
+            }
+        }
+        false
+    }
+
+    async fn attempt(
+        &self,
+        webhook: &GameWebhookDto,
+        event: &str,
+        body: &str,
+        attempt: u32,
+    ) -> Attempt {
+        let timestamp = Utc::now().timestamp();
+        let response = self
+            .client
+            .post(&webhook.url)
+            .header("Content-Type", "application/json")
+            .header("X-Bingo-Event", event)
+            .header("X-Bingo-Timestamp", timestamp.to_string())
+            .header(
+                "X-Bingo-Signature",
+                format!("sha256={}", signature(&webhook.secret, timestamp, body)),
+            )
+            .body(body.to_string())
+            .send()
+            .await;
+
+        let (outcome, status_code, error) = match response {
+            Ok(response) => {
+                let status = response.status();
+                let outcome = if status.is_success() {
+                    Attempt::Delivered
+                } else if status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS {
+                    Attempt::Retry
+                } else {
+                    Attempt::GiveUp
+                };
+                let error = (!status.is_success()).then(|| status.to_string());
+                (outcome, Some(status.as_u16()), error)
+            }
+            Err(e) => (Attempt::Retry, None, Some(e.to_string())),
+        };
+        tracing::debug!(
+            attempt,
+            status = status_code,
+            error = error.as_deref(),
+            "Webhook delivery attempted"
+        );
+
+        let logged = self.pool.get().map_err(|e| e.to_string()).and_then(|conn| {
+            WebhookRepository::new()
+                .log_delivery(
+                    &conn,
+                    webhook.id,
+                    event,
+                    attempt,
+                    status_code,
+                    error.as_deref(),
+                )
+                .map_err(|e| e.to_string())
+        });
+        if let Err(e) = logged {
+            tracing::error!(webhook_id = webhook.id, error = %e, "Failed to log webhook delivery");
+        }
+
+        outcome
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use chrono::Utc;
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{method, path},
+    };
+
+    use super::{
+        DEFAULT_EVENTS, RetryPolicy, WebhookDispatcher, WebhookError, WebhookService, signature,
+    };
+    use crate::{
+        db::{DbPool, test_pool},
+        model::{
+            aoc::{AocPart, PuzzleDate},
+            game::GameEvent,
+            webhook::{GameWebhookDto, WebhookFormat},
+        },
+        repository::{GameRepository, WebhookRepository},
+        service::{game::GameError, game_events::GameEventMessage},
+    };
+
+    const GAME_ID: &str = "abcd1234";
+
+    fn setup() -> DbPool {
+        let pool = test_pool();
+        let conn = pool.get().unwrap();
+        let repo = GameRepository::new();
+        repo.create_game(&conn, GAME_ID, 42, "token", &Default::default())
+            .unwrap();
+        repo.create_membership(&conn, GAME_ID, 7, "Alice").unwrap();
+        pool
+    }
+
+    /// Stored directly, the service refuses the loopback address of the mock server
+    fn create_webhook(
+        pool: &DbPool,
+        url: String,
+        format: WebhookFormat,
+        events: Option<Vec<String>>,
+    ) -> GameWebhookDto {
+        let conn = pool.get().unwrap();
+        let events = events.unwrap_or_else(|| DEFAULT_EVENTS.map(String::from).to_vec());
+        WebhookRepository::new()
+            .create_webhook(&conn, GAME_ID, &url, format, &events, "secret")
+            .unwrap()
+    }
+
+    fn dispatcher(pool: &DbPool) -> WebhookDispatcher {
+        WebhookDispatcher::new(
+            pool.clone(),
+            RetryPolicy {
+                max_attempts: 3,
+                initial_backoff: Duration::from_millis(10),
+                max_backoff: Duration::from_millis(20),
+                timeout: Duration::from_secs(5),
+            },
+        )
+    }
+
+    fn star_earned() -> GameEventMessage {
+        GameEventMessage {
+            game_id: GAME_ID.to_string(),
+            event: GameEvent::StarEarned {
+                member_id: 7,
+                puzzle: PuzzleDate::new(2024, 3)
+                    .unwrap()
+                    .to_aoc_puzzle_part(AocPart::Two),
+                completed_at: Utc::now(),
+            },
+        }
+    }
+
+    async fn dispatch(dispatcher: &WebhookDispatcher, message: &GameEventMessage) -> Vec<bool> {
+        let mut results = Vec::new();
+        for handle in dispatcher.dispatch(message) {
+            results.push(handle.await.unwrap());
+        }
+        results
+    }
+
+    fn attempts(pool: &DbPool, webhook: &GameWebhookDto) -> Vec<(u32, Option<u16>)> {
+        let conn = pool.get().unwrap();
+        let mut deliveries = WebhookService::new()
+            .get_deliveries(&conn, GAME_ID, "token", webhook.id)
+            .unwrap();
+        deliveries.reverse();
+        deliveries
+            .into_iter()
+            .map(|delivery| (delivery.attempt, delivery.status_code))
+            .collect()
+    }
+
+    #[test]
+    fn test_create_webhook_validation() {
+        let pool = setup();
+        let conn = pool.get().unwrap();
+        let service = WebhookService::new();
+        // A public address, so that the test does not depend on DNS
+        let url = "https://1.1.1.1/hook";
+        let create = |game_id: &str, token: &str, url: &str, events: Option<Vec<String>>| {
+            service.create_webhook(&conn, game_id, token, url, WebhookFormat::Json, events)
+        };
+
+        let result = create(GAME_ID, "token", "ftp://example.com", None);
+        assert!(matches!(result, Err(WebhookError::InvalidUrl(_))));
+
+        let result = create(GAME_ID, "token", url, Some(vec!["star_lost".to_string()]));
+        assert!(matches!(result, Err(WebhookError::UnknownEvent(e)) if e == "star_lost"));
+
+        let result = create("unknown1", "token", url, None);
+        assert!(matches!(
+            result,
+            Err(WebhookError::Game(GameError::NotFound(_)))
+        ));
+        let result = create(GAME_ID, "other", url, None);
+        assert!(matches!(
+            result,
+            Err(WebhookError::Game(GameError::NotOwner(_)))
+        ));
+
+        for private in [
+            "http://localhost:8000/hook",
+            "http://127.0.0.1/hook",
+            "http://10.0.0.5/hook",
+            "http://192.168.1.1/hook",
+            "http://169.254.169.254/latest/meta-data",
+            "http://[::1]/hook",
+            "http://[fd00::1]/hook",
+            "http://[::ffff:127.0.0.1]/hook",
+        ] {
+            let result = create(GAME_ID, "token", private, None);
+            assert!(
+                matches!(result, Err(WebhookError::PrivateAddress(_))),
+                "{}: {:?}",
+                private,
+                result
+            );
+        }
+
+        let webhook = service
+            .create_webhook(&conn, GAME_ID, "token", url, WebhookFormat::Slack, None)
+            .unwrap();
+        assert_eq!(
+            webhook.events,
+            vec![
+                "star_earned",
+                "bingo_achieved",
+                "game_won",
+                "session_expired"
+            ]
+        );
+        assert_eq!(webhook.secret.len(), 32);
+    }
+
+    #[tokio::test]
+    async fn test_signed_delivery() {
+        let pool = setup();
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/hook"))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let webhook = create_webhook(
+            &pool,
+            format!("{}/hook", server.uri()),
+            WebhookFormat::Json,
+            None,
+        );
+
+        let results = dispatch(&dispatcher(&pool), &star_earned()).await;
+        assert_eq!(results, vec![true]);
+
+        let requests = server.received_requests().await.unwrap();
+        let request = &requests[0];
+        let header = |name: &str| request.headers.get(name).unwrap().to_str().unwrap();
+        let body = String::from_utf8(request.body.clone()).unwrap();
+        let timestamp: i64 = header("X-Bingo-Timestamp").parse().unwrap();
+        assert_eq!(header("X-Bingo-Event"), "star_earned");
+        assert_eq!(
+            header("X-Bingo-Signature"),
+            format!("sha256={}", signature(&webhook.secret, timestamp, &body))
+        );
+
+        let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body["game_id"], GAME_ID);
+        assert_eq!(body["event"]["type"], "star_earned");
+        assert_eq!(attempts(&pool, &webhook), vec![(1, Some(204))]);
+    }
+
+    #[tokio::test]
+    async fn test_retry_then_success() {
+        let pool = setup();
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+        let webhook = create_webhook(&pool, server.uri(), WebhookFormat::Discord, None);
+
+        let results = dispatch(&dispatcher(&pool), &star_earned()).await;
+        assert_eq!(results, vec![true]);
+        assert_eq!(
+            attempts(&pool, &webhook),
+            vec![(1, Some(503)), (2, Some(503)), (3, Some(200))]
+        );
+
+        let requests = server.received_requests().await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&requests[2].body).unwrap();
+        assert_eq!(
+            body["content"],
+            "[Bingo abcd1234] ⭐ Alice solved 2024 day 3 part 2"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_give_up() {
+        let pool = setup();
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+        let webhook = create_webhook(&pool, server.uri(), WebhookFormat::Slack, None);
+
+        // Client errors other than 429 are not retried
+        let results = dispatch(&dispatcher(&pool), &star_earned()).await;
+        assert_eq!(results, vec![false]);
+        assert_eq!(attempts(&pool, &webhook), vec![(1, Some(404))]);
+    }
+
+    #[tokio::test]
+    async fn test_event_filter() {
+        let pool = setup();
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&server)
+            .await;
+        create_webhook(
+            &pool,
+            server.uri(),
+            WebhookFormat::Json,
+            Some(vec!["game_won".to_string()]),
+        );
+
+        assert!(
+            dispatch(&dispatcher(&pool), &star_earned())
+                .await
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_backoff() {
+        let policy = RetryPolicy::default();
+        let backoffs: Vec<_> = (1..=4).map(|attempt| policy.backoff(attempt)).collect();
+        assert_eq!(
+            backoffs,
+            vec![
+                Duration::from_secs(2),
+                Duration::from_secs(4),
+                Duration::from_secs(8),
+                Duration::from_secs(16)
+            ]
+        );
+        assert_eq!(policy.backoff(20), Duration::from_secs(300));
+    }
+}
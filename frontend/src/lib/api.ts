@@ -1,9 +1,20 @@
 const BACKEND_URL = import.meta.env.VITE_BACKEND_URL || 'http://localhost:8000';
 
+export type GameStatus = 'scheduled' | 'running' | 'finished' | 'archived';
+
+export type GameVisibility = 'unlisted' | 'listed';
+
 export interface GameDto {
     id: string;
     leaderboard_id: number;
     session_token: string;
+    starts_at: string;
+    ends_at: string | null;
+    status: GameStatus;
+    title: string | null;
+    description: string | null;
+    creator_name: string | null;
+    visibility: GameVisibility;
     created_at: string;
     updated_at: string;
 }
@@ -48,6 +59,23 @@ export interface GetAllPuzzlesResponse {
     game_id: string;
 }
 
+/** Error body of failed backend requests, `code` is stable and `message` human readable */
+export interface ApiError {
+    code: string;
+    message: string;
+    details: Record<string, unknown> | null;
+}
+
+async function readError(response: Response): Promise<string> {
+    const text = await response.text();
+    try {
+        const error: ApiError = JSON.parse(text);
+        return error.message;
+    } catch {
+        return text;
+    }
+}
+
 export async function createGame(leaderboardId: number, sessionToken: string): Promise<GameDto> {
     const response = await fetch(`${BACKEND_URL}/game`, {
         method: 'POST',
@@ -61,7 +89,7 @@ export async function createGame(leaderboardId: number, sessionToken: string): P
     });
 
     if (!response.ok) {
-        const error = await response.text();
+        const error = await readError(response);
         throw new Error(`Failed to create game: ${error}`);
     }
 
@@ -78,7 +106,7 @@ export async function getGameMembers(gameId: string): Promise<GetGameMembersResp
     });
 
     if (!response.ok) {
-        const error = await response.text();
+        const error = await readError(response);
         throw new Error(`Failed to get game members: ${error}`);
     }
 
@@ -94,7 +122,7 @@ export async function getGamePuzzles(gameId: string): Promise<GetAllPuzzlesRespo
     });
 
     if (!response.ok) {
-        const error = await response.text();
+        const error = await readError(response);
         throw new Error(`Failed to get game puzzles: ${error}`);
     }
 
@@ -110,8 +138,12 @@ export interface CreateMembershipResponse {
     membership: GameMembershipDto;
 }
 
+/**
+ * Only the game's owner may add members, [sessionToken] is the token the game was created with
+ */
 export async function addGameMember(
     gameId: string,
+    sessionToken: string,
     memberId: number,
     memberName: string
 ): Promise<GameMembershipDto> {
@@ -119,6 +151,7 @@ export async function addGameMember(
         method: 'POST',
         headers: {
             'Content-Type': 'application/json',
+            'Authorization': `Bearer ${sessionToken}`,
         },
         body: JSON.stringify({
             member_id: memberId,
@@ -127,7 +160,7 @@ export async function addGameMember(
     });
 
     if (!response.ok) {
-        const error = await response.text();
+        const error = await readError(response);
         throw new Error(`Failed to add game member: ${error}`);
     }
 
@@ -135,16 +168,24 @@ export async function addGameMember(
     return data.membership;
 }
 
-export async function removeGameMember(gameId: string, memberId: number): Promise<void> {
+/**
+ * Only the game's owner may remove members
+ */
+export async function removeGameMember(
+    gameId: string,
+    sessionToken: string,
+    memberId: number
+): Promise<void> {
     const response = await fetch(`${BACKEND_URL}/game/${gameId}/members/${memberId}`, {
         method: 'DELETE',
         headers: {
             'Content-Type': 'application/json',
+            'Authorization': `Bearer ${sessionToken}`,
         },
     });
 
     if (!response.ok) {
-        const error = await response.text();
+        const error = await readError(response);
         throw new Error(`Failed to remove game member: ${error}`);
     }
 }
@@ -173,9 +214,44 @@ export async function getGameCompletion(gameId: string): Promise<GetCompletionRe
     });
 
     if (!response.ok) {
-        const error = await response.text();
+        const error = await readError(response);
         throw new Error(`Failed to get game completion: ${error}`);
     }
 
     return await response.json();
 }
+
+/**
+ * Live events pushed by the backend for a game
+ */
+export type GameEvent =
+    | { type: 'star_earned'; member_id: number; puzzle: AocPuzzle; completed_at: string }
+    | { type: 'score_changed'; member_id: number; score: number; previous_score: number }
+    | { type: 'bingo_achieved'; member_id: number }
+    | { type: 'member_joined'; membership: GameMembershipDto }
+    | { type: 'game_won'; member_id: number }
+    | { type: 'session_expired' };
+
+const GAME_EVENT_TYPES: GameEvent['type'][] = [
+    'star_earned',
+    'score_changed',
+    'bingo_achieved',
+    'member_joined',
+    'game_won',
+    'session_expired',
+];
+
+/**
+ * Subscribes to live events of a game via Server-Sent Events
+ * Returns a function that closes the subscription
+ */
+export function subscribeToGameEvents(gameId: string, onEvent: (event: GameEvent) => void): () => void {
+    // Only served under /v1, unlike the older routes used above
+    const source = new EventSource(`${BACKEND_URL}/v1/game/${gameId}/events`);
+    for (const type of GAME_EVENT_TYPES) {
+        source.addEventListener(type, (message) => {
+            onEvent(JSON.parse((message as MessageEvent).data));
+        });
+    }
+    return () => source.close();
+}
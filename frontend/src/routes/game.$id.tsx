@@ -2,7 +2,7 @@ import { createFileRoute } from '@tanstack/react-router'
 import _ from 'lodash';
 import { useMemo, useEffect, useState } from 'react';
 import { useLeaderboardContext } from '@/contexts/LeaderboardContext';
-import { getGameMembers, getGamePuzzles, getGameCompletion, addGameMember, removeGameMember, type GameLeaderboardMemberDto, type GameMembershipDto } from '@/lib/api';
+import { getGameMembers, getGamePuzzles, getGameCompletion, addGameMember, removeGameMember, subscribeToGameEvents, type GameLeaderboardMemberDto, type GameMembershipDto } from '@/lib/api';
 import { useQuery, useMutation, useQueryClient } from '@tanstack/react-query';
 
 export const Route = createFileRoute('/game/$id')({
@@ -12,7 +12,7 @@ export const Route = createFileRoute('/game/$id')({
 function RouteComponent() {
     const { id } = Route.useParams();
     const queryClient = useQueryClient();
-    const { gameMemberships, setGameMemberships } = useLeaderboardContext();
+    const { sessionToken, gameMemberships, setGameMemberships } = useLeaderboardContext();
     const [members, setMembers] = useState<GameMembershipDto[] | null>(null);
     const [possibleMembers, setPossibleMembers] = useState<GameLeaderboardMemberDto[] | null>(null);
     const [scoredMembers, setScoredMembers] = useState<(GameMembershipDto & { score: number })[] | null>(null);
@@ -79,6 +79,20 @@ function RouteComponent() {
         refetchInterval: 900_000,
     })
 
+    /**
+     * Refetch as soon as the backend reports changes instead of waiting for the next poll
+     */
+    useEffect(() => {
+        if (!id) return;
+        return subscribeToGameEvents(id, (event) => {
+            if (event.type === 'member_joined') {
+                queryClient.invalidateQueries({ queryKey: ['gameMembers', id] });
+                queryClient.invalidateQueries({ queryKey: ['gamePuzzles', id] });
+            }
+            queryClient.invalidateQueries({ queryKey: ['gameCompletion', id] });
+        });
+    }, [id, queryClient]);
+
     /**
      * Update member scores based on completion data
      * Points are awarded for each puzzle based on:
@@ -133,7 +147,8 @@ function RouteComponent() {
     const addMemberMutation = useMutation({
         mutationFn: async ({ memberId, memberName }: { memberId: number; memberName: string }) => {
             if (!id) throw new Error('Game ID is required');
-            return await addGameMember(id, memberId, memberName);
+            if (!sessionToken) throw new Error('Only the game owner can add members');
+            return await addGameMember(id, sessionToken, memberId, memberName);
         },
         onSuccess: () => {
             queryClient.invalidateQueries({ queryKey: ['gameMembers', id] });
@@ -145,7 +160,8 @@ function RouteComponent() {
     const removeMemberMutation = useMutation({
         mutationFn: async (memberId: number) => {
             if (!id) throw new Error('Game ID is required');
-            return await removeGameMember(id, memberId);
+            if (!sessionToken) throw new Error('Only the game owner can remove members');
+            return await removeGameMember(id, sessionToken, memberId);
         },
         onSuccess: () => {
             queryClient.invalidateQueries({ queryKey: ['gameMembers', id] });